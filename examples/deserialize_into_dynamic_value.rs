@@ -9,6 +9,29 @@ struct TestStruct {
     data: YamlNodeWrapper,
 }
 
+#[derive(Deserialize, Debug, PartialEq)]
+struct Bar {
+    name: String,
+    enabled: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Data {
+    Foo(Vec<i32>),
+    Bar(Bar),
+}
+
+/// Decodes the data into the type the kind names
+fn decode(document: &str) -> Data {
+    let result: TestStruct = from_str(document).unwrap();
+
+    match result.kind.as_str() {
+        "Foo" => Data::Foo(result.data.deserialize_into().unwrap()),
+        "Bar" => Data::Bar(result.data.deserialize_into().unwrap()),
+        kind => panic!("Unknown kind {kind}"),
+    }
+}
+
 fn main() {
     let result: TestStruct = from_str("kind: Foo\ndata: [1,2,3]\n").unwrap();
 
@@ -20,4 +43,7 @@ fn main() {
             ),
         ),
     }, result);
+
+    assert_eq!(Data::Foo(vec![1, 2, 3]), decode("kind: Foo\ndata: [1,2,3]\n"));
+    assert_eq!(Data::Bar(Bar { name: "web".to_owned(), enabled: true }), decode("kind: Bar\ndata:\n  name: web\n  enabled: true\n"));
 }
@@ -19,17 +19,17 @@ fn main() {
     }
 
     let result = to_string(TestEnum::VariantA).unwrap();
-    assert_eq!("VariantA: ~", result);
+    assert_eq!("VariantA\n", result);
 
     let result = to_string(TestEnum::VariantB()).unwrap();
-    assert_eq!("VariantB: []", result);
+    assert_eq!("VariantB: []\n", result);
 
     let result = to_string(TestEnum::VariantC(123, 45.0)).unwrap();
-    assert_eq!("VariantC:\n  - \n   123\n  - \n   45\n  ", result);
+    assert_eq!("VariantC:\n  - 123\n  - 45.0\n", result);
 
     let result = to_string(TestEnum::VariantD(Point { x: 1, y: 2, z: 3 })).unwrap();
-    assert_eq!("VariantD:\n  'x':\n   1\n  'y':\n   2\n  'z':\n   3\n  ", result);
+    assert_eq!("VariantD:\n  x: 1\n  y: 2\n  z: 3\n", result);
 
     let result = to_string(TestEnum::VariantE{ a: true, b: 3 }).unwrap();
-    assert_eq!("VariantE:\n  'a':\n   true\n  'b':\n   3\n  ", result);
+    assert_eq!("VariantE:\n  a: true\n  b: 3\n", result);
 }
@@ -16,5 +16,5 @@ fn main() {
     };
 
     let serialized = to_string(value).unwrap();
-    assert_eq!("'x':\n  -41\n'y':\n  'Hello world'\n'z':\n  - \n   1\n  - \n   2\n  - \n   3\n  \n", serialized);
+    assert_eq!("x: -41\ny: Hello world\nz:\n  - 1\n  - 2\n  - 3\n", serialized);
 }
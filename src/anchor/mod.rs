@@ -0,0 +1,163 @@
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeTupleStruct;
+
+/// Name of the tuple struct which asks [`YamlSerializer`](crate::ser::YamlSerializer) to write its second field
+/// with the anchor named by the first one
+pub(crate) const ANCHOR_TOKEN: &str = "$serde_yaml2::private::Anchor";
+
+/// Name of the newtype struct which asks [`YamlSerializer`](crate::ser::YamlSerializer) for an alias to the anchor
+/// it names
+pub(crate) const ALIAS_TOKEN: &str = "$serde_yaml2::private::Alias";
+
+/// A node written with an anchor, e.g. `&limits`, or an alias to it, e.g. `*limits`, so a subtree repeated
+/// across the document is written once.
+///
+/// An alias must follow the anchor it refers to, otherwise the serialization fails. With
+/// [`sort_keys`](crate::SerializerOptions::sort_keys) entries are written in another order than they are
+/// serialized in, so an alias may come before its anchor. Other serializers write a definition as a tuple of the
+/// name and the value, and a reference as the name alone.
+#[derive(Debug)]
+pub struct Anchored<'a, T: ?Sized> {
+    name: &'a str,
+    value: Option<&'a T>,
+}
+
+impl<'a, T: ?Sized> Anchored<'a, T> {
+    /// The value with an anchor of the name, e.g. `&limits {cpu: 1}`
+    pub fn define(name: &'a str, value: &'a T) -> Self {
+        Anchored { name, value: Some(value) }
+    }
+
+    /// An alias to the anchor of the name, e.g. `*limits`
+    pub fn reference(name: &'a str) -> Self {
+        Anchored { name, value: None }
+    }
+}
+
+impl<T: Serialize + ?Sized> Serialize for Anchored<'_, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let Some(value) = self.value else {
+            return serializer.serialize_newtype_struct(ALIAS_TOKEN, self.name);
+        };
+
+        let mut state = serializer.serialize_tuple_struct(ANCHOR_TOKEN, 2)?;
+        state.serialize_field(self.name)?;
+        state.serialize_field(value)?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde::{Deserialize, Serialize};
+    use crate::{from_str, to_string, to_string_with_options, Anchored, SerializerOptions, Style};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+    struct Limits {
+        cpu: u32,
+        memory: String,
+    }
+
+    #[derive(Serialize)]
+    struct Service<'a> {
+        image: &'a str,
+        limits: Anchored<'a, Limits>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct OwnedService {
+        image: String,
+        limits: Limits,
+    }
+
+    #[test]
+    fn should_work() {
+        let limits = Limits { cpu: 2, memory: String::from("1Gi") };
+        let services = BTreeMap::from([
+            ("api", Service { image: "api:1", limits: Anchored::define("limits", &limits) }),
+            ("web", Service { image: "web:1", limits: Anchored::reference("limits") }),
+            ("worker", Service { image: "worker:1", limits: Anchored::reference("limits") }),
+        ]);
+
+        let output = to_string(&services).unwrap();
+        assert_eq!("api:
+  image: api:1
+  limits: &limits
+    cpu: 2
+    memory: 1Gi
+web:
+  image: web:1
+  limits: *limits
+worker:
+  image: worker:1
+  limits: *limits
+", output);
+
+        let parsed: BTreeMap<String, OwnedService> = from_str(&output).unwrap();
+        assert_eq!(3, parsed.len());
+        assert!(parsed.values().all(|service| service.limits == limits), "{parsed:?}");
+        assert_eq!("web:1", parsed["web"].image);
+
+        let output = to_string_with_options(&services, SerializerOptions::new().style(Style::Flow)).unwrap();
+        assert_eq!("{api: {image: api:1, limits: &limits {cpu: 2, memory: 1Gi}}, web: {image: web:1, limits: *limits}, worker: {image: worker:1, limits: *limits}}\n", output);
+        assert_eq!(parsed, from_str::<BTreeMap<String, OwnedService>>(&output).unwrap());
+    }
+
+    #[test]
+    fn anchors_any_node() {
+        let items = vec![1, 2];
+        let empty = BTreeMap::<String, i32>::new();
+        let value = (
+            Anchored::define("items", &items),
+            Anchored::<Vec<i32>>::reference("items"),
+            Anchored::define("name", "web"),
+            Anchored::<str>::reference("name"),
+            Anchored::define("empty", &empty),
+        );
+
+        let output = to_string(&value).unwrap();
+        assert_eq!("- &items\n  - 1\n  - 2\n- *items\n- &name web\n- *name\n- &empty {}\n", output);
+        let parsed: (Vec<i32>, Vec<i32>, String, String, BTreeMap<String, i32>) = from_str(&output).unwrap();
+        assert_eq!((items.clone(), items.clone(), String::from("web"), String::from("web"), empty), parsed);
+
+        // the anchor of a mapping at the root goes on its own line, it would belong to the first key otherwise
+        let limits = Limits { cpu: 1, memory: String::from("512Mi") };
+        let output = to_string(Anchored::define("root", &limits)).unwrap();
+        assert_eq!("&root\ncpu: 1\nmemory: 512Mi\n", output);
+        assert_eq!(limits, from_str::<Limits>(&output).unwrap());
+
+        // an alias as a key is followed by a space, `:` would be part of its name otherwise
+        struct AliasKey {}
+
+        impl Serialize for AliasKey {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: serde::Serializer {
+                serializer.collect_map([(Anchored::<str>::reference("key"), 1)])
+            }
+        }
+
+        let value = (Anchored::define("key", "a"), AliasKey {});
+        let output = to_string(&value).unwrap();
+        assert_eq!("- &key a\n-\n  *key : 1\n", output);
+        assert_eq!((String::from("a"), BTreeMap::from([(String::from("a"), 1)])), from_str(&output).unwrap());
+    }
+
+    #[test]
+    fn rejects_undefined_and_invalid_names() {
+        let error = to_string(Anchored::<i32>::reference("missing")).unwrap_err();
+        assert_eq!("Alias to undefined anchor missing", error.to_string());
+
+        // an alias inside its own anchored node refers to an anchor which isn't defined yet
+        let inner = vec![Anchored::<()>::reference("outer")];
+        let error = to_string(Anchored::define("outer", &inner)).unwrap_err();
+        assert_eq!("Alias to undefined anchor outer", error.to_string());
+
+        for name in ["", "a b", "a,b", "[a]", "{a}", "a\tb"] {
+            let error = to_string(Anchored::define(name, &1)).unwrap_err();
+            assert_eq!(format!("Invalid anchor name {name:?}"), error.to_string());
+        }
+
+        let error = to_string(Anchored::define("a", &Anchored::define("b", &1))).unwrap_err();
+        assert_eq!("Node can have a single anchor, b follows a", error.to_string());
+    }
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::ser::SerializeTupleStruct;
+
+/// Name of the tuple struct which asks [`YamlSerializer`](crate::ser::YamlSerializer) to write its first field as
+/// a comment before the second one
+pub(crate) const COMMENT_TOKEN: &str = "$serde_yaml2::private::Comment";
+
+/// A value written after a comment, e.g. `# managed by deployctl, do not edit`. Each line of the comment becomes a
+/// comment line of its own.
+///
+/// The comment is written on the lines before the mapping entry or sequence element the value belongs to, or
+/// before the document if the value is its root. Anywhere else, e.g. inside a flow collection, a comment would end
+/// the line the node is written on, so it is left out. Other serializers write the comment and the value as a tuple.
+/// Deserializing reads the value alone, with an empty comment.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Commented<T> {
+    pub comment: String,
+    pub value: T,
+}
+
+impl<T> Commented<T> {
+    pub fn new(comment: impl Into<String>, value: T) -> Self {
+        Commented { comment: comment.into(), value }
+    }
+}
+
+impl<T: Serialize> Serialize for Commented<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let mut state = serializer.serialize_tuple_struct(COMMENT_TOKEN, 2)?;
+        state.serialize_field(&self.comment)?;
+        state.serialize_field(&self.value)?;
+        state.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Commented<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        T::deserialize(deserializer).map(|value| Commented { comment: String::new(), value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde::{Deserialize, Serialize};
+    use crate::{from_str, to_string, to_string_with_options, Commented, SerializerOptions, Style};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Limits {
+        cpu: u32,
+        memory: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Service {
+        name: String,
+        replicas: Commented<u32>,
+        limits: Commented<Limits>,
+        ports: Vec<Commented<u16>>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct PlainService {
+        name: String,
+        replicas: u32,
+        limits: Limits,
+        ports: Vec<u16>,
+    }
+
+    fn service() -> Service {
+        Service {
+            name: String::from("web"),
+            replicas: Commented::new("scaled by the autoscaler", 3),
+            limits: Commented::new("per replica\n\nraise together with replicas", Limits { cpu: 2, memory: String::from("1Gi") }),
+            ports: vec![Commented::new("http", 80), Commented::new("", 443)],
+        }
+    }
+
+    #[test]
+    fn should_work() {
+        let options = SerializerOptions::new().header_comment("managed by deployctl, do not edit");
+        let output = to_string_with_options(service(), options).unwrap();
+        assert_eq!("# managed by deployctl, do not edit
+name: web
+# scaled by the autoscaler
+replicas: 3
+# per replica
+#
+# raise together with replicas
+limits:
+  cpu: 2
+  memory: 1Gi
+ports:
+  # http
+  - 80
+  - 443
+", output);
+
+        let parsed: PlainService = from_str(&output).unwrap();
+        assert_eq!(PlainService { name: String::from("web"), replicas: 3, limits: Limits { cpu: 2, memory: String::from("1Gi") }, ports: vec![80, 443] }, parsed);
+
+        // the comments are left out when reading the wrapper back
+        let parsed: Service = from_str(&output).unwrap();
+        assert_eq!(3, parsed.replicas.value);
+        assert_eq!("", parsed.replicas.comment);
+    }
+
+    #[test]
+    fn comments_keep_structure() {
+        // nested entries and the root
+        let value = vec![BTreeMap::from([("a", Commented::new("first", 1)), ("b", Commented::new("second", 2))])];
+        assert_eq!("-\n  # first\n  a: 1\n  # second\n  b: 2\n", to_string(&value).unwrap());
+        assert_eq!("# root\n- 1\n", to_string(Commented::new("root", vec![1])).unwrap());
+
+        // sorted entries keep their comment
+        let options = SerializerOptions::new().sort_keys(true);
+        let value = BTreeMap::from([("b", Commented::new("second", 2)), ("a", Commented::new("first", 1))]);
+        assert_eq!("# first\na: 1\n# second\nb: 2\n", to_string_with_options(&value, options).unwrap());
+
+        // there is no line of its own for a comment inside a flow collection
+        let options = SerializerOptions::new().style(Style::Flow);
+        let output = to_string_with_options(service(), options).unwrap();
+        assert_eq!("{name: web, replicas: 3, limits: {cpu: 2, memory: 1Gi}, ports: [80, 443]}\n", output);
+
+        // a nested block sequence starts on the line after the dash, so its elements can have comments
+        let output = to_string(vec![vec![Commented::new("inner", 1)]]).unwrap();
+        assert_eq!("-\n  # inner\n  - 1\n", output);
+        assert_eq!(vec![vec![1]], from_str::<Vec<Vec<i32>>>(&output).unwrap());
+
+        // a short sequence written in flow style leaves its comments in block style
+        let options = SerializerOptions::new().compact_leaf_seqs(40);
+        let value = BTreeMap::from([("ports", vec![Commented::new("http", 80)]), ("other", vec![])]);
+        assert_eq!("other: []\nports:\n  # http\n  - 80\n", to_string_with_options(&value, options).unwrap());
+    }
+}
@@ -0,0 +1,152 @@
+//! Detection of the character encoding of a byte stream, see
+//! <https://yaml.org/spec/1.2.2/#52-character-encodings>
+
+use std::borrow::Cow;
+use serde::de::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+impl Encoding {
+    /// Detects the encoding by the byte order mark or the pattern of null bytes of the first character, which
+    /// is ASCII in a document without byte order mark. Returns the length of the byte order mark as well.
+    fn detect(bytes: &[u8]) -> (Encoding, usize) {
+        match bytes {
+            [0x00, 0x00, 0xFE, 0xFF, ..] => (Encoding::Utf32Be, 4),
+            [0x00, 0x00, 0x00, _, ..] => (Encoding::Utf32Be, 0),
+            [0xFF, 0xFE, 0x00, 0x00, ..] => (Encoding::Utf32Le, 4),
+            [_, 0x00, 0x00, 0x00, ..] => (Encoding::Utf32Le, 0),
+            [0xFE, 0xFF, ..] => (Encoding::Utf16Be, 2),
+            [0x00, _, ..] => (Encoding::Utf16Be, 0),
+            [0xFF, 0xFE, ..] => (Encoding::Utf16Le, 2),
+            [_, 0x00, ..] => (Encoding::Utf16Le, 0),
+            [0xEF, 0xBB, 0xBF, ..] => (Encoding::Utf8, 3),
+            _ => (Encoding::Utf8, 0),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Utf32Le => "UTF-32LE",
+            Encoding::Utf32Be => "UTF-32BE",
+        }
+    }
+}
+
+/// Decodes UTF-8, UTF-16 or UTF-32 input without its byte order mark. UTF-8 input is borrowed.
+pub(super) fn decode(bytes: &[u8]) -> Result<Cow<'_, str>, serde::de::value::Error> {
+    let (encoding, bom) = Encoding::detect(bytes);
+    let bytes = &bytes[bom..];
+
+    let invalid = |offset: usize| serde::de::value::Error::custom(format!("Invalid {} input at byte {}", encoding.name(), bom + offset));
+
+    match encoding {
+        Encoding::Utf8 => std::str::from_utf8(bytes).map(Cow::Borrowed).map_err(|e| invalid(e.valid_up_to())),
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let units = bytes.chunks(2).map(|unit| match (unit, encoding) {
+                ([low, high], Encoding::Utf16Le) => Some(u16::from_le_bytes([*low, *high])),
+                ([high, low], _) => Some(u16::from_be_bytes([*high, *low])),
+                _ => None,
+            });
+
+            let mut text = String::with_capacity(bytes.len() / 2);
+            let mut offset = 0;
+
+            for c in char::decode_utf16(units.map(|unit| unit.unwrap_or(0xDC00))) {
+                match c {
+                    // an incomplete unit is reported as unpaired surrogate
+                    Ok(c) if bytes.len() - offset >= 2 => {
+                        text.push(c);
+                        offset += 2 * c.len_utf16();
+                    },
+                    _ => return Err(invalid(offset)),
+                }
+            }
+
+            Ok(Cow::Owned(text))
+        },
+        Encoding::Utf32Le | Encoding::Utf32Be => {
+            let mut text = String::with_capacity(bytes.len() / 4);
+
+            for (index, unit) in bytes.chunks(4).enumerate() {
+                let value = match (<[u8; 4]>::try_from(unit), encoding) {
+                    (Ok(unit), Encoding::Utf32Le) => u32::from_le_bytes(unit),
+                    (Ok(unit), _) => u32::from_be_bytes(unit),
+                    (Err(_), _) => return Err(invalid(index * 4)),
+                };
+
+                text.push(char::from_u32(value).ok_or_else(|| invalid(index * 4))?);
+            }
+
+            Ok(Cow::Owned(text))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use crate::de::{from_reader, from_slice};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        port: u16,
+    }
+
+    fn utf16le(text: &str, bom: bool) -> Vec<u8> {
+        let bom = if bom { vec![0xFF, 0xFE] } else { vec![] };
+        bom.into_iter().chain(text.encode_utf16().flat_map(u16::to_le_bytes)).collect()
+    }
+
+    #[test]
+    fn should_work() {
+        let expected = Config { name: String::from("Grüße 😀"), port: 8080 };
+        let text = "name: Grüße 😀\nport: 8080\n";
+
+        assert_eq!(expected, from_slice(text.as_bytes()).unwrap());
+        assert_eq!(expected, from_slice(&[&[0xEF, 0xBB, 0xBF], text.as_bytes()].concat()).unwrap());
+        assert_eq!(expected, from_slice(&utf16le(text, true)).unwrap());
+        assert_eq!(expected, from_slice(&utf16le(text, false)).unwrap());
+        assert_eq!(expected, from_reader(utf16le(text, true).as_slice()).unwrap());
+
+        let utf16be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(expected, from_slice(&utf16be).unwrap());
+
+        let utf32le: Vec<u8> = [0xFF, 0xFE, 0x00, 0x00].into_iter().chain(text.chars().flat_map(|c| (c as u32).to_le_bytes())).collect();
+        assert_eq!(expected, from_slice(&utf32le).unwrap());
+
+        let utf32be: Vec<u8> = text.chars().flat_map(|c| (c as u32).to_be_bytes()).collect();
+        assert_eq!(expected, from_slice(&utf32be).unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        // positions count characters of the decoded text
+        let error = from_slice::<Config>(&utf16le("name: Grüße 😀\nport: x\n", true)).unwrap_err().to_string();
+        assert_eq!("Error while parsing scalar x into u16 at position Line: 2, Column: 7, Index: 20. Not a number", error);
+
+        let error = from_slice::<Config>(&[b'a', b':', b' ', 0xC3, 0x28]).unwrap_err().to_string();
+        assert_eq!("Invalid UTF-8 input at byte 3", error);
+
+        let mut odd = utf16le("name: a", true);
+        odd.push(b'x');
+        assert_eq!("Invalid UTF-16LE input at byte 16", from_slice::<Config>(&odd).unwrap_err().to_string());
+
+        // unpaired surrogate
+        let unpaired = [0xFF, 0xFE, b'a', 0x00, 0x00, 0xD8, b'b', 0x00];
+        assert_eq!("Invalid UTF-16LE input at byte 4", from_slice::<Config>(&unpaired).unwrap_err().to_string());
+
+        let invalid = [0x00, 0x00, 0xFE, 0xFF, 0x00, 0x11, 0x00, 0x00];
+        assert_eq!("Invalid UTF-32BE input at byte 4", from_slice::<Config>(&invalid).unwrap_err().to_string());
+    }
+}
@@ -0,0 +1,419 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use yaml_rust2::Event;
+use yaml_rust2::parser::{Parser, Tag};
+use yaml_rust2::scanner::{Marker, ScanError, TScalarStyle};
+
+#[derive(Debug, Clone)]
+pub(crate) enum ReadError {
+    Scan(ScanError),
+    /// Alias to an anchor which is not defined yet, e.g. an alias inside its own anchored node
+    UnknownAnchor(Marker),
+    /// More events than the limit were replayed from aliases
+    AliasBudget(usize, Marker),
+    /// The document has more nodes than the limit
+    NodeBudget(usize, Marker),
+    /// Anchor, alias or tag found in restricted mode, named by the first field
+    Restricted(String, Marker),
+    /// Included file which can't be read, parsed or would include itself, described by the first field
+    Include(String, Marker),
+}
+
+impl From<ScanError> for ReadError {
+    fn from(error: ScanError) -> Self {
+        ReadError::Scan(error)
+    }
+}
+
+/// Events of an anchored node which is not complete yet
+struct Recording {
+    anchor_id: usize,
+    events: Vec<Event>,
+    depth: usize,
+}
+
+/// Events of an anchored node replayed for an alias, or of an included file
+struct Replay {
+    events: Rc<Vec<Event>>,
+    position: usize,
+    marker: Marker,
+    /// Replayed events count towards the alias limit
+    alias: bool,
+}
+
+/// Function reading the file at a path
+pub(crate) type IncludeResolver = Arc<dyn Fn(&Path) -> io::Result<String> + Send + Sync>;
+
+/// Resolves `!include` scalars, see [`YamlLoader`](super::YamlLoader)
+#[derive(Clone)]
+pub(crate) struct Includes {
+    pub(crate) resolver: IncludeResolver,
+    /// Files including each other, the last one is being read
+    pub(crate) chain: Vec<PathBuf>,
+    /// Canonical paths of the files in `chain`, see [`canonical_path`]
+    pub(crate) canonical: Vec<PathBuf>,
+    pub(crate) max_depth: usize,
+}
+
+/// Path which is the same for every way of naming the file, e.g. `a.yaml`, `./a.yaml` and `b/../a.yaml`. The
+/// file may not be on the filesystem if a resolver reads it from elsewhere, then `.` and `..` are resolved
+/// without following symbolic links.
+pub(crate) fn canonical_path(path: &Path) -> PathBuf {
+    if let Ok(path) = std::fs::canonicalize(path) {
+        return path;
+    }
+
+    let absolute = std::env::current_dir().map(|directory| directory.join(path)).unwrap_or_else(|_| path.to_path_buf());
+    let mut result = PathBuf::new();
+
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                result.pop();
+            },
+            component => result.push(component),
+        }
+    }
+
+    result
+}
+
+/// Wraps the parser and replaces aliases with the events of their anchored nodes.
+///
+/// Recorded nodes keep nested aliases as they are, which are expanded only when replayed, so memory stays
+/// linear in the size of the input. Replayed events carry the position of the alias.
+pub(crate) struct EventReader<I> {
+    parser: Parser<I>,
+    peeked: Option<Result<(Event, Marker), ReadError>>,
+    /// Event after the peeked one, only read if the peeked one was read
+    peeked_second: Option<Result<(Event, Marker), ReadError>>,
+    anchors: HashMap<usize, Rc<Vec<Event>>>,
+    recordings: Vec<Recording>,
+    replays: Vec<Replay>,
+    max_alias_events: Option<usize>,
+    max_nodes: Option<usize>,
+    restricted: bool,
+    alias_events: usize,
+    nodes: usize,
+    failed: bool,
+    includes: Option<Includes>,
+    /// Last read event, only kept if `keep_last` is set
+    last: Option<(Event, Marker)>,
+    keep_last: bool,
+}
+
+impl<I: Iterator<Item = char>> EventReader<I> {
+    pub(crate) fn new(parser: Parser<I>, max_alias_events: Option<usize>, max_nodes: Option<usize>, restricted: bool) -> Self {
+        EventReader {
+            parser,
+            peeked: None,
+            peeked_second: None,
+            anchors: HashMap::new(),
+            recordings: Vec::new(),
+            replays: Vec::new(),
+            max_alias_events,
+            max_nodes,
+            restricted,
+            alias_events: 0,
+            nodes: 0,
+            failed: false,
+            includes: None,
+            last: None,
+            keep_last: false,
+        }
+    }
+
+    pub(crate) fn set_includes(&mut self, includes: Includes) {
+        self.includes = Some(includes);
+    }
+
+    /// Returns true if reading failed, after which the rest of the input can't be read
+    pub(crate) fn failed(&self) -> bool {
+        self.failed
+    }
+
+    pub(crate) fn peek(&mut self) -> Result<&(Event, Marker), ReadError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.fetch());
+        }
+
+        match self.peeked.as_ref() {
+            Some(Ok(next)) => Ok(next),
+            Some(Err(error)) => Err(error.clone()),
+            None => unreachable!(),
+        }
+    }
+
+    /// Removes the tag of the next event once it is interpreted, so the node reads as untagged afterwards
+    pub(crate) fn take_tag(&mut self) -> Option<Tag> {
+        self.peek().ok()?;
+
+        match self.peeked.as_mut() {
+            Some(Ok((Event::Scalar(_, _, _, tag) | Event::SequenceStart(_, tag) | Event::MappingStart(_, tag), _))) => tag.take(),
+            _ => None,
+        }
+    }
+
+    /// Returns the event after the next one without consuming either
+    pub(crate) fn peek_second(&mut self) -> Result<&(Event, Marker), ReadError> {
+        self.peek()?;
+
+        if self.peeked_second.is_none() {
+            self.peeked_second = Some(self.fetch());
+        }
+
+        match self.peeked_second.as_ref() {
+            Some(Ok(next)) => Ok(next),
+            Some(Err(error)) => Err(error.clone()),
+            None => unreachable!(),
+        }
+    }
+
+    pub(crate) fn next_token(&mut self) -> Result<(Event, Marker), ReadError> {
+        let next = match self.peeked.take() {
+            Some(next) => {
+                self.peeked = self.peeked_second.take();
+                next
+            },
+            None => self.fetch(),
+        };
+
+        if let (true, Ok(next)) = (self.keep_last, &next) {
+            self.last = Some(next.clone());
+        }
+
+        next
+    }
+
+    /// Keeps the last read event from now on, see [`last_event`](Self::last_event)
+    pub(crate) fn keep_last_event(&mut self) {
+        self.keep_last = true;
+    }
+
+    pub(crate) fn last_event(&self) -> Option<&(Event, Marker)> {
+        self.last.as_ref()
+    }
+
+    fn fetch(&mut self) -> Result<(Event, Marker), ReadError> {
+        let result = self.fetch_expanded();
+        self.failed |= result.is_err();
+        result
+    }
+
+    fn fetch_expanded(&mut self) -> Result<(Event, Marker), ReadError> {
+        loop {
+            if let Some(replay) = self.replays.last_mut() {
+                let Some(event) = replay.events.get(replay.position).cloned() else {
+                    self.replays.pop();
+                    continue;
+                };
+
+                replay.position += 1;
+                let marker = replay.marker;
+
+                if replay.alias {
+                    self.alias_events += 1;
+                    if let Some(limit) = self.max_alias_events {
+                        if self.alias_events > limit {
+                            return Err(ReadError::AliasBudget(limit, marker));
+                        }
+                    }
+                }
+
+                if let Event::Alias(anchor_id) = event {
+                    self.replay(anchor_id, marker)?;
+                    continue;
+                }
+
+                if let Some(events) = self.include(&event, marker)? {
+                    self.replays.push(Replay { events, position: 0, marker, alias: false });
+                    continue;
+                }
+
+                self.count_node(&event, marker)?;
+                return Ok((event, marker));
+            }
+
+            let (event, marker) = self.parser.next_token()?;
+
+            if self.restricted {
+                check_restricted(&event, marker)?;
+            }
+
+            if event == Event::DocumentStart {
+                // anchors are local to their document
+                self.anchors.clear();
+                self.alias_events = 0;
+                self.nodes = 0;
+            }
+
+            self.record(&event);
+
+            if let Event::Alias(anchor_id) = event {
+                self.replay(anchor_id, marker)?;
+                continue;
+            }
+
+            if let Some(events) = self.include(&event, marker)? {
+                self.replays.push(Replay { events, position: 0, marker, alias: false });
+                continue;
+            }
+
+            self.count_node(&event, marker)?;
+            return Ok((event, marker));
+        }
+    }
+
+    /// Adds the event to all anchored nodes it belongs to and stores the nodes it completes
+    fn record(&mut self, event: &Event) {
+        for recording in self.recordings.iter_mut() {
+            recording.events.push(event.clone());
+
+            match event {
+                Event::SequenceStart(..) | Event::MappingStart(..) => recording.depth += 1,
+                Event::SequenceEnd | Event::MappingEnd => recording.depth -= 1,
+                _ => {},
+            }
+        }
+
+        match event {
+            Event::Scalar(_, _, anchor_id, _) if *anchor_id > 0 => {
+                self.anchors.insert(*anchor_id, Rc::new(vec![event.clone()]));
+            },
+            Event::SequenceStart(anchor_id, _) | Event::MappingStart(anchor_id, _) if *anchor_id > 0 => {
+                self.recordings.push(Recording { anchor_id: *anchor_id, events: vec![event.clone()], depth: 1 });
+            },
+            _ => {},
+        }
+
+        // anchored nodes are nested, so the innermost ones are complete first
+        while matches!(self.recordings.last(), Some(recording) if recording.depth == 0) {
+            if let Some(recording) = self.recordings.pop() {
+                self.anchors.insert(recording.anchor_id, Rc::new(recording.events));
+            }
+        }
+    }
+
+    fn replay(&mut self, anchor_id: usize, marker: Marker) -> Result<(), ReadError> {
+        match self.anchors.get(&anchor_id) {
+            Some(events) => {
+                self.replays.push(Replay { events: events.clone(), position: 0, marker, alias: true });
+                Ok(())
+            },
+            None => Err(ReadError::UnknownAnchor(marker)),
+        }
+    }
+
+    /// Returns the events of the node of the file included by an `!include` scalar. The path is relative to
+    /// the including file.
+    fn include(&mut self, event: &Event, marker: Marker) -> Result<Option<Rc<Vec<Event>>>, ReadError> {
+        let (Some(includes), Event::Scalar(value, _, _, Some(Tag { handle, suffix }))) = (&self.includes, event) else {
+            return Ok(None);
+        };
+
+        if handle != "!" || suffix != "include" {
+            return Ok(None);
+        }
+
+        let path = match includes.chain.last().and_then(|including| including.parent()) {
+            Some(directory) => directory.join(value),
+            None => PathBuf::from(value),
+        };
+
+        let canonical = canonical_path(&path);
+
+        if includes.canonical.contains(&canonical) {
+            let chain = includes.chain.iter().chain([&path]).map(|path| path.display().to_string()).collect::<Vec<_>>();
+            return Err(ReadError::Include(format!("Include cycle {}", chain.join(" -> ")), marker));
+        }
+
+        if includes.chain.len() > includes.max_depth {
+            return Err(ReadError::Include(format!("Include depth limit of {} exceeded by {}", includes.max_depth, path.display()), marker));
+        }
+
+        let content = (includes.resolver)(&path)
+            .map_err(|e| ReadError::Include(format!("Failed to include {}: {e}", path.display()), marker))?;
+
+        let mut reader = EventReader::new(Parser::new_from_str(&content), self.max_alias_events, self.max_nodes, self.restricted);
+        let mut chain = includes.chain.clone();
+        chain.push(path.clone());
+        let mut canonical_chain = includes.canonical.clone();
+        canonical_chain.push(canonical);
+        reader.set_includes(Includes { chain, canonical: canonical_chain, ..includes.clone() });
+
+        let mut events = vec![];
+        let mut documents = 0;
+
+        loop {
+            match reader.next_token() {
+                Ok((Event::StreamEnd, ..)) => break,
+                Ok((Event::DocumentStart, ..)) if documents > 0 => {
+                    return Err(ReadError::Include(format!("Included file {} contains more than one document", path.display()), marker));
+                },
+                Ok((Event::DocumentStart, ..)) => {
+                    // aliases of all files share one budget, the reader resets its count at the start of a document
+                    reader.alias_events = self.alias_events;
+                    documents += 1;
+                },
+                Ok((Event::StreamStart | Event::DocumentEnd, ..)) => {},
+                Ok((event, ..)) => events.push(event),
+                // errors of nested includes already name their file
+                Err(error @ ReadError::Include(..)) => return Err(error),
+                Err(error) => {
+                    let message = format!("Error in included file {}: {}", path.display(), super::Errors::read_error(error));
+                    return Err(ReadError::Include(message, marker));
+                },
+            }
+        }
+
+        self.alias_events = reader.alias_events;
+
+        // an empty file is null
+        if events.is_empty() {
+            events.push(Event::Scalar(String::from("~"), TScalarStyle::Plain, 0, None));
+        }
+
+        Ok(Some(Rc::new(events)))
+    }
+
+    fn count_node(&mut self, event: &Event, marker: Marker) -> Result<(), ReadError> {
+        if !matches!(event, Event::Scalar(..) | Event::SequenceStart(..) | Event::MappingStart(..)) {
+            return Ok(());
+        }
+
+        self.nodes += 1;
+
+        match self.max_nodes {
+            Some(limit) if self.nodes > limit => Err(ReadError::NodeBudget(limit, marker)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Tags of the YAML type repository which the deserializer interprets, e.g. `!!str`
+const ALLOWED_TAGS: [&str; 8] = ["str", "int", "float", "bool", "null", "seq", "map", "set"];
+
+/// Rejects anchors, aliases and tags other than [`ALLOWED_TAGS`] before anything is recorded or replayed
+fn check_restricted(event: &Event, marker: Marker) -> Result<(), ReadError> {
+    let (anchor_id, tag) = match event {
+        Event::Alias(_) => return Err(ReadError::Restricted(String::from("Alias"), marker)),
+        Event::Scalar(_, _, anchor_id, tag) => (*anchor_id, tag),
+        Event::SequenceStart(anchor_id, tag) | Event::MappingStart(anchor_id, tag) => (*anchor_id, tag),
+        _ => return Ok(()),
+    };
+
+    if anchor_id > 0 {
+        return Err(ReadError::Restricted(String::from("Anchor"), marker));
+    }
+
+    match tag {
+        Some(Tag { handle, suffix }) if handle != "tag:yaml.org,2002:" || !ALLOWED_TAGS.contains(&suffix.as_str()) => {
+            Err(ReadError::Restricted(format!("Tag {handle}{suffix}"), marker))
+        },
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,132 @@
+use std::marker::PhantomData;
+use std::str::Chars;
+use serde::Deserialize;
+use yaml_rust2::Event;
+use yaml_rust2::scanner::Marker;
+use crate::de::{with_position, Errors, YamlDeserializer};
+
+/// Deserializes elements of a top-level sequence one by one, see [`seq_iter`](crate::de::seq_iter)
+pub struct SeqIter<'de, T, I = Chars<'de>> {
+    deserializer: YamlDeserializer<'de, I>,
+    index: usize,
+    finished: bool,
+    _marker: PhantomData<T>,
+}
+
+impl<'de, T: Deserialize<'de>, I: Iterator<Item = char>> SeqIter<'de, T, I> {
+    pub(crate) fn new(mut deserializer: YamlDeserializer<'de, I>) -> Result<Self, serde::de::value::Error> {
+        // empty document is an empty sequence
+        let finished = deserializer.at_document_end();
+
+        if !finished {
+            match deserializer.parser.next_token() {
+                Ok((Event::SequenceStart(..), ..)) => {},
+                Ok((event, marker)) => return Err(Errors::unexpected_event_error("SequenceStart", event, marker).into()),
+                Err(scan_error) => return Err(Errors::read_error(scan_error).into()),
+            }
+        }
+
+        Ok(SeqIter {
+            deserializer,
+            index: 0,
+            finished,
+            _marker: PhantomData,
+        })
+    }
+
+    fn finish(&mut self) -> Result<(), serde::de::value::Error> {
+        self.finished = true;
+        self.deserializer.parser.next_token().map_err(|e| Errors::read_error(e).into())?;
+        self.deserializer.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>, I: Iterator<Item = char>> Iterator for SeqIter<'de, T, I> {
+    type Item = Result<T, serde::de::value::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let marker = match self.deserializer.parser.peek() {
+            Ok((Event::SequenceEnd, ..)) => return self.finish().err().map(Err),
+            Ok((_, marker)) => *marker,
+            Err(scan_error) => {
+                self.finished = true;
+                return Some(Err(Errors::read_error(scan_error).into()));
+            },
+        };
+
+        let result = T::deserialize(&mut self.deserializer).map_err(|e| Errors::sequence_element_error(self.index, marker, e.to_string()).into());
+
+        self.index += 1;
+        self.finished = result.is_err();
+
+        Some(result)
+    }
+}
+
+/// Deserializes documents of a multi-document stream one by one, see [`YamlDeserializer::into_iter`]
+pub struct StreamDeserializer<'de, T, I = Chars<'de>> {
+    deserializer: YamlDeserializer<'de, I>,
+    first: bool,
+    finished: bool,
+    end_marker: Option<Marker>,
+    _marker: PhantomData<T>,
+}
+
+impl<'de, T: Deserialize<'de>, I: Iterator<Item = char>> StreamDeserializer<'de, T, I> {
+    pub(crate) fn new(deserializer: YamlDeserializer<'de, I>) -> Self {
+        StreamDeserializer {
+            deserializer,
+            first: true,
+            finished: false,
+            end_marker: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Position where the last successfully deserialized document ended
+    pub fn end_marker(&self) -> Option<Marker> {
+        self.end_marker
+    }
+
+    fn next_document(&mut self) -> Result<bool, serde::de::value::Error> {
+        if self.first {
+            self.first = false;
+            return Ok(!matches!(self.deserializer.parser.peek(), Ok((Event::StreamEnd, ..))));
+        }
+
+        Ok(self.deserializer.next_document()?.is_some())
+    }
+}
+
+impl<'de, T: Deserialize<'de>, I: Iterator<Item = char>> Iterator for StreamDeserializer<'de, T, I> {
+    type Item = Result<T, serde::de::value::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let result = match self.next_document() {
+            Ok(true) => {
+                let marker = self.deserializer.peek_marker();
+                T::deserialize(&mut self.deserializer).map_err(|e| with_position(e, marker))
+            },
+            Ok(false) => {
+                self.finished = true;
+                return None;
+            },
+            Err(error) => Err(error),
+        };
+
+        match self.deserializer.parser.peek() {
+            Ok((_, marker)) if result.is_ok() => self.end_marker = Some(*marker),
+            _ => self.finished = true,
+        }
+
+        Some(result)
+    }
+}
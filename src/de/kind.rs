@@ -0,0 +1,39 @@
+use yaml_rust2::parser::Tag;
+use yaml_rust2::scanner::TScalarStyle;
+use crate::ser::ScalarStyle;
+
+/// Kind of the next node, returned by [`YamlDeserializer::peek_kind`](super::YamlDeserializer::peek_kind).
+///
+/// Aliases are expanded while reading, so the kind of an alias is the kind of its anchored node.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NodeKind {
+    /// Scalar with its style and tag
+    Scalar { style: ScalarStyle, tag: Option<NodeTag> },
+    Sequence { tag: Option<NodeTag> },
+    Mapping { tag: Option<NodeTag> },
+    /// No node is left in the document, e.g. the document is empty
+    DocumentEnd,
+}
+
+/// Tag of a node. The handle is resolved, e.g. `!!str` has the handle `tag:yaml.org,2002:` and the suffix `str`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct NodeTag {
+    pub handle: String,
+    pub suffix: String,
+}
+
+impl From<&Tag> for NodeTag {
+    fn from(tag: &Tag) -> Self {
+        NodeTag { handle: tag.handle.clone(), suffix: tag.suffix.clone() }
+    }
+}
+
+pub(super) fn scalar_style(style: TScalarStyle) -> ScalarStyle {
+    match style {
+        TScalarStyle::Plain => ScalarStyle::Plain,
+        TScalarStyle::SingleQuoted => ScalarStyle::SingleQuoted,
+        TScalarStyle::DoubleQuoted => ScalarStyle::DoubleQuoted,
+        TScalarStyle::Literal => ScalarStyle::Literal,
+        TScalarStyle::Folded => ScalarStyle::Folded,
+    }
+}
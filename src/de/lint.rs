@@ -0,0 +1,244 @@
+use std::collections::HashSet;
+use std::marker::PhantomData;
+use serde::de::{DeserializeSeed, Error, Visitor};
+use serde::{Deserialize, Deserializer};
+use yaml_rust2::Event;
+use yaml_rust2::scanner::Marker;
+use super::positions::Place;
+use super::{with_position, EmptyAccess, YamlDeserializer};
+
+/// Deserializes the document and collects all errors instead of stopping at the first one, e.g. to show every
+/// problem of a user-edited file at once.
+///
+/// A value which can't be deserialized, like a scalar of the wrong type or a number out of range, is replaced
+/// by a default (zero, false, an empty string, null or an empty collection) and an entry with an unknown key
+/// is skipped. Scan errors and values whose default can't be deserialized either stop at the error.
+///
+/// Values of the wrong type and unknown keys of a struct already known to deny them are recovered from while
+/// reading, so the document is usually read once. Errors raised by the deserialized types themselves, like a
+/// missing field, need the document to be read again with the failed node replaced.
+pub fn validate<'de, T: Deserialize<'de>>(data: &'de str) -> Result<T, Vec<serde::de::value::Error>> {
+    let mut lint = Lint::default();
+    // errors which needed another run, by the character index of their node
+    let mut failures = vec![];
+
+    let (value, mut errors) = loop {
+        let deserializer = &mut YamlDeserializer::from_str(data).map_err(|e| vec![e])?;
+        deserializer.lint = Some(lint);
+
+        let result = deserializer.deserialize_node(PhantomData::<T>, Place::Document).and_then(|value| deserializer.end().map(|_| value));
+        lint = deserializer.lint.take().unwrap_or_default();
+
+        let error = match result {
+            Ok(value) => break (Some(value), failures),
+            Err(error) => error,
+        };
+
+        let index = match lint.failure.take() {
+            Some(Failure::Key(index)) => {
+                lint.skipped_keys.insert(index);
+                index
+            },
+            Some(Failure::Value(index)) => {
+                lint.defaults.insert(index);
+                index
+            },
+            // the error is caused by the default, the value itself is already reported
+            Some(Failure::Default) => break (None, failures),
+            Some(Failure::Fatal) | None => {
+                failures.push((usize::MAX, error));
+                break (None, failures);
+            },
+        };
+
+        failures.push((index, error));
+    };
+
+    // errors recovered from are found again by later runs
+    errors.append(&mut lint.errors);
+    errors.sort_by_key(|(index, _)| *index);
+    let mut reported = HashSet::new();
+    errors.retain(|(index, error)| reported.insert((*index, error.to_string())));
+
+    match value {
+        Some(value) if errors.is_empty() => Ok(value),
+        _ => Err(errors.into_iter().map(|(_, error)| error).collect()),
+    }
+}
+
+/// Nodes which failed in previous runs of [`validate`], identified by the character index of their position
+#[derive(Default)]
+pub(super) struct Lint {
+    /// Values replaced by a default
+    defaults: HashSet<usize>,
+    /// Keys whose entry is skipped
+    skipped_keys: HashSet<usize>,
+    /// Fields of structs which failed on an unknown key, further unknown keys of them are skipped
+    denying: HashSet<&'static [&'static str]>,
+    /// Errors recovered from while reading, with the character index of their node
+    errors: Vec<(usize, serde::de::value::Error)>,
+    /// Innermost node which failed in this run
+    failure: Option<Failure>,
+}
+
+enum Failure {
+    Key(usize),
+    Value(usize),
+    /// Default which replaced a value failed
+    Default,
+    /// Reading the input failed, which can't be recovered from
+    Fatal,
+}
+
+impl<'de, I: Iterator<Item = char>> YamlDeserializer<'de, I> {
+    pub(super) fn deserialize_linted<T: DeserializeSeed<'de>>(&mut self, seed: T, marker: Option<Marker>, key: bool) -> Result<T::Value, serde::de::value::Error> {
+        let index = marker.map(|marker| marker.index());
+        let replaced = matches!((&self.lint, index), (Some(lint), Some(index)) if lint.defaults.contains(&index));
+
+        let result = match replaced {
+            true => self.skip_node().and_then(|_| seed.deserialize(DefaultDeserializer {})),
+            false => seed.deserialize(&mut *self),
+        };
+
+        if result.is_err() {
+            let failed = self.parser.failed();
+
+            if let Some(lint) = self.lint.as_mut().filter(|lint| lint.failure.is_none()) {
+                lint.failure = Some(match (index, key) {
+                    _ if failed => Failure::Fatal,
+                    _ if replaced => Failure::Default,
+                    (Some(index), true) => Failure::Key(index),
+                    (Some(index), false) => Failure::Value(index),
+                    (None, _) => Failure::Fatal,
+                });
+            }
+        }
+
+        result
+    }
+
+    /// Skips mapping entries whose key failed in a previous run, and the unknown keys of a struct known to deny
+    /// them, whose errors are recorded instead
+    pub(super) fn skip_linted_entries(&mut self, fields: &'static [&'static str]) -> Result<(), serde::de::value::Error> {
+        loop {
+            let Some(lint) = &self.lint else {
+                return Ok(());
+            };
+
+            let unknown = match self.parser.peek() {
+                Ok((Event::MappingEnd, ..)) => return Ok(()),
+                Ok((_, marker)) if lint.skipped_keys.contains(&marker.index()) => None,
+                Ok((Event::Scalar(key, ..), marker)) if lint.denying.contains(fields) && !fields.contains(&key.as_str()) => {
+                    Some((marker.index(), with_position(Error::unknown_field(key, fields), Some(*marker))))
+                },
+                _ => return Ok(()),
+            };
+
+            if let (Some(error), Some(lint)) = (unknown, self.lint.as_mut()) {
+                lint.errors.push(error);
+            }
+
+            // key and value
+            self.skip_node()?;
+            self.skip_node()?;
+        }
+    }
+
+    /// Remembers that the struct denies unknown keys once one of them failed with the error of serde, so the
+    /// following ones are skipped while reading
+    pub(super) fn deny_unknown_fields(&mut self, key: &str, fields: &'static [&'static str], error: &serde::de::value::Error) {
+        let denied = !fields.is_empty() && !fields.contains(&key) && error.to_string().starts_with(&serde::de::value::Error::unknown_field(key, fields).to_string());
+
+        if let (true, Some(lint)) = (denied, self.lint.as_mut()) {
+            lint.denying.insert(fields);
+        }
+    }
+
+    /// Records the error of a value of the wrong type in lint mode and deserializes its default instead, skipping
+    /// the rest of the node which starts with the event. Outside of lint mode, or if the default fails as well, the
+    /// error is returned and the node is replaced in the next run, see [`validate`].
+    pub(super) fn recover<T>(&mut self, error: serde::de::value::Error, marker: Marker, event: Option<&Event>, default: impl FnOnce() -> Result<T, serde::de::value::Error>) -> Result<T, serde::de::value::Error> {
+        if self.lint.is_none() {
+            return Err(error);
+        }
+
+        if let Some(event) = event {
+            self.skip_replayed_node(event)?;
+        }
+
+        match (default(), self.lint.as_mut()) {
+            (Ok(value), Some(lint)) => {
+                lint.errors.push((marker.index(), with_position(error, Some(marker))));
+                Ok(value)
+            },
+            _ => Err(error),
+        }
+    }
+}
+
+/// Deserializer of the default value of any type, used in place of a value which failed
+struct DefaultDeserializer {}
+
+macro_rules! deserialize_default {
+    ($($method:ident => $visit:ident($($value:expr)?)),* $(,)?) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for DefaultDeserializer {
+    type Error = serde::de::value::Error;
+
+    deserialize_default! {
+        deserialize_any => visit_unit(),
+        deserialize_bool => visit_bool(false),
+        deserialize_i8 => visit_i8(0),
+        deserialize_i16 => visit_i16(0),
+        deserialize_i32 => visit_i32(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_u8 => visit_u8(0),
+        deserialize_u16 => visit_u16(0),
+        deserialize_u32 => visit_u32(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_f32 => visit_f32(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_char => visit_char('\0'),
+        deserialize_str => visit_str(""),
+        deserialize_string => visit_string(String::new()),
+        deserialize_bytes => visit_bytes(&[]),
+        deserialize_byte_buf => visit_byte_buf(Vec::new()),
+        deserialize_option => visit_none(),
+        deserialize_unit => visit_unit(),
+        deserialize_seq => visit_seq(EmptyAccess {}),
+        deserialize_map => visit_map(EmptyAccess {}),
+        deserialize_identifier => visit_str(""),
+        deserialize_ignored_any => visit_unit(),
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        visitor.visit_seq(EmptyAccess {})
+    }
+
+    fn deserialize_tuple_struct<V>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        visitor.visit_seq(EmptyAccess {})
+    }
+
+    fn deserialize_struct<V>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        visitor.visit_map(EmptyAccess {})
+    }
+
+    fn deserialize_enum<V>(self, name: &'static str, _variants: &'static [&'static str], _visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        Err(Self::Error::custom(format!("Enum {name} has no default value")))
+    }
+}
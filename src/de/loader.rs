@@ -0,0 +1,229 @@
+use std::io;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use serde::de::{DeserializeOwned, Error};
+use yaml_rust2::parser::Parser;
+use super::events::{canonical_path, IncludeResolver, Includes};
+use super::positions::Place;
+use super::{DeserializerOptions, YamlDeserializer};
+
+/// Deserializes the file, replacing `!include path` scalars by the document of the included file, see [`YamlLoader`]
+pub fn from_file_with_includes<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, serde::de::value::Error> {
+    YamlLoaderBuilder::new().build().from_file_with_includes(path)
+}
+
+/// Loads documents composed of several files.
+///
+/// A scalar tagged `!include`, e.g. `database: !include db.yaml`, is replaced by the document of the file at
+/// its path, relative to the file which includes it. Included files may include further files, up to a depth
+/// limit. A file which includes itself, directly or through other files, is an error naming the chain of files,
+/// however the paths name it. Events replayed from aliases count towards one
+/// [`max_alias_events`](DeserializerOptions::max_alias_events) limit for the document and all files it includes.
+///
+/// ```no_run
+/// use std::collections::HashMap;
+///
+/// let loader = serde_yaml2::YamlLoaderBuilder::new()
+///     .include_resolver(|path| std::fs::read_to_string(path))
+///     .max_include_depth(4)
+///     .build();
+/// let config: HashMap<String, String> = loader.from_file_with_includes("config/main.yaml").unwrap();
+/// ```
+pub struct YamlLoader {
+    options: DeserializerOptions,
+    resolver: IncludeResolver,
+    max_include_depth: usize,
+}
+
+impl YamlLoader {
+    pub fn from_file_with_includes<T: DeserializeOwned>(&self, path: impl AsRef<Path>) -> Result<T, serde::de::value::Error> {
+        let path = path.as_ref();
+        let content = (self.resolver)(path)
+            .map_err(|e| serde::de::value::Error::custom(format!("Failed to read {}: {e}", path.display())))?;
+
+        // events of included files have no source text in the content, so nothing is borrowed from it
+        let deserializer = &mut YamlDeserializer::from_parser_with_options(Parser::new_from_str(&content), self.options.clone())?;
+        deserializer.parser.set_includes(Includes {
+            resolver: self.resolver.clone(),
+            chain: vec![PathBuf::from(path)],
+            canonical: vec![canonical_path(path)],
+            max_depth: self.max_include_depth,
+        });
+
+        let value = deserializer.deserialize_node(PhantomData::<T>, Place::Document)?;
+        deserializer.end()?;
+        Ok(value)
+    }
+}
+
+/// Builder of a [`YamlLoader`]
+pub struct YamlLoaderBuilder {
+    options: DeserializerOptions,
+    resolver: IncludeResolver,
+    max_include_depth: usize,
+}
+
+impl Default for YamlLoaderBuilder {
+    fn default() -> Self {
+        YamlLoaderBuilder {
+            options: DeserializerOptions::default(),
+            resolver: Arc::new(|path| std::fs::read_to_string(path)),
+            max_include_depth: 16,
+        }
+    }
+}
+
+impl YamlLoaderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options of the deserializer, which apply to included files as well
+    pub fn options(mut self, value: DeserializerOptions) -> Self {
+        self.options = value;
+        self
+    }
+
+    /// Function reading the file at a path, defaults to reading it from the filesystem. It is `Send` and `Sync`,
+    /// so the loader can be shared between threads.
+    pub fn include_resolver<F: Fn(&Path) -> io::Result<String> + Send + Sync + 'static>(mut self, value: F) -> Self {
+        self.resolver = Arc::new(value);
+        self
+    }
+
+    /// Maximum number of files including each other. Defaults to 16.
+    pub fn max_include_depth(mut self, value: usize) -> Self {
+        self.max_include_depth = value;
+        self
+    }
+
+    pub fn build(self) -> YamlLoader {
+        YamlLoader {
+            options: self.options,
+            resolver: self.resolver,
+            max_include_depth: self.max_include_depth,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io;
+    use std::path::Path;
+    use serde::Deserialize;
+    use crate::wrapper::YamlNodeWrapper;
+    use crate::DeserializerOptions;
+    use super::{YamlLoader, YamlLoaderBuilder};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        services: Vec<Service>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Service {
+        image: String,
+        ports: Vec<u16>,
+    }
+
+    fn builder(files: &[(&str, &str)]) -> YamlLoaderBuilder {
+        let files: HashMap<String, String> = files.iter().map(|(path, content)| (path.to_string(), content.to_string())).collect();
+
+        YamlLoaderBuilder::new()
+            .include_resolver(move |path: &Path| {
+                files.get(path.to_str().unwrap_or_default()).cloned().ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+            })
+    }
+
+    fn loader(files: &[(&str, &str)]) -> YamlLoader {
+        builder(files).build()
+    }
+
+    #[test]
+    fn should_work() {
+        let loader = loader(&[
+            ("config/main.yaml", "name: app\nservices:\n  - !include services/web.yaml\n  - image: redis\n    ports: [6379]\n"),
+            ("config/services/web.yaml", "image: nginx\nports: !include ports.yaml\n"),
+            ("config/services/ports.yaml", "# exposed ports\n- 80\n- 443\n"),
+        ]);
+
+        assert_eq!(Config {
+            name: String::from("app"),
+            services: vec![
+                Service { image: String::from("nginx"), ports: vec![80, 443] },
+                Service { image: String::from("redis"), ports: vec![6379] },
+            ],
+        }, loader.from_file_with_includes("config/main.yaml").unwrap());
+
+        let error = loader.from_file_with_includes::<Config>("config/missing.yaml").unwrap_err().to_string();
+        assert!(error.starts_with("Failed to read config/missing.yaml"), "{error}");
+    }
+
+    #[test]
+    fn errors() {
+        let loader = loader(&[
+            ("a.yaml", "name: app\nservices: !include b.yaml\n"),
+            ("b.yaml", "- !include c.yaml\n"),
+            ("c.yaml", "image: !include a.yaml\nports: []\n"),
+            ("broken.yaml", "name: app\nservices: !include syntax.yaml\n"),
+            ("syntax.yaml", "- [1, 2\n"),
+            ("missing.yaml", "name: app\nservices: !include nowhere.yaml\n"),
+        ]);
+
+        let error = loader.from_file_with_includes::<Config>("a.yaml").unwrap_err().to_string();
+        assert!(error.starts_with("Include cycle a.yaml -> b.yaml -> c.yaml -> a.yaml at position Line: 1, Column: 17"), "{error}");
+
+        let error = loader.from_file_with_includes::<Config>("broken.yaml").unwrap_err().to_string();
+        assert!(error.starts_with("Error in included file syntax.yaml: Scan error at position Line: 2"), "{error}");
+        assert!(error.ends_with("at position Line: 2, Column: 20, Index: 29"), "{error}");
+
+        let error = loader.from_file_with_includes::<Config>("missing.yaml").unwrap_err().to_string();
+        assert!(error.starts_with("Failed to include nowhere.yaml"), "{error}");
+
+        let loader = YamlLoaderBuilder::new()
+            .include_resolver(|_: &Path| Ok(String::from("!include self.yaml")))
+            .max_include_depth(3)
+            .build();
+        let error = loader.from_file_with_includes::<Config>("nested/self.yaml").unwrap_err().to_string();
+        assert!(error.starts_with("Include cycle nested/self.yaml -> nested/self.yaml"), "{error}");
+
+        let loader = YamlLoaderBuilder::new()
+            .include_resolver(|_: &Path| Ok(String::from("!include d/x.yaml")))
+            .max_include_depth(3)
+            .build();
+        let error = loader.from_file_with_includes::<Config>("x.yaml").unwrap_err().to_string();
+        assert!(error.starts_with("Include depth limit of 3 exceeded by d/d/d/d/x.yaml"), "{error}");
+
+        // the same file named by another path
+        let renamed = self::loader(&[
+            ("conf/a.yaml", "name: app\nservices: !include ./sub/b.yaml\n"),
+            ("conf/./sub/b.yaml", "- !include ../a.yaml\n"),
+            ("conf/./sub/../a.yaml", "name: app\nservices: []\n"),
+        ]);
+        let error = renamed.from_file_with_includes::<Config>("conf/a.yaml").unwrap_err().to_string();
+        assert!(error.starts_with("Include cycle conf/a.yaml -> conf/./sub/b.yaml -> conf/./sub/../a.yaml"), "{error}");
+    }
+
+    #[test]
+    fn alias_budget() {
+        let files = [
+            ("main.yaml", "- !include a.yaml\n- !include a.yaml\n- &x [1, 2, 3]\n- *x\n"),
+            ("a.yaml", "- &y [1, 2, 3]\n- *y\n- *y\n"),
+        ];
+
+        // each included file replays 10 events, the main file 5
+        for (limit, ok) in [(Some(12), false), (Some(24), false), (Some(25), true)] {
+            let loader = builder(&files).options(DeserializerOptions::new().max_alias_events(limit)).build();
+            assert_eq!(ok, loader.from_file_with_includes::<YamlNodeWrapper>("main.yaml").is_ok(), "{limit:?}");
+        }
+    }
+
+    #[test]
+    fn loader_is_send() {
+        fn send_and_sync<T: Send + Sync>(_: &T) {}
+        send_and_sync(&YamlLoaderBuilder::new().build());
+    }
+}
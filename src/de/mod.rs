@@ -1,15 +1,114 @@
+use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
+use std::io;
+use std::marker::PhantomData;
 use std::str::Chars;
-use serde::de::{Visitor, Error, SeqAccess, DeserializeSeed, MapAccess, EnumAccess, VariantAccess};
+use serde::de::{DeserializeOwned, Visitor, Error, SeqAccess, DeserializeSeed, MapAccess, EnumAccess, VariantAccess, IntoDeserializer};
 use serde::{Deserialize, Deserializer};
-use yaml_rust2::{Event, Yaml};
-use yaml_rust2::parser::{Parser};
+use yaml_rust2::Event;
+use yaml_rust2::parser::{Parser, Tag};
+use events::{EventReader, ReadError};
+use lint::Lint;
+use raw::ByteOffsets;
+use resolve::Resolved;
+use crate::ser::{binary, ScalarStyle};
 use thiserror::Error;
 use yaml_rust2::scanner::{Marker, TScalarStyle};
 
+mod encoding;
+mod events;
+mod iter;
+mod kind;
+mod lint;
+mod loader;
+mod options;
+mod path;
+mod positions;
+mod raw;
+pub(crate) mod resolve;
+
+pub use iter::{SeqIter, StreamDeserializer};
+pub use kind::{NodeKind, NodeTag};
+pub use lint::validate;
+pub use loader::{from_file_with_includes, YamlLoader, YamlLoaderBuilder};
+pub use options::{DeserializerOptions, Schema};
+pub use path::from_str_path;
+pub use positions::{Position, Positions};
+use positions::{Place, PositionRecorder};
+
 pub fn from_str<'de, T: Deserialize<'de>>(data: &'de str) -> Result<T, serde::de::value::Error> {
+    from_str_with_options(data, DeserializerOptions::default())
+}
+
+pub fn from_str_with_options<'de, T: Deserialize<'de>>(data: &'de str, options: DeserializerOptions) -> Result<T, serde::de::value::Error> {
+    let deserializer = &mut YamlDeserializer::from_str_with_options(data, options)?;
+    let marker = deserializer.peek_marker();
+    let value = T::deserialize(&mut *deserializer).map_err(|e| with_position(e, marker))?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Deserializes a document encoded as UTF-8, UTF-16 or UTF-32, detected by its byte order mark or, without
+/// one, by the null bytes of its first character. Error positions count characters of the decoded text.
+pub fn from_slice<T: DeserializeOwned>(data: &[u8]) -> Result<T, serde::de::value::Error> {
+    from_str(&encoding::decode(data)?)
+}
+
+/// Reads the whole input and deserializes it like [`from_slice`]
+pub fn from_reader<R: io::Read, T: DeserializeOwned>(mut reader: R) -> Result<T, serde::de::value::Error> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data).map_err(|e| serde::de::value::Error::custom(format!("Failed to read input: {e}")))?;
+    from_slice(&data)
+}
+
+/// Deserializes the document and returns the source locations of its nodes by their path, e.g. to point at
+/// a value which is rejected by validation done after deserializing. See [`Positions`].
+pub fn from_str_with_positions<'de, T: Deserialize<'de>>(data: &'de str) -> Result<(T, Positions), serde::de::value::Error> {
     let deserializer = &mut YamlDeserializer::from_str(data)?;
-    T::deserialize(deserializer)
+    deserializer.positions = Some(PositionRecorder::default());
+    deserializer.parser.keep_last_event();
+
+    let value = deserializer.deserialize_node(PhantomData::<T>, Place::Document)?;
+    deserializer.end()?;
+
+    let recorder = deserializer.positions.take().unwrap_or_default();
+    Ok((value, Positions::new(data, recorder)))
+}
+
+/// Deserializes every document of a multi-document stream (documents separated by `---`)
+pub fn from_str_multi<'de, T: Deserialize<'de>>(data: &'de str) -> Result<Vec<T>, serde::de::value::Error> {
+    YamlDeserializer::from_str(data)?.into_iter().collect()
+}
+
+/// Lazily deserializes elements of a top-level sequence, so huge documents don't have to be loaded into memory at once
+pub fn seq_iter<'de, T: Deserialize<'de>>(data: &'de str) -> Result<SeqIter<'de, T>, serde::de::value::Error> {
+    SeqIter::new(YamlDeserializer::from_str(data)?)
+}
+
+/// Checks if the tag is the given tag of the YAML core schema, e.g. `!!set`
+fn is_core_tag(tag: &Option<Tag>, name: &str) -> bool {
+    matches!(tag, Some(Tag { handle, suffix }) if handle == "tag:yaml.org,2002:" && suffix == name)
+}
+
+fn is_quoted(style: &TScalarStyle) -> bool {
+    matches!(style, TScalarStyle::SingleQuoted | TScalarStyle::DoubleQuoted)
+}
+
+fn is_null(value: &str, style: &TScalarStyle) -> bool {
+    *style == TScalarStyle::Plain && resolve::is_null(value)
+}
+
+/// Appends the position of the node to an error which doesn't have one yet, e.g. one returned by a visitor for a
+/// missing field or a value of invalid type
+fn with_position(error: serde::de::value::Error, marker: Option<Marker>) -> serde::de::value::Error {
+    let message = error.to_string();
+
+    match marker {
+        Some(marker) if !message.contains("position Line: ") => {
+            serde::de::value::Error::custom(format!("{message} at position {}", MarkerWrapper(marker)))
+        },
+        _ => error,
+    }
 }
 
 #[derive(Debug)]
@@ -17,7 +116,8 @@ struct MarkerWrapper(Marker);
 
 impl Display for MarkerWrapper {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Line: {}, Column: {}, Index: {}", self.0.line(), self.0.col(), self.0.index())
+        // lines are already counted from 1 by the scanner, columns are shown the same way editors do
+        write!(f, "Line: {}, Column: {}, Index: {}", self.0.line(), self.0.col() + 1, self.0.index())
     }
 }
 
@@ -30,8 +130,28 @@ enum Errors<'a> {
     UnexpectedEventError(&'a str, Event, MarkerWrapper),
     #[error("Scan error at position {0}")]
     ScanError(MarkerWrapper),
-    #[error("Error while parsing scalar {0} into number")]
-    ParseNumberError(&'a str),
+    #[error("Alias at position {0} refers to an anchor which is undefined or contains the alias")]
+    UnknownAnchorError(MarkerWrapper),
+    #[error("{0} limit of {1} exceeded at position {2}")]
+    LimitExceededError(&'a str, usize, MarkerWrapper),
+    #[error("{0} at position {1} is not allowed in restricted mode")]
+    RestrictedError(String, MarkerWrapper),
+    #[error("{0} at position {1}")]
+    IncludeError(String, MarkerWrapper),
+    #[error("Error while parsing scalar {0} into {1} at position {2}. Not a number")]
+    ParseNumberError(&'a str, &'a str, MarkerWrapper),
+    #[error("Error while parsing scalar {0} into {1} at position {2}. Number out of range")]
+    NumberOutOfRangeError(&'a str, &'a str, MarkerWrapper),
+    #[error("Scalar {1} at position {2} can not be interpreted as {0}")]
+    InvalidTaggedScalarError(&'a str, String, MarkerWrapper),
+    #[error("Variant {0} at position {1} matches more than one variant when ignoring case")]
+    AmbiguousVariantError(&'a str, MarkerWrapper),
+    #[error("Error in sequence element {0} at position {1}: {2}")]
+    SequenceElementError(usize, MarkerWrapper, String),
+    #[error("Multiple documents found, second document starts at position {0}. Use from_str_multi to deserialize all of them")]
+    MultipleDocumentsError(MarkerWrapper),
+    #[error("Path segment {0} not found in {1} at position {2}")]
+    PathNotFoundError(&'a str, &'a str, MarkerWrapper),
 }
 
 impl<'a> Errors<'a> {
@@ -43,12 +163,52 @@ impl<'a> Errors<'a> {
         Errors::UnexpectedEventError(expected, got, MarkerWrapper(marker))
     }
 
-    fn scan_error(marker: Marker) -> Self {
-        Errors::ScanError(MarkerWrapper(marker))
+    fn read_error(error: ReadError) -> Self {
+        match error {
+            ReadError::Scan(scan_error) => Errors::ScanError(MarkerWrapper(*scan_error.marker())),
+            ReadError::UnknownAnchor(marker) => Errors::UnknownAnchorError(MarkerWrapper(marker)),
+            ReadError::AliasBudget(limit, marker) => Errors::LimitExceededError("Alias expansion", limit, MarkerWrapper(marker)),
+            ReadError::NodeBudget(limit, marker) => Errors::LimitExceededError("Node count", limit, MarkerWrapper(marker)),
+            ReadError::Restricted(construct, marker) => Errors::RestrictedError(construct, MarkerWrapper(marker)),
+            ReadError::Include(message, marker) => Errors::IncludeError(message, MarkerWrapper(marker)),
+        }
+    }
+
+    fn parse_number_error(value: &'a str, type_name: &'a str, marker: Marker) -> Self {
+        // any integer which the target type failed to parse doesn't fit into it
+        if value.parse::<i128>().is_ok() || value.parse::<u128>().is_ok() {
+            Errors::NumberOutOfRangeError(value, type_name, MarkerWrapper(marker))
+        } else {
+            Errors::ParseNumberError(value, type_name, MarkerWrapper(marker))
+        }
+    }
+
+    fn invalid_tagged_scalar_error(tag: &'a str, value: &str, marker: Marker) -> Self {
+        // the scalar may be a whole block of text
+        const MAX_LENGTH: usize = 32;
+
+        let value = match value.char_indices().nth(MAX_LENGTH) {
+            Some((index, _)) => format!("{}...", &value[..index]),
+            None => value.to_string(),
+        };
+
+        Errors::InvalidTaggedScalarError(tag, value, MarkerWrapper(marker))
+    }
+
+    fn ambiguous_variant_error(value: &'a str, marker: Marker) -> Self {
+        Errors::AmbiguousVariantError(value, MarkerWrapper(marker))
+    }
+
+    fn sequence_element_error(index: usize, marker: Marker, error: String) -> Self {
+        Errors::SequenceElementError(index, MarkerWrapper(marker), error)
+    }
+
+    fn multiple_documents_error(marker: Marker) -> Self {
+        Errors::MultipleDocumentsError(MarkerWrapper(marker))
     }
 
-    fn parse_number_error(value: &'a str) -> Self {
-        Errors::ParseNumberError(value)
+    fn path_not_found_error(segment: &'a str, found: &'a str, marker: Marker) -> Self {
+        Errors::PathNotFoundError(segment, found, MarkerWrapper(marker))
     }
 }
 
@@ -59,11 +219,16 @@ impl<'a> Into<serde::de::value::Error> for Errors<'a> {
     }
 }
 
-struct EventsSequenceAccess<'a, 'de> {
-    deserializer: &'a mut YamlDeserializer<'de>,
+struct EventsSequenceAccess<'a, 'de, I> {
+    deserializer: &'a mut YamlDeserializer<'de, I>,
+    /// Fields of the struct being deserialized, with `null_as_default` their entries with a null value are skipped,
+    /// so they fall back to their default
+    fields: &'static [&'static str],
+    /// Index of the next element of a sequence
+    index: usize,
 }
 
-impl<'de, 'a> SeqAccess<'de> for EventsSequenceAccess<'a, 'de> {
+impl<'de, 'a, I: Iterator<Item = char>> SeqAccess<'de> for EventsSequenceAccess<'a, 'de, I> {
     type Error = serde::de::value::Error;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> where T: DeserializeSeed<'de> {
@@ -71,43 +236,87 @@ impl<'de, 'a> SeqAccess<'de> for EventsSequenceAccess<'a, 'de> {
             return Ok(None);
         }
 
-        seed.deserialize(&mut *self.deserializer).map(Some)
+        self.index += 1;
+        self.deserializer.deserialize_node(seed, Place::Element(self.index - 1)).map(Some)
     }
 }
 
-impl<'de, 'a> MapAccess<'de> for EventsSequenceAccess<'a, 'de> {
+impl<'de, 'a, I: Iterator<Item = char>> MapAccess<'de> for EventsSequenceAccess<'a, 'de, I> {
     type Error = serde::de::value::Error;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> where K: DeserializeSeed<'de> {
-        if let Ok((Event::MappingEnd, ..), ..) = self.deserializer.parser.peek() {
-            return Ok(None);
+        if self.deserializer.lint.is_some() {
+            self.deserializer.skip_linted_entries(self.fields)?;
+        }
+
+        if self.deserializer.options.null_as_default && !self.fields.is_empty() {
+            self.deserializer.skip_null_entries(self.fields)?;
+        }
+
+        // a failed unknown key tells that the struct denies them, see `validate`
+        let unknown_key = match (&self.deserializer.lint, self.deserializer.parser.peek()) {
+            (Some(_), Ok((Event::Scalar(key, ..), ..))) if !self.fields.is_empty() && !self.fields.contains(&key.as_str()) => Some(key.clone()),
+            _ => None,
+        };
+
+        match self.deserializer.parser.peek() {
+            Ok((Event::MappingEnd, ..), ..) => {
+                return Ok(None);
+            },
+            Ok((Event::Scalar(value, ..), marker)) if self.deserializer.ignored_key_callback.is_some() => {
+                self.deserializer.last_key = Some((value.clone(), *marker));
+            },
+            _ => {},
         }
 
-        seed.deserialize(&mut *self.deserializer).map(Some)
+        let result = self.deserializer.deserialize_node(seed, Place::Key);
+
+        if let (Err(error), Some(key)) = (&result, unknown_key) {
+            self.deserializer.deny_unknown_fields(&key, self.fields, error);
+        }
+
+        result.map(Some)
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error> where V: DeserializeSeed<'de> {
-        seed.deserialize(&mut *self.deserializer)
+        if let Some((key, marker)) = self.deserializer.last_key.take() {
+            if let Ok((_, value_marker)) = self.deserializer.parser.peek() {
+                self.deserializer.ignored_value = Some((key, marker, value_marker.index()));
+            }
+        }
+
+        self.deserializer.deserialize_node(seed, Place::Value)
     }
 }
 
-impl<'de, 'a> EnumAccess<'de> for EventsSequenceAccess<'a, 'de> {
+impl<'de, 'a, I: Iterator<Item = char>> EnumAccess<'de> for EventsSequenceAccess<'a, 'de, I> {
     type Error = serde::de::value::Error;
     type Variant = Self;
 
     fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error> where V: DeserializeSeed<'de> {
-        let value = seed.deserialize(&mut *self.deserializer)?;
-        Ok((value, self))
+        self.deserializer.variant_identifier = true;
+        let value = self.deserializer.deserialize_node(seed, Place::Variant);
+
+        // the seed may fail or read the variant without an identifier, which leaves the state for the next one
+        self.deserializer.variant_identifier = false;
+        self.deserializer.enum_variants = &[];
+
+        Ok((value?, self))
     }
 }
 
-impl<'de, 'a> VariantAccess<'de> for EventsSequenceAccess<'a, 'de> {
+impl<'de, 'a, I: Iterator<Item = char>> VariantAccess<'de> for EventsSequenceAccess<'a, 'de, I> {
     type Error = serde::de::value::Error;
 
     fn unit_variant(self) -> Result<(), Self::Error> {
+        // the value of the variant key may be missing altogether
+        if let Ok((Event::MappingEnd, ..)) = self.deserializer.parser.peek() {
+            return Ok(());
+        }
+
         match self.deserializer.parser.next_token() {
-            Ok((Event::Scalar(value, ..), marker), ..) => {
-                if value == "null" || value == "~" {
+            Ok((Event::Scalar(value, style, ..), marker), ..) => {
+                if value == "null" || value == "~" || (value.is_empty() && style == TScalarStyle::Plain) {
                     Ok(())
                 } else {
                     Err(Errors::unexpected_scalar_value_error("'~' or 'null'", &value, marker).into())
@@ -117,7 +326,90 @@ impl<'de, 'a> VariantAccess<'de> for EventsSequenceAccess<'a, 'de> {
                 Err(Errors::unexpected_event_error("Scalar", event, marker).into())
             },
             Err(scan_error) => {
-                Err(Errors::scan_error(*scan_error.marker()).into())
+                Err(Errors::read_error(scan_error).into())
+            },
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error> where T: DeserializeSeed<'de> {
+        self.deserializer.deserialize_node(seed, Place::Value)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.deserializer.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.deserializer.deserialize_struct("", fields, visitor)
+    }
+}
+
+/// Access over keys of a `!!set` mapping as sequence elements. Values of a set must be null.
+struct SetAccess<'a, 'de, I> {
+    deserializer: &'a mut YamlDeserializer<'de, I>,
+    index: usize,
+}
+
+impl<'de, 'a, I: Iterator<Item = char>> SeqAccess<'de> for SetAccess<'a, 'de, I> {
+    type Error = serde::de::value::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> where T: DeserializeSeed<'de> {
+        if let Ok((Event::MappingEnd, ..), ..) = self.deserializer.parser.peek() {
+            return Ok(None);
+        }
+
+        self.index += 1;
+        let value = self.deserializer.deserialize_node(seed, Place::Element(self.index - 1))?;
+
+        match self.deserializer.parser.next_token() {
+            Ok((Event::Scalar(value, style, ..), ..)) if is_null(&value, &style) => {},
+            Ok((Event::Scalar(value, _, _, _), marker)) => {
+                return Err(Errors::unexpected_scalar_value_error("null value of set entry", &value, marker).into());
+            },
+            Ok((event, marker)) => {
+                return Err(Errors::unexpected_event_error("null value of set entry", event, marker).into());
+            },
+            Err(scan_error) => {
+                return Err(Errors::read_error(scan_error).into());
+            },
+        }
+
+        Ok(Some(value))
+    }
+}
+
+/// Access over a node whose local tag names the enum variant, the node itself is the value of the variant
+struct TaggedVariantAccess<'a, 'de, I> {
+    deserializer: &'a mut YamlDeserializer<'de, I>,
+    name: String,
+}
+
+impl<'de, 'a, I: Iterator<Item = char>> EnumAccess<'de> for TaggedVariantAccess<'a, 'de, I> {
+    type Error = serde::de::value::Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error> where V: DeserializeSeed<'de> {
+        let value = seed.deserialize(self.name.as_str().into_deserializer())?;
+        Ok((value, self))
+    }
+}
+
+impl<'de, 'a, I: Iterator<Item = char>> VariantAccess<'de> for TaggedVariantAccess<'a, 'de, I> {
+    type Error = serde::de::value::Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self.deserializer.parser.next_token() {
+            Ok((Event::Scalar(value, style, ..), ..)) if value.is_empty() || is_null(&value, &style) => {
+                Ok(())
+            },
+            Ok((Event::Scalar(value, ..), marker)) => {
+                Err(Errors::unexpected_scalar_value_error("'~' or 'null'", &value, marker).into())
+            },
+            Ok((event, marker)) => {
+                Err(Errors::unexpected_event_error("Scalar", event, marker).into())
+            },
+            Err(scan_error) => {
+                Err(Errors::read_error(scan_error).into())
             },
         }
     }
@@ -130,91 +422,417 @@ impl<'de, 'a> VariantAccess<'de> for EventsSequenceAccess<'a, 'de> {
         self.deserializer.deserialize_seq(visitor)
     }
 
-    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        self.deserializer.deserialize_map(visitor)
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.deserializer.deserialize_struct("", fields, visitor)
+    }
+}
+
+/// Access over a collection without elements. Used when an empty document stands in for a map or sequence.
+struct EmptyAccess {}
+
+impl<'de> SeqAccess<'de> for EmptyAccess {
+    type Error = serde::de::value::Error;
+
+    fn next_element_seed<T>(&mut self, _seed: T) -> Result<Option<T::Value>, Self::Error> where T: DeserializeSeed<'de> {
+        Ok(None)
+    }
+}
+
+impl<'de> MapAccess<'de> for EmptyAccess {
+    type Error = serde::de::value::Error;
+
+    fn next_key_seed<K>(&mut self, _seed: K) -> Result<Option<K::Value>, Self::Error> where K: DeserializeSeed<'de> {
+        Ok(None)
+    }
+
+    fn next_value_seed<V>(&mut self, _seed: V) -> Result<V::Value, Self::Error> where V: DeserializeSeed<'de> {
+        Err(Error::custom("Value requested from empty map"))
     }
 }
 
+/// Visits a scalar with the type given by its tag or resolved from its text
+fn deserialize_scalar<'de, V>(value: String, style: TScalarStyle, tag: Option<Tag>, marker: Marker, schema: Schema, visitor: V) -> Result<V::Value, serde::de::value::Error> where V: Visitor<'de> {
+    let tag = match tag {
+        Some(Tag { handle, suffix }) if handle == "tag:yaml.org,2002:" => Some(suffix),
+        _ => None,
+    };
+
+    match tag.as_deref() {
+        Some("str") => {
+            return visitor.visit_string(value);
+        },
+        Some("null") => {
+            return match resolve::is_null(&value) {
+                true => visitor.visit_unit(),
+                false => Err(Errors::invalid_tagged_scalar_error("!!null", &value, marker).into()),
+            };
+        },
+        Some("bool") => {
+            return match resolve::resolve_bool(&value) {
+                Some(v) => visitor.visit_bool(v),
+                None => Err(Errors::invalid_tagged_scalar_error("!!bool", &value, marker).into()),
+            };
+        },
+        Some("int") => {
+            return match resolve::resolve_int(&value) {
+                Some(Resolved::Int(v)) => visitor.visit_i64(v),
+                Some(Resolved::UInt(v)) => visitor.visit_u64(v),
+                _ => Err(Errors::invalid_tagged_scalar_error("!!int", &value, marker).into()),
+            };
+        },
+        Some("float") => {
+            return match resolve::resolve_float(&value) {
+                Some(v) => visitor.visit_f64(v),
+                None => Err(Errors::invalid_tagged_scalar_error("!!float", &value, marker).into()),
+            };
+        },
+        _ => {},
+    }
+
+    // only plain scalars are resolved, quoted and block scalars are strings
+    if style != TScalarStyle::Plain {
+        return visitor.visit_string(value);
+    }
+
+    match resolve::resolve(&value, schema) {
+        Resolved::Null => visitor.visit_none(),
+        Resolved::Bool(v) => visitor.visit_bool(v),
+        Resolved::Int(v) => visitor.visit_i64(v),
+        Resolved::UInt(v) => visitor.visit_u64(v),
+        Resolved::Float(v) => visitor.visit_f64(v),
+        Resolved::String => visitor.visit_string(value),
+    }
+}
 
 macro_rules! deserialize_number {
     ($self:ident, $visitor:ident, $visit:ident, $type:ty) => {
+        deserialize_number!($self, $visitor, $visit, $type, |value: &str| value.parse::<$type>().ok())
+    };
+    ($self:ident, $visitor:ident, $visit:ident, $type:ty, $parse:expr) => {
         match $self.parser.next_token() {
-            Ok((Event::Scalar(value, TScalarStyle::Plain, ..), ..), ..) => {
-                let Ok(parsed) = value.parse::<$type>() else {
-                    return Err(Errors::parse_number_error(&value).into());
+            Ok((Event::Scalar(value, style, ..), marker), ..) if style == TScalarStyle::Plain || ($self.options.lenient_numbers && is_quoted(&style)) => {
+                let Some(parsed) = $parse(&value) else {
+                    let error = Errors::parse_number_error(&value, stringify!($type), marker).into();
+                    return $self.recover(error, marker, None, || $visitor.$visit(<$type>::default()));
                 };
-                return $visitor.$visit(parsed);
+                $visitor.$visit(parsed)
             },
             Ok((event, marker)) => {
-                Err(Errors::unexpected_event_error("Scalar", event, marker).into())
+                let error = Errors::unexpected_event_error("Scalar", event.clone(), marker).into();
+                $self.recover(error, marker, Some(&event), || $visitor.$visit(<$type>::default()))
             },
             Err(scan_error) => {
-                Err(Errors::scan_error(*scan_error.marker()).into())
+                Err(Errors::read_error(scan_error).into())
             },
         }
     }
 }
 
-pub struct YamlDeserializer<'de> {
-    parser: Parser<Chars<'de>>,
+type IgnoredKeyCallback<'de> = Box<dyn FnMut(&str, Marker) + 'de>;
+
+/// Deserializer reading events from a [`Parser`]. Usually created from a string, which also allows borrowing
+/// strings and [`RawYaml`](crate::RawYaml) from the input.
+pub struct YamlDeserializer<'de, I = Chars<'de>> {
+    input: Option<&'de str>,
+    parser: EventReader<I>,
+    options: DeserializerOptions,
+    ignored_key_callback: Option<IgnoredKeyCallback<'de>>,
+    /// Key of the map entry whose value is about to be deserialized
+    last_key: Option<(String, Marker)>,
+    /// Key of the value which starts at the index, reported if the value is ignored
+    ignored_value: Option<(String, Marker, usize)>,
+    /// Next identifier names an enum variant
+    variant_identifier: bool,
+    /// Variants of the enum being deserialized
+    enum_variants: &'static [&'static str],
+    /// Nodes replaced while collecting errors, see [`validate`]
+    lint: Option<Lint>,
+    offsets: ByteOffsets,
+    /// Locations of the consumed nodes, see [`from_str_with_positions`]
+    positions: Option<PositionRecorder>,
 }
 
 impl<'de> YamlDeserializer<'de> {
     #[allow(clippy::should_implement_trait)]
     pub fn from_str(data: &'de str) -> Result<Self, serde::de::value::Error> {
-        let mut parser = Parser::new_from_str(data);
+        Self::from_str_with_options(data, DeserializerOptions::default())
+    }
+
+    pub fn from_str_with_options(data: &'de str, options: DeserializerOptions) -> Result<Self, serde::de::value::Error> {
+        Self::new(Parser::new_from_str(data), Some(data), options)
+    }
+}
+
+impl<'de, I: Iterator<Item = char>> YamlDeserializer<'de, I> {
+    /// Creates a deserializer reading events from an already configured parser
+    pub fn from_parser(parser: Parser<I>) -> Result<Self, serde::de::value::Error> {
+        Self::from_parser_with_options(parser, DeserializerOptions::default())
+    }
+
+    pub fn from_parser_with_options(parser: Parser<I>, options: DeserializerOptions) -> Result<Self, serde::de::value::Error> {
+        Self::new(parser, None, options)
+    }
 
-        // skip stream and doc events
-        if let Ok((Event::StreamStart, ..)) = parser.peek() {
-            parser.next_token().map_err(|e| Errors::scan_error(*e.marker()).into())?;
+    /// Creates a deserializer parsing characters of the iterator
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_iter(iter: I) -> Result<Self, serde::de::value::Error> {
+        Self::from_parser(Parser::new(iter))
+    }
 
-            if let Ok((Event::DocumentStart, ..)) = parser.peek() {
-                parser.next_token().map_err(|e| Errors::scan_error(*e.marker()).into())?;
+    fn new(mut parser: Parser<I>, input: Option<&'de str>, options: DeserializerOptions) -> Result<Self, serde::de::value::Error> {
+        // skip stream and doc events. Directives (%YAML, %TAG) are consumed by the parser together with
+        // the document start, tag handles declared by %TAG are already resolved in the events it emits
+        for expected in [Event::StreamStart, Event::DocumentStart] {
+            match parser.peek() {
+                Ok((event, ..)) if *event == expected => {
+                    parser.next_token().map_err(|e| Errors::read_error(e.into()).into())?;
+                },
+                Ok(_) => break,
+                Err(scan_error) => return Err(Errors::read_error(scan_error.into()).into()),
             }
         }
 
         Ok(YamlDeserializer {
-            parser
+            input,
+            parser: EventReader::new(parser, options.max_alias_events, options.max_nodes, options.restricted),
+            options,
+            ignored_key_callback: None,
+            last_key: None,
+            ignored_value: None,
+            variant_identifier: false,
+            enum_variants: &[],
+            lint: None,
+            offsets: ByteOffsets::new(input),
+            positions: None,
         })
     }
+
+    /// Sets a callback invoked with the key and its position for every map entry whose value is ignored by
+    /// the deserialized type, e.g. unknown struct fields
+    pub fn on_ignored_key<F: FnMut(&str, Marker) + 'de>(&mut self, callback: F) {
+        self.ignored_key_callback = Some(Box::new(callback));
+    }
+
+    /// Turns the deserializer into an iterator over all documents of a multi-document stream
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter<T: Deserialize<'de>>(self) -> StreamDeserializer<'de, T, I> {
+        StreamDeserializer::new(self)
+    }
+
+    /// Checks that the input contains nothing but an optional `...` marker after the deserialized document
+    pub fn end(&mut self) -> Result<(), serde::de::value::Error> {
+        match self.next_document()? {
+            None => Ok(()),
+            Some(marker) => Err(Errors::multiple_documents_error(marker).into()),
+        }
+    }
+
+    /// Returns the kind of the next node without consuming it, e.g. for a `Deserialize` implementation which
+    /// accepts both a scalar and a mapping. It is an error to peek at the end of a sequence or mapping.
+    pub fn peek_kind(&mut self) -> Result<NodeKind, serde::de::value::Error> {
+        match self.parser.peek() {
+            Ok((Event::Scalar(_, style, _, tag), ..)) => {
+                Ok(NodeKind::Scalar { style: kind::scalar_style(*style), tag: tag.as_ref().map(NodeTag::from) })
+            },
+            Ok((Event::SequenceStart(_, tag), ..)) => {
+                Ok(NodeKind::Sequence { tag: tag.as_ref().map(NodeTag::from) })
+            },
+            Ok((Event::MappingStart(_, tag), ..)) => {
+                Ok(NodeKind::Mapping { tag: tag.as_ref().map(NodeTag::from) })
+            },
+            Ok((Event::DocumentEnd | Event::StreamEnd, ..)) => {
+                Ok(NodeKind::DocumentEnd)
+            },
+            Ok((event, marker)) => {
+                Err(Errors::unexpected_event_error("Scalar, SequenceStart or MappingStart", event.clone(), *marker).into())
+            },
+            Err(scan_error) => {
+                Err(Errors::read_error(scan_error).into())
+            },
+        }
+    }
+
+    /// Moves past the end of the current document. Returns the position of the next document if there is one.
+    fn next_document(&mut self) -> Result<Option<Marker>, serde::de::value::Error> {
+        if let Ok((Event::DocumentEnd, ..)) = self.parser.peek() {
+            self.parser.next_token().map_err(|e| Errors::read_error(e).into())?;
+        }
+
+        match self.parser.next_token() {
+            Ok((Event::StreamEnd, ..)) => {
+                Ok(None)
+            },
+            Ok((Event::DocumentStart, marker)) => {
+                Ok(Some(marker))
+            },
+            Ok((event, marker)) => {
+                Err(Errors::unexpected_event_error("DocumentEnd", event, marker).into())
+            },
+            Err(scan_error) => {
+                Err(Errors::read_error(scan_error).into())
+            },
+        }
+    }
+
+    /// Returns the name of the variant which matches the value ignoring case with `case_insensitive_enums`, or the
+    /// value itself if it matches exactly or not at all
+    fn variant_name<'v>(&self, value: &'v str, variants: &'static [&'static str], marker: Marker) -> Result<&'v str, serde::de::value::Error> {
+        if !self.options.case_insensitive_enums || variants.contains(&value) {
+            return Ok(value);
+        }
+
+        let mut matching = variants.iter().filter(|variant| variant.eq_ignore_ascii_case(value));
+
+        match (matching.next(), matching.next()) {
+            (Some(variant), None) => Ok(variant),
+            (Some(_), Some(_)) => Err(Errors::ambiguous_variant_error(value, marker).into()),
+            (None, _) => Ok(value),
+        }
+    }
+
+    /// Deserializes the next node with the seed and adds its position to errors. In lint mode the node may be
+    /// replaced by a default value, see [`validate`]. The location of the node is recorded for
+    /// [`from_str_with_positions`].
+    fn deserialize_node<T: DeserializeSeed<'de>>(&mut self, seed: T, place: Place) -> Result<T::Value, serde::de::value::Error> {
+        let marker = self.peek_marker();
+
+        let (Some(input), Some(marker), Some(_)) = (self.input, marker, self.positions.as_ref()) else {
+            return self.deserialize_unrecorded_node(seed, marker, place);
+        };
+
+        if self.at_document_end() {
+            return self.deserialize_unrecorded_node(seed, Some(marker), place);
+        }
+
+        let name = match (place, self.parser.peek()) {
+            (Place::Key | Place::Variant, Ok((Event::Scalar(value, ..), _))) => Some(value.clone()),
+            _ => None,
+        };
+        let start = self.node_start(input, marker);
+        let (parent, earliest) = match self.positions.as_mut() {
+            Some(recorder) => (recorder.enter(place), std::mem::replace(&mut recorder.earliest, usize::MAX)),
+            None => (None, usize::MAX),
+        };
+
+        let value = self.deserialize_unrecorded_node(seed, Some(marker), place)?;
+
+        let last_child_end = self.positions.as_ref().map_or(start, |recorder| recorder.end);
+        let end = self.consumed_node_end(input, self.offsets.byte_offset(input, marker.index()), last_child_end)?.max(start);
+
+        if let Some(recorder) = self.positions.as_mut() {
+            let start = start.min(recorder.earliest);
+            // a collection used as key is named by its source text
+            let name = name.unwrap_or_else(|| input[start..end].trim().to_string());
+
+            recorder.earliest = earliest.min(start);
+            recorder.end = end;
+            recorder.leave(place, parent, start..end, &name);
+        }
+
+        Ok(value)
+    }
+
+    fn deserialize_unrecorded_node<T: DeserializeSeed<'de>>(&mut self, seed: T, marker: Option<Marker>, place: Place) -> Result<T::Value, serde::de::value::Error> {
+        let result = match self.lint.is_some() {
+            true => self.deserialize_linted(seed, marker, matches!(place, Place::Key)),
+            false => seed.deserialize(&mut *self),
+        };
+
+        result.map_err(|e| with_position(e, marker))
+    }
+
+    fn peek_marker(&mut self) -> Option<Marker> {
+        self.parser.peek().ok().map(|(_, marker)| *marker)
+    }
+
+    /// Returns true if there is no node left in the current document, i.e. the document is empty
+    fn at_document_end(&mut self) -> bool {
+        matches!(self.peek_kind(), Ok(NodeKind::DocumentEnd))
+    }
+
+    /// Deserializes a mapping, `fields` are the fields of a struct
+    fn deserialize_mapping<V: Visitor<'de>>(&mut self, visitor: V, fields: &'static [&'static str]) -> Result<V::Value, serde::de::value::Error> {
+        if self.at_document_end() || self.skip_null_collection()? {
+            return visitor.visit_map(EmptyAccess {});
+        }
+
+        match self.parser.next_token() {
+            Ok((Event::MappingStart(..), marker), ..) => {
+                let value = visitor.visit_map(EventsSequenceAccess { deserializer: self, fields, index: 0 }).map_err(|e| with_position(e, Some(marker)))?;
+
+                match self.parser.next_token() {
+                    Ok((Event::MappingEnd, ..), ..) => {
+                        Ok(value)
+                    },
+                    Ok((event, marker)) => {
+                        Err(Errors::unexpected_event_error("MappingEnd", event, marker).into())
+                    },
+                    Err(scan_error) => {
+                        Err(Errors::read_error(scan_error).into())
+                    },
+                }
+            },
+            Ok((event, marker)) => {
+                let error = Errors::unexpected_event_error("MappingStart", event.clone(), marker).into();
+                self.recover(error, marker, Some(&event), || visitor.visit_map(EmptyAccess {}))
+            },
+            Err(scan_error) => {
+                Err(Errors::read_error(scan_error).into())
+            },
+        }
+    }
+
+    /// Skips mapping entries of the fields whose value is null. Entries of other keys are kept, so unknown fields
+    /// are still reported with `deny_unknown_fields`.
+    fn skip_null_entries(&mut self, fields: &[&str]) -> Result<(), serde::de::value::Error> {
+        loop {
+            let null = matches!(self.parser.peek(), Ok((Event::Scalar(key, ..), ..)) if fields.contains(&key.as_str()))
+                && matches!(self.parser.peek_second(), Ok((Event::Scalar(value, style, ..), ..)) if is_null(value, style));
+
+            if !null {
+                return Ok(());
+            }
+
+            // key and value
+            self.skip_node()?;
+            self.skip_node()?;
+        }
+    }
+
+    /// Consumes the next event if it is a null scalar and `null_as_empty_collections` is enabled
+    fn skip_null_collection(&mut self) -> Result<bool, serde::de::value::Error> {
+        if !self.options.null_as_empty_collections {
+            return Ok(false);
+        }
+
+        if let Ok((Event::Scalar(value, style, ..), ..)) = self.parser.peek() {
+            if is_null(value, style) {
+                self.parser.next_token().map_err(|e| Errors::read_error(e).into())?;
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
 }
 
-impl<'de, 'a> Deserializer<'de> for &'a mut YamlDeserializer<'de> {
+impl<'de, I: Iterator<Item = char>> Deserializer<'de> for &mut YamlDeserializer<'de, I> {
     type Error = serde::de::value::Error;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        // empty document is null
+        if self.at_document_end() {
+            return visitor.visit_unit();
+        }
+
         match self.parser.next_token() {
-            Ok((event, _)) => {
+            Ok((event, marker)) => {
                 match event {
-                    Event::Scalar(value, _, _, _) => {
-                        let yaml_node = Yaml::from_str(&value);
-
-                        match yaml_node {
-                            Yaml::Real(v) => {
-                                let Ok(parsed) = v.parse() else {
-                                    return Err(Errors::parse_number_error(&v).into());
-                                };
-                                visitor.visit_f64(parsed)
-                            },
-                            Yaml::Integer(v) => {
-                                visitor.visit_i64(v)
-                            },
-                            Yaml::String(v) => {
-                                visitor.visit_string(v)
-                            },
-                            Yaml::Boolean(v) => {
-                                visitor.visit_bool(v)
-                            },
-                            Yaml::Null => {
-                                visitor.visit_none()
-                            },
-                            _ => {
-                                Err(Self::Error::custom("Unexpected Yaml node type"))
-                            },
-                        }
+                    Event::Scalar(value, style, _, tag) => {
+                        deserialize_scalar(value, style, tag, marker, self.options.schema, visitor)
                     },
                     Event::SequenceStart(_, _) => {
-                        let value = visitor.visit_seq(EventsSequenceAccess { deserializer: self })?;
+                        let value = visitor.visit_seq(EventsSequenceAccess { deserializer: self, fields: &[], index: 0 }).map_err(|e| with_position(e, Some(marker)))?;
 
                         if let Ok((Event::SequenceEnd, ..), ..) = self.parser.next_token() {
                             Ok(value)
@@ -223,7 +841,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut YamlDeserializer<'de> {
                         }
                     },
                     Event::MappingStart(_, _) => {
-                        let value = visitor.visit_map(EventsSequenceAccess { deserializer: self })?;
+                        let value = visitor.visit_map(EventsSequenceAccess { deserializer: self, fields: &[], index: 0 }).map_err(|e| with_position(e, Some(marker)))?;
 
                         match self.parser.next_token() {
                             Ok((Event::MappingEnd, ..), ..) => {
@@ -233,7 +851,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut YamlDeserializer<'de> {
                                 Err(Errors::unexpected_event_error("MappingEnd", event, marker).into())
                             },
                             Err(scan_error) => {
-                                Err(Errors::scan_error(*scan_error.marker()).into())
+                                Err(Errors::read_error(scan_error).into())
                             },
                         }
                     },
@@ -259,36 +877,47 @@ impl<'de, 'a> Deserializer<'de> for &'a mut YamlDeserializer<'de> {
                         Err(Self::Error::custom("Unexpected DocumentEnd event"))
                     }
                     Event::Alias(_) => {
-                        Err(Self::Error::custom("Unexpected Alias event"))
+                        Err(Errors::unexpected_event_error("Scalar, SequenceStart or MappingStart", event, marker).into())
                     }
                 }
             },
             Err(scan_error) => {
-                Err(Errors::scan_error(*scan_error.marker()).into())
+                Err(Errors::read_error(scan_error).into())
             },
         }
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        let lenient = self.options.lenient_booleans;
+
         match self.parser.next_token() {
-            Ok((Event::Scalar(value, TScalarStyle::Plain, ..), marker), ..) => {
+            Ok((Event::Scalar(value, style, ..), marker), ..) if style == TScalarStyle::Plain || (lenient && is_quoted(&style)) => {
                 match value.as_str() {
                     "true" => {
-                        return visitor.visit_bool(true);
+                        visitor.visit_bool(true)
                     },
                     "false" => {
-                        return visitor.visit_bool(false);
+                        visitor.visit_bool(false)
+                    },
+                    "1" if lenient && style == TScalarStyle::Plain => {
+                        visitor.visit_bool(true)
+                    },
+                    "0" if lenient && style == TScalarStyle::Plain => {
+                        visitor.visit_bool(false)
                     },
                     _ => {
-                        Err(Errors::unexpected_scalar_value_error("'true' of 'false'", &value, marker).into())
-                    }
+                        let expected = if lenient { "'true', 'false', 1 or 0" } else { "'true' or 'false'" };
+                        let error = Errors::unexpected_scalar_value_error(expected, &value, marker).into();
+                        self.recover(error, marker, None, || visitor.visit_bool(false))
+                    },
                 }
             },
             Ok((event, marker)) => {
-                Err(Errors::unexpected_event_error("Plain scalar", event, marker).into())
+                let error = Errors::unexpected_event_error("Plain scalar", event.clone(), marker).into();
+                self.recover(error, marker, Some(&event), || visitor.visit_bool(false))
             },
             Err(scan_error) => {
-                Err(Errors::scan_error(*scan_error.marker()).into())
+                Err(Errors::read_error(scan_error).into())
             },
         }
     }
@@ -309,6 +938,10 @@ impl<'de, 'a> Deserializer<'de> for &'a mut YamlDeserializer<'de> {
         deserialize_number!(self, visitor, visit_i64, i64)
     }
 
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        deserialize_number!(self, visitor, visit_i128, i128)
+    }
+
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
         deserialize_number!(self, visitor, visit_u8, u8)
     }
@@ -325,76 +958,140 @@ impl<'de, 'a> Deserializer<'de> for &'a mut YamlDeserializer<'de> {
         deserialize_number!(self, visitor, visit_u64, u64)
     }
 
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        deserialize_number!(self, visitor, visit_u128, u128)
+    }
+
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        deserialize_number!(self, visitor, visit_f32, f32)
+        // `.inf` and `.nan` of the core schema as well
+        deserialize_number!(self, visitor, visit_f32, f32, |value: &str| value.parse().ok().or_else(|| resolve::resolve_float(value).map(|v| v as f32)))
     }
 
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        deserialize_number!(self, visitor, visit_f64, f64)
+        deserialize_number!(self, visitor, visit_f64, f64, |value: &str| value.parse().ok().or_else(|| resolve::resolve_float(value)))
     }
 
     fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
         match self.parser.next_token() {
-            Ok((Event::Scalar(value, TScalarStyle::Plain, ..), marker), ..) => {
-                if value.len() != 1 {
-                    Err(Errors::unexpected_scalar_value_error("String with length 1", &value, marker).into())
-                } else {
-                    let Some(char) = value.chars().next() else {
-                        return Err(Errors::unexpected_scalar_value_error("String with single char", &value, marker).into());
-                    };
-                    visitor.visit_char(char)
+            Ok((Event::Scalar(value, ..), marker), ..) => {
+                let mut chars = value.chars();
+
+                match (chars.next(), chars.next()) {
+                    (Some(char), None) => visitor.visit_char(char),
+                    _ => {
+                        let error = Errors::unexpected_scalar_value_error("String with single char", &value, marker).into();
+                        self.recover(error, marker, None, || visitor.visit_char('\0'))
+                    },
                 }
             },
             Ok((event, marker)) => {
-                Err(Errors::unexpected_event_error("Plain scalar", event, marker).into())
+                let error = Errors::unexpected_event_error("Scalar", event.clone(), marker).into();
+                self.recover(error, marker, Some(&event), || visitor.visit_char('\0'))
             },
             Err(scan_error) => {
-                Err(Errors::scan_error(*scan_error.marker()).into())
+                Err(Errors::read_error(scan_error).into())
             },
         }
     }
 
-    fn deserialize_str<V>(self, _visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        Err(Self::Error::custom("Deserialization of &str is not supported"))
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        match self.parser.next_token() {
+            Ok((Event::Scalar(value, style, ..), marker), ..) => {
+                // borrow from the input if the scalar appears in it verbatim
+                match self.verbatim_scalar(&value, style, marker) {
+                    Some(borrowed) => visitor.visit_borrowed_str(borrowed),
+                    None => visitor.visit_string(value),
+                }
+            },
+            Ok((event, marker)) => {
+                let error = Errors::unexpected_event_error("Scalar", event.clone(), marker).into();
+                self.recover(error, marker, Some(&event), || visitor.visit_str(""))
+            },
+            Err(scan_error) => {
+                Err(Errors::read_error(scan_error).into())
+            },
+        }
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
         match self.parser.next_token() {
             Ok((Event::Scalar(value, ..), ..), ..) => {
-                return visitor.visit_string(value);
+                visitor.visit_string(value)
             },
             Ok((event, marker)) => {
-                Err(Errors::unexpected_event_error("Scalar", event, marker).into())
+                let error = Errors::unexpected_event_error("Scalar", event.clone(), marker).into();
+                self.recover(error, marker, Some(&event), || visitor.visit_string(String::new()))
             },
             Err(scan_error) => {
-                Err(Errors::scan_error(*scan_error.marker()).into())
+                Err(Errors::read_error(scan_error).into())
             },
         }
     }
 
-    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        Err(Self::Error::custom("Deserialization of bytes is not supported"))
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.deserialize_byte_buf(visitor)
     }
 
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        Err(Self::Error::custom("Deserialization of byte buffer is not supported"))
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        match self.parser.next_token() {
+            Ok((Event::Scalar(value, _, _, tag), marker)) if is_core_tag(&tag, "binary") => {
+                match binary::decode(&value) {
+                    Some(bytes) => visitor.visit_byte_buf(bytes),
+                    None => Err(Errors::invalid_tagged_scalar_error("!!binary", &value, marker).into()),
+                }
+            },
+            Ok((Event::Scalar(value, ..), ..)) => {
+                visitor.visit_byte_buf(value.into_bytes())
+            },
+            Ok((Event::SequenceStart(..), ..)) => {
+                let mut bytes = Vec::new();
+
+                loop {
+                    match self.parser.peek() {
+                        Ok((Event::SequenceEnd, ..)) => {
+                            self.parser.next_token().map_err(|e| Errors::read_error(e).into())?;
+                            break;
+                        },
+                        Ok(_) => {
+                            bytes.push(u8::deserialize(&mut *self)?);
+                        },
+                        Err(scan_error) => {
+                            return Err(Errors::read_error(scan_error).into());
+                        },
+                    }
+                }
+
+                visitor.visit_byte_buf(bytes)
+            },
+            Ok((event, marker)) => {
+                Err(Errors::unexpected_event_error("Scalar or SequenceStart", event, marker).into())
+            },
+            Err(scan_error) => {
+                Err(Errors::read_error(scan_error).into())
+            },
+        }
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        match self.parser.peek() {
-            Ok((Event::Scalar(value, ..), ..), ..) => {
-                if value == "null" || value == "~" {
-                    self.parser.next_token().map_err(|e| Errors::scan_error(*e.marker()).into())?;
+        match self.peek_kind()? {
+            NodeKind::DocumentEnd => {
+                visitor.visit_none()
+            },
+            NodeKind::Scalar { style: ScalarStyle::Plain, .. } => {
+                let schema = self.options.schema;
+                let null = matches!(self.parser.peek(), Ok((Event::Scalar(value, ..), ..)) if resolve::is_schema_null(value, schema));
+
+                // null of the schema, the same as in `deserialize_any`. A quoted "null" is a string. serde doesn't tell what the option contains, so null is `None` for nested options as well,
+                // see `with::double_option`
+                if null {
+                    self.parser.next_token().map_err(|e| Errors::read_error(e).into())?;
                     visitor.visit_none()
                 } else {
                     visitor.visit_some(self)
                 }
             },
-            Ok((event, marker)) => {
-                Err(Errors::unexpected_event_error("Scalar", event.clone(), *marker).into())
-            },
-            Err(scan_error) => {
-                Err(Errors::scan_error(*scan_error.marker()).into())
+            NodeKind::Scalar { .. } | NodeKind::Sequence { .. } | NodeKind::Mapping { .. } => {
+                visitor.visit_some(self)
             },
         }
     }
@@ -415,7 +1112,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut YamlDeserializer<'de> {
                 Err(Errors::unexpected_event_error("Scalar", event, marker).into())
             },
             Err(scan_error) => {
-                Err(Errors::scan_error(*scan_error.marker()).into())
+                Err(Errors::read_error(scan_error).into())
             },
         }
     }
@@ -424,14 +1121,42 @@ impl<'de, 'a> Deserializer<'de> for &'a mut YamlDeserializer<'de> {
         self.deserialize_unit(visitor)
     }
 
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        if name == crate::raw::TOKEN {
+            let Some(input) = self.input else {
+                return Err(Self::Error::custom("RawYaml can only be deserialized by a deserializer created from a string"));
+            };
+
+            let block = match self.parser.peek() {
+                Ok((Event::SequenceStart(..) | Event::MappingStart(..), marker)) => !input[self.offsets.byte_offset(input, marker.index())..].starts_with(['[', '{']),
+                _ => false,
+            };
+
+            // nodes inside of the raw node have a location as well
+            let mut positions = self.positions.take();
+            let range = self.raw_node(input, positions.as_mut())?;
+            self.positions = positions.map(|recorder| PositionRecorder { earliest: recorder.earliest.min(range.start), end: range.end, ..recorder });
+
+            return match block {
+                true => match raw::block_text(input, range) {
+                    Cow::Borrowed(text) => visitor.visit_borrowed_str(text),
+                    Cow::Owned(text) => visitor.visit_string(text),
+                },
+                false => visitor.visit_borrowed_str(&input[range]),
+            };
+        }
+
         visitor.visit_newtype_struct(self)
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        if self.at_document_end() || self.skip_null_collection()? {
+            return visitor.visit_seq(EmptyAccess {});
+        }
+
         match self.parser.next_token() {
-            Ok((Event::SequenceStart(..), ..), ..) => {
-                let value = visitor.visit_seq(EventsSequenceAccess { deserializer: self })?;
+            Ok((Event::SequenceStart(..), marker), ..) => {
+                let value = visitor.visit_seq(EventsSequenceAccess { deserializer: self, fields: &[], index: 0 }).map_err(|e| with_position(e, Some(marker)))?;
 
                 if let Ok((Event::SequenceEnd, ..), ..) = self.parser.next_token() {
                     Ok(value)
@@ -439,27 +1164,8 @@ impl<'de, 'a> Deserializer<'de> for &'a mut YamlDeserializer<'de> {
                     Err(Self::Error::custom("Expected SequenceEnd"))
                 }
             },
-            Ok((event, marker)) => {
-                Err(Errors::unexpected_event_error("SequenceStart", event, marker).into())
-            },
-            Err(scan_error) => {
-                Err(Errors::scan_error(*scan_error.marker()).into())
-            },
-        }
-    }
-
-    fn deserialize_tuple<V>(self, _visitor: usize, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        self.deserialize_seq(visitor)
-    }
-
-    fn deserialize_tuple_struct<V>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        self.deserialize_seq(visitor)
-    }
-
-    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        match self.parser.next_token() {
-            Ok((Event::MappingStart(..), ..), ..) => {
-                let value = visitor.visit_map(EventsSequenceAccess { deserializer: self })?;
+            Ok((Event::MappingStart(_, tag), marker), ..) if is_core_tag(&tag, "set") || self.options.untagged_sets => {
+                let value = visitor.visit_seq(SetAccess { deserializer: self, index: 0 }).map_err(|e| with_position(e, Some(marker)))?;
 
                 match self.parser.next_token() {
                     Ok((Event::MappingEnd, ..), ..) => {
@@ -469,27 +1175,64 @@ impl<'de, 'a> Deserializer<'de> for &'a mut YamlDeserializer<'de> {
                         Err(Errors::unexpected_event_error("MappingEnd", event, marker).into())
                     },
                     Err(scan_error) => {
-                        Err(Errors::scan_error(*scan_error.marker()).into())
+                        Err(Errors::read_error(scan_error).into())
                     },
                 }
             },
             Ok((event, marker)) => {
-                Err(Errors::unexpected_event_error("MappingStart", event, marker).into())
+                let error = Errors::unexpected_event_error("SequenceStart", event.clone(), marker).into();
+                self.recover(error, marker, Some(&event), || visitor.visit_seq(EmptyAccess {}))
             },
             Err(scan_error) => {
-                Err(Errors::scan_error(*scan_error.marker()).into())
+                Err(Errors::read_error(scan_error).into())
             },
         }
     }
 
-    fn deserialize_struct<V>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        self.deserialize_map(visitor)
+    fn deserialize_tuple<V>(self, _visitor: usize, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.deserialize_mapping(visitor, &[])
     }
 
-    fn deserialize_enum<V>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+    fn deserialize_struct<V>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.deserialize_mapping(visitor, fields)
+    }
+
+    fn deserialize_enum<V>(self, _name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        // a local tag names the variant of the node, e.g. `!Ref Bucket`. The tag is removed once read, the value
+        // of a newtype variant may be an enum itself, which is then written as a mapping, e.g. `!Outer {Inner: 1}`.
+        let tag = match self.peek_kind() {
+            Ok(NodeKind::Scalar { tag: Some(tag), .. } | NodeKind::Sequence { tag: Some(tag) } | NodeKind::Mapping { tag: Some(tag) }) if tag.handle == "!" => {
+                self.peek_marker()
+            },
+            _ => None,
+        };
+
+        if let Some(marker) = tag {
+            let suffix = self.parser.take_tag().map(|tag| tag.suffix).unwrap_or_default();
+            let name = self.variant_name(&suffix, variants, marker)?.to_string();
+            return visitor.visit_enum(TaggedVariantAccess { deserializer: self, name }).map_err(|e| with_position(e, Some(marker)));
+        }
+
         match self.parser.next_token() {
-            Ok((Event::MappingStart(..), ..), ..) => {
-                let value = visitor.visit_enum(EventsSequenceAccess { deserializer: self })?;
+            Ok((Event::Scalar(value, ..), marker), ..) => {
+                // bare variant name is a unit variant
+                let name = self.variant_name(&value, variants, marker)?;
+                visitor.visit_enum(name.into_deserializer())
+            },
+            Ok((Event::MappingStart(..), marker), ..) => {
+                self.enum_variants = variants;
+                let value = visitor.visit_enum(EventsSequenceAccess { deserializer: self, fields: &[], index: 0 }).map_err(|e| with_position(e, Some(marker)));
+                // the visitor may fail before it reads the variant
+                self.enum_variants = &[];
+                let value = value?;
 
                 if let Ok((Event::MappingEnd, ..), ..) = self.parser.next_token() {
                     Ok(value)
@@ -501,16 +1244,53 @@ impl<'de, 'a> Deserializer<'de> for &'a mut YamlDeserializer<'de> {
                 Err(Errors::unexpected_event_error("MappingStart", event, marker).into())
             },
             Err(scan_error) => {
-                Err(Errors::scan_error(*scan_error.marker()).into())
+                Err(Errors::read_error(scan_error).into())
             },
         }
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        self.deserialize_string(visitor)
+        let variant_identifier = std::mem::take(&mut self.variant_identifier);
+        let variants = std::mem::take(&mut self.enum_variants);
+
+        // field and variant visitors only need to compare the name, keys which are slices of the input are borrowed
+        match self.parser.next_token() {
+            Ok((Event::Scalar(value, style, ..), marker), ..) => {
+                match value.parse::<u64>() {
+                    // variants may be addressed by their index, unless a variant is named by the number
+                    Ok(index) if variant_identifier && style == TScalarStyle::Plain && index < variants.len() as u64 && !variants.contains(&value.as_str()) => {
+                        visitor.visit_u64(index)
+                    },
+                    _ if variant_identifier => visitor.visit_str(self.variant_name(&value, variants, marker)?),
+                    _ => match self.verbatim_scalar(&value, style, marker) {
+                        Some(borrowed) => visitor.visit_borrowed_str(borrowed),
+                        None => visitor.visit_str(&value),
+                    },
+                }
+            },
+            Ok((event, marker)) => {
+                Err(Errors::unexpected_event_error("Scalar", event, marker).into())
+            },
+            Err(scan_error) => {
+                Err(Errors::read_error(scan_error).into())
+            },
+        }
     }
 
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        if let Some((key, marker, index)) = self.ignored_value.take() {
+            let is_value_of_key = matches!(self.parser.peek(), Ok((_, value_marker)) if value_marker.index() == index);
+
+            if let (true, Some(mut callback)) = (is_value_of_key, self.ignored_key_callback.take()) {
+                callback(&key, marker);
+
+                // keys inside of the ignored value are not reported
+                let result = self.deserialize_any(visitor);
+                self.ignored_key_callback = Some(callback);
+                return result;
+            }
+        }
+
         self.deserialize_any(visitor)
     }
 }
@@ -518,7 +1298,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut YamlDeserializer<'de> {
 
 #[cfg(test)]
 mod tests {
-    use crate::de::YamlDeserializer;
+    use crate::de::{DeserializerOptions, YamlDeserializer};
     use serde::{Deserialize};
     use yaml_rust2::Yaml;
     use crate::wrapper::YamlNodeWrapper;
@@ -540,6 +1320,10 @@ mod tests {
         test!((), (), "~");
 
         test!(char, 'a', "a");
+        test!(char, 'ü', "ü");
+        test!(char, '#', "'#'");
+        test!(char, ' ', "\" \"");
+        assert!(crate::from_str::<char>("ab").is_err());
 
         test!(i8,  123, "123");
         test!(i16, 123, "123");
@@ -550,6 +1334,8 @@ mod tests {
         test!(u32, 123, "123");
         test!(u64, 123, "123");
         test!(i64, -123, "-123");
+        test!(u128, u128::MAX, "340282366920938463463374607431768211455");
+        test!(i128, i128::MIN, "-170141183460469231731687303715884105728");
 
         test!(f64, 123.341, "123.341");
         test!(f64, -123.341 ,"-123.341");
@@ -559,6 +1345,10 @@ mod tests {
 
         test!(f64, 0.0, "0");
         test!(f64, 0.0, "-0");
+        test!(f64, f64::INFINITY, ".inf");
+        test!(f64, f64::NEG_INFINITY, "-.Inf");
+        test!(f32, f32::INFINITY, "+.INF");
+        assert!(crate::from_str::<f64>(".nan").unwrap().is_nan());
 
         test!(bool, true, "true");
         test!(bool, false, "false");
@@ -689,4 +1479,1179 @@ VariantD:
             data: YamlNodeWrapper::new(Yaml::Array(vec![Yaml::String("Hello".to_owned()), Yaml::String("world".to_owned())])),
         }, "kind: Test\ndata: [ 'Hello', 'world' ]");
     }
+
+    #[test]
+    fn empty_document() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestOptionalStruct {
+            x: Option<i32>,
+            #[serde(default)]
+            y: String,
+        }
+
+        type Map = std::collections::HashMap<String, String>;
+
+        for data in ["", "  \n\n  ", "# only a comment\n# and another one\n"] {
+            let deserializer = &mut YamlDeserializer::from_str(data).unwrap();
+            assert_eq!(Map::new(), Map::deserialize(deserializer).unwrap());
+
+            let deserializer = &mut YamlDeserializer::from_str(data).unwrap();
+            assert_eq!(Vec::<i32>::new(), Vec::<i32>::deserialize(deserializer).unwrap());
+
+            let deserializer = &mut YamlDeserializer::from_str(data).unwrap();
+            assert_eq!(TestOptionalStruct { x: None, y: String::new() }, TestOptionalStruct::deserialize(deserializer).unwrap());
+        }
+    }
+
+    #[test]
+    fn null_as_empty_collections() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestCollections {
+            items: Vec<String>,
+            labels: std::collections::HashMap<String, String>,
+        }
+
+        for data in ["items:\nlabels:\n", "items: ~\nlabels: ~\n", "items: null\nlabels: null\n"] {
+            let deserializer = &mut YamlDeserializer::from_str(data).unwrap();
+            assert!(TestCollections::deserialize(deserializer).is_err());
+
+            let options = DeserializerOptions::new().null_as_empty_collections(true);
+            let deserializer = &mut YamlDeserializer::from_str_with_options(data, options).unwrap();
+            assert_eq!(TestCollections { items: vec![], labels: Default::default() }, TestCollections::deserialize(deserializer).unwrap());
+        }
+    }
+
+    #[test]
+    fn null_as_default() {
+        use crate::de::{from_str, from_str_with_options};
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestDefaults {
+            #[serde(default)]
+            replicas: u32,
+            #[serde(default = "default_name")]
+            name: String,
+            #[serde(default)]
+            ports: Vec<u16>,
+            image: String,
+            tag: Option<String>,
+        }
+
+        fn default_name() -> String {
+            String::from("web")
+        }
+
+        let data = "replicas: null
+name: ~
+ports:
+image: nginx
+tag: null
+";
+        let options = DeserializerOptions::new().null_as_default(true);
+
+        assert!(from_str::<TestDefaults>(data).is_err());
+        assert_eq!(
+            TestDefaults { replicas: 0, name: String::from("web"), ports: vec![], image: String::from("nginx"), tag: None },
+            from_str_with_options(data, options.clone()).unwrap()
+        );
+
+        // values which are not null, maps and fields without default are not affected
+        let data = "replicas: 3
+name: api
+ports: [80]
+image: nginx
+tag: '1.25'
+";
+        assert_eq!(
+            TestDefaults { replicas: 3, name: String::from("api"), ports: vec![80], image: String::from("nginx"), tag: Some(String::from("1.25")) },
+            from_str_with_options(data, options.clone()).unwrap()
+        );
+
+        let map: std::collections::HashMap<String, Option<u32>> = from_str_with_options("a: null
+b: 1
+", options.clone()).unwrap();
+        assert_eq!(2, map.len());
+
+        let error = from_str_with_options::<TestDefaults>("image: null
+", options.clone()).unwrap_err().to_string();
+        assert_eq!("missing field `image` at position Line: 1, Column: 6, Index: 5", error);
+
+        // only entries of known fields are skipped
+        #[derive(Deserialize, Debug)]
+        #[serde(deny_unknown_fields)]
+        struct TestStrict {
+            #[serde(default)]
+            #[allow(dead_code)]
+            replicas: u32,
+        }
+
+        assert!(from_str_with_options::<TestStrict>("replicas: null", options.clone()).is_ok());
+        let error = from_str_with_options::<TestStrict>("replicas: null\nreplica: null", options).unwrap_err().to_string();
+        assert!(error.starts_with("unknown field `replica`"), "{error}");
+    }
+
+    #[test]
+    fn document_markers() {
+        use crate::de::{from_str, from_str_multi};
+
+        assert_eq!(123, from_str::<i32>("123\n...\n").unwrap());
+        assert_eq!(vec![1, 2, 3], from_str::<Vec<i32>>("[1, 2, 3]\n...\n").unwrap());
+        assert_eq!(123, from_str::<i32>("---\n123\n").unwrap());
+        assert_eq!(123, from_str::<i32>("--- 123\n").unwrap());
+
+        let error = from_str::<String>("first doc\n--- second doc\n").unwrap_err().to_string();
+        assert_eq!("Multiple documents found, second document starts at position Line: 2, Column: 1, Index: 10. Use from_str_multi to deserialize all of them", error);
+
+        assert_eq!(vec![String::from("first doc"), String::from("second doc")], from_str_multi::<String>("first doc\n--- second doc\n").unwrap());
+        assert_eq!(vec![1, 2], from_str_multi::<i32>("--- 1\n...\n--- 2\n...\n").unwrap());
+        assert_eq!(Vec::<i32>::new(), from_str_multi::<i32>("").unwrap());
+    }
+
+    #[test]
+    fn directives() {
+        use crate::de::from_str;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            x: i32,
+            y: String,
+            z: YamlNodeWrapper,
+        }
+
+        let result: TestStruct = from_str("%YAML 1.2\n%TAG ! tag:example.com,2024:\n%TAG !e! tag:example.com,2024:e/\n---\nx: 1\ny: !local Hello\nz: !e!wrapped [1, 2]\n").unwrap();
+        assert_eq!(TestStruct { x: 1, y: String::from("Hello"), z: YamlNodeWrapper::new(Yaml::Array(vec![Yaml::Integer(1), Yaml::Integer(2)])) }, result);
+
+        assert!(from_str::<TestStruct>("%YAML 1.2\n%YAML 1.2\n---\nx: 1\n").is_err());
+    }
+
+    #[test]
+    fn nested_options() {
+        use crate::de::from_str;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            #[serde(default, with = "crate::with::double_option")]
+            x: Option<Option<i32>>,
+            y: Option<Vec<i32>>,
+        }
+
+        test!(Option<Option<i32>>, None, "~");
+        test!(Option<Option<i32>>, Some(Some(5)), "5");
+
+        for (expected, data) in [
+            (TestStruct { x: None, y: None }, "y: ~"),
+            (TestStruct { x: Some(None), y: None }, "x: ~\ny: ~"),
+            (TestStruct { x: Some(Some(5)), y: Some(vec![1, 2]) }, "x: 5\ny: [1, 2]"),
+        ] {
+            assert_eq!(expected, from_str::<TestStruct>(data).unwrap());
+        }
+    }
+
+    #[test]
+    fn quoted_null_into_option() {
+        use crate::de::from_str;
+        use crate::ser::to_string;
+
+        test!(Option<String>, Some(String::from("null")), "'null'");
+        test!(Option<String>, Some(String::from("~")), "\"~\"");
+        test!(Option<String>, None, "null");
+
+        for value in [Some(String::from("null")), Some(String::from("~")), None] {
+            assert_eq!(value, from_str::<Option<String>>(&to_string(&value).unwrap()).unwrap());
+        }
+    }
+
+    #[test]
+    fn schema_null_into_option() {
+        use crate::de::{from_str_with_options, DeserializerOptions, Schema};
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            x: Option<i32>,
+        }
+
+        test!(TestStruct, TestStruct { x: None }, "x: Null");
+        test!(TestStruct, TestStruct { x: None }, "x: NULL");
+        test!(TestStruct, TestStruct { x: None }, "x:");
+        test!(Option<String>, Some(String::from("nULL")), "nULL");
+
+        // null is the null of the schema, like in `deserialize_any`
+        let options = DeserializerOptions::new().schema(Schema::Json);
+        assert_eq!(None, from_str_with_options::<Option<i32>>("null", options.clone()).unwrap());
+        assert_eq!(Some(String::from("Null")), from_str_with_options::<Option<String>>("Null", options).unwrap());
+        let options = DeserializerOptions::new().schema(Schema::Failsafe);
+        assert_eq!(Some(String::from("~")), from_str_with_options::<Option<String>>("~", options).unwrap());
+    }
+
+    #[test]
+    fn empty_document_into_option() {
+        use crate::de::from_str;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestConfig {
+            x: i32,
+        }
+
+        for data in ["", "---\n", "---\n...\n", "# only a comment\n"] {
+            assert_eq!(None, from_str::<Option<TestConfig>>(data).unwrap());
+        }
+
+        assert_eq!(Some(TestConfig { x: 1 }), from_str::<Option<TestConfig>>("---\nx: 1\n").unwrap());
+    }
+
+    #[test]
+    fn unit_variant_without_value() {
+        use crate::de::from_str;
+        use crate::ser::to_string;
+        use serde::Serialize;
+
+        #[derive(Deserialize, Serialize, Debug, PartialEq)]
+        enum TestEnum {
+            VariantA,
+            VariantB(i32),
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            action: TestEnum,
+            next: i32,
+        }
+
+        for data in ["VariantA:", "VariantA: ~", "VariantA: null", "VariantA", "VariantA: !!null"] {
+            assert_eq!(TestEnum::VariantA, from_str::<TestEnum>(data).unwrap());
+        }
+
+        assert_eq!(TestEnum::VariantA, from_str::<TestEnum>(&to_string(TestEnum::VariantA).unwrap()).unwrap());
+        assert!(from_str::<TestEnum>("VariantA: 1").is_err());
+        assert!(from_str::<TestEnum>("VariantB").is_err());
+
+        for data in ["action: VariantA\nnext: 1", "action:\n  VariantA:\nnext: 1", "action: {VariantA: }\nnext: 1"] {
+            assert_eq!(TestStruct { action: TestEnum::VariantA, next: 1 }, from_str::<TestStruct>(data).unwrap());
+        }
+    }
+
+    #[test]
+    fn renamed_identifiers() {
+        use std::collections::BTreeMap;
+        use crate::de::from_str;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            #[serde(rename = "first-name")]
+            first_name: String,
+            #[serde(alias = "surname")]
+            last_name: String,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum TestEnum {
+            #[serde(rename = "variant-a")]
+            VariantA,
+            #[serde(alias = "b")]
+            VariantB(i32),
+        }
+
+        let expected = TestStruct { first_name: String::from("John"), last_name: String::from("Doe") };
+        assert_eq!(expected, from_str::<TestStruct>("first-name: John\nlast_name: Doe").unwrap());
+        assert_eq!(expected, from_str::<TestStruct>("first-name: John\nsurname: Doe").unwrap());
+        assert!(from_str::<TestStruct>("first_name: John\nlast_name: Doe").is_err());
+
+        assert_eq!(TestEnum::VariantA, from_str::<TestEnum>("variant-a: ~").unwrap());
+        assert_eq!(TestEnum::VariantB(1), from_str::<TestEnum>("b: 1").unwrap());
+        assert_eq!(TestEnum::VariantB(1), from_str::<TestEnum>("VariantB: 1").unwrap());
+
+        // keys which appear in the input verbatim are borrowed, e.g. by the keys of a flattened map
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Labels<'a> {
+            name: &'a str,
+            #[serde(flatten, borrow)]
+            rest: BTreeMap<&'a str, i32>,
+        }
+
+        let labels = from_str::<Labels>("name: web\napp: 1\n'tier': 2").unwrap();
+        assert_eq!(Labels { name: "web", rest: BTreeMap::from([("app", 1), ("tier", 2)]) }, labels);
+        assert!(from_str::<Labels>("name: web\n\"a\\tb\": 1").is_err());
+    }
+
+    #[test]
+    fn sequence_iterator() {
+        use crate::de::seq_iter;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestRecord {
+            id: i32,
+        }
+
+        let result: Vec<TestRecord> = seq_iter("- id: 1\n- id: 2\n- id: 3\n").unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(vec![TestRecord { id: 1 }, TestRecord { id: 2 }, TestRecord { id: 3 }], result);
+
+        assert_eq!(0, seq_iter::<TestRecord>("").unwrap().count());
+        assert_eq!(0, seq_iter::<TestRecord>("[]").unwrap().count());
+        assert!(seq_iter::<TestRecord>("id: 1").is_err());
+
+        let mut iter = seq_iter::<TestRecord>("- id: 1\n- id: x\n- id: 3\n").unwrap();
+        assert_eq!(TestRecord { id: 1 }, iter.next().unwrap().unwrap());
+        let error = iter.next().unwrap().unwrap_err().to_string();
+        assert!(error.starts_with("Error in sequence element 1 at position Line: 2"), "{error}");
+        assert!(iter.next().is_none());
+
+        let mut iter = seq_iter::<TestRecord>("- id: 1\n- id: 2\n").unwrap();
+        assert_eq!(TestRecord { id: 1 }, iter.next().unwrap().unwrap());
+        drop(iter);
+
+        let mut iter = seq_iter::<i32>("[1, 2]\n--- [3]").unwrap();
+        assert_eq!(1, iter.next().unwrap().unwrap());
+        assert_eq!(2, iter.next().unwrap().unwrap());
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn stream_deserializer() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestDocument {
+            kind: String,
+        }
+
+        let mut iter = YamlDeserializer::from_str("kind: Pod\n---\nkind: Service\n...\n--- {kind: Secret}\n").unwrap().into_iter::<TestDocument>();
+        assert_eq!(None, iter.end_marker().map(|m| m.index()));
+        assert_eq!(TestDocument { kind: String::from("Pod") }, iter.next().unwrap().unwrap());
+        assert_eq!(Some(10), iter.end_marker().map(|m| m.index()));
+        assert_eq!(TestDocument { kind: String::from("Service") }, iter.next().unwrap().unwrap());
+        assert_eq!(Some(28), iter.end_marker().map(|m| m.index()));
+        assert_eq!(TestDocument { kind: String::from("Secret") }, iter.next().unwrap().unwrap());
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+
+        assert_eq!(0, YamlDeserializer::from_str("").unwrap().into_iter::<TestDocument>().count());
+
+        let mut iter = YamlDeserializer::from_str("kind: Pod\n---\nname: Service\n---\nkind: Secret\n").unwrap().into_iter::<TestDocument>();
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+        assert_eq!(Some(10), iter.end_marker().map(|m| m.index()));
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        use crate::de::from_str;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            literal: String,
+            folded: String,
+            quoted: String,
+            plain: String,
+            list: Vec<i32>,
+        }
+
+        let lf = "literal: |\n  line 1\n  line 2\nfolded: >\n  line 1\n  line 2\n\n  line 3\nquoted: \"line 1\n  line 2\"\nplain: line 1\n  line 2\nlist:\n  - 1\n  - 2\n";
+        let crlf = lf.replace('\n', "\r\n");
+
+        let expected = TestStruct {
+            literal: String::from("line 1\nline 2\n"),
+            folded: String::from("line 1 line 2\nline 3\n"),
+            quoted: String::from("line 1 line 2"),
+            plain: String::from("line 1 line 2"),
+            list: vec![1, 2],
+        };
+        assert_eq!(expected, from_str::<TestStruct>(lf).unwrap());
+        assert_eq!(expected, from_str::<TestStruct>(&crlf).unwrap());
+
+        let lf_error = from_str::<Vec<i32>>("- 1\n- 2\n- [3]\n").unwrap_err().to_string();
+        let crlf_error = from_str::<Vec<i32>>("- 1\r\n- 2\r\n- [3]\r\n").unwrap_err().to_string();
+        assert!(lf_error.contains("Line: 3, Column: 3"), "{lf_error}");
+        assert!(crlf_error.contains("Line: 3, Column: 3"), "{crlf_error}");
+    }
+
+    #[test]
+    fn lenient_numbers() {
+        use crate::de::from_str_with_options;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            port: u16,
+            ratio: f64,
+        }
+
+        let data = "port: \"8080\"\nratio: '0.5'";
+        assert!(from_str_with_options::<TestStruct>(data, DeserializerOptions::new()).is_err());
+
+        let options = DeserializerOptions::new().lenient_numbers(true);
+        assert_eq!(TestStruct { port: 8080, ratio: 0.5 }, from_str_with_options::<TestStruct>(data, options.clone()).unwrap());
+        assert_eq!(TestStruct { port: 8080, ratio: 0.5 }, from_str_with_options::<TestStruct>("port: 8080\nratio: 0.5", options.clone()).unwrap());
+
+        assert_eq!(
+            "Error while parsing scalar http into u16 at position Line: 1, Column: 7, Index: 6. Not a number",
+            from_str_with_options::<TestStruct>("port: \"http\"\nratio: 0.5", options).unwrap_err().to_string(),
+        );
+    }
+
+    #[test]
+    fn lenient_booleans() {
+        use crate::de::from_str_with_options;
+
+        let strict = DeserializerOptions::new();
+        let lenient = DeserializerOptions::new().lenient_booleans(true);
+
+        for (expected, data) in [(true, "true"), (false, "false")] {
+            assert_eq!(expected, from_str_with_options::<bool>(data, strict.clone()).unwrap());
+            assert_eq!(expected, from_str_with_options::<bool>(data, lenient.clone()).unwrap());
+        }
+
+        for (expected, data) in [(true, "1"), (false, "0"), (true, "'true'"), (false, "\"false\"")] {
+            assert!(from_str_with_options::<bool>(data, strict.clone()).is_err());
+            assert_eq!(expected, from_str_with_options::<bool>(data, lenient.clone()).unwrap());
+        }
+
+        let error = from_str_with_options::<bool>("2", lenient.clone()).unwrap_err().to_string();
+        assert_eq!("Unexpected scalar value at position Line: 1, Column: 1, Index: 0. Expected: 'true', 'false', 1 or 0, got: 2", error);
+        assert!(from_str_with_options::<bool>("'1'", lenient).is_err());
+    }
+
+    #[test]
+    fn core_schema_resolution() {
+        let string = |v: &str| YamlNodeWrapper::new(Yaml::String(String::from(v)));
+
+        test!(YamlNodeWrapper, YamlNodeWrapper::new(Yaml::Real(String::from("inf"))), ".inf");
+        test!(YamlNodeWrapper, YamlNodeWrapper::new(Yaml::Real(String::from("-inf"))), "-.Inf");
+        test!(YamlNodeWrapper, YamlNodeWrapper::new(Yaml::Boolean(false)), "FALSE");
+        test!(YamlNodeWrapper, YamlNodeWrapper::new(Yaml::Null), "NULL");
+        test!(YamlNodeWrapper, YamlNodeWrapper::new(Yaml::Integer(8)), "08");
+        test!(YamlNodeWrapper, string("NO"), "NO");
+        test!(YamlNodeWrapper, string("Off"), "Off");
+        test!(YamlNodeWrapper, string("inf"), "inf");
+        test!(YamlNodeWrapper, string("0b101"), "0b101");
+    }
+
+    #[test]
+    fn ignored_keys() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestNested {
+            x: i32,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            name: String,
+            nested: TestNested,
+            list: Vec<TestNested>,
+        }
+
+        let mut ignored = vec![];
+        let result = {
+            let mut deserializer = YamlDeserializer::from_str("name: test\ncolor: red\nnested:\n  x: 1\n  y: [1, 2]\nlist:\n  - x: 2\n    z: {a: 1}\n").unwrap();
+            deserializer.on_ignored_key(|key, marker| ignored.push((key.to_owned(), marker.line(), marker.col())));
+            TestStruct::deserialize(&mut deserializer).unwrap()
+        };
+
+        assert_eq!(TestStruct { name: String::from("test"), nested: TestNested { x: 1 }, list: vec![TestNested { x: 2 }] }, result);
+        assert_eq!(vec![(String::from("color"), 2, 0), (String::from("y"), 5, 2), (String::from("z"), 8, 4)], ignored);
+    }
+
+    #[test]
+    fn complex_keys() {
+        use crate::de::from_str;
+        use std::collections::HashMap;
+
+        #[derive(Deserialize, Debug, PartialEq, Eq, Hash)]
+        struct TestKey {
+            x: i32,
+            y: i32,
+        }
+
+        let result: HashMap<(String, String), i32> = from_str("? [a, b]\n: 1\n? - c\n  - d\n: 2\n").unwrap();
+        assert_eq!(HashMap::from([((String::from("a"), String::from("b")), 1), ((String::from("c"), String::from("d")), 2)]), result);
+
+        let result: HashMap<TestKey, String> = from_str("? {x: 1, y: 2}\n: first\n? x: 3\n  y: 4\n: second\n").unwrap();
+        assert_eq!(HashMap::from([(TestKey { x: 1, y: 2 }, String::from("first")), (TestKey { x: 3, y: 4 }, String::from("second"))]), result);
+
+        let result: HashMap<(i32, i32), Option<i32>> = from_str("? [1, 2]\n? [3, 4]\n: 5\n").unwrap();
+        assert_eq!(HashMap::from([((1, 2), None), ((3, 4), Some(5))]), result);
+
+        let result: HashMap<YamlNodeWrapper, i32> = from_str("? [a, b]\n: 1\n? {x: 1}\n: 2\nplain: 3\n").unwrap();
+        assert_eq!(HashMap::from([
+            (YamlNodeWrapper::new(Yaml::Array(vec![Yaml::String(String::from("a")), Yaml::String(String::from("b"))])), 1),
+            (YamlNodeWrapper::new(Yaml::Hash([(Yaml::String(String::from("x")), Yaml::Integer(1))].into_iter().collect())), 2),
+            (YamlNodeWrapper::new(Yaml::String(String::from("plain"))), 3),
+        ]), result);
+    }
+
+    #[test]
+    fn variant_index() {
+        use crate::de::from_str;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum TestEnum {
+            VariantA(i32),
+            VariantB { x: i32 },
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            first: i32,
+        }
+
+        assert_eq!(TestEnum::VariantB { x: 2 }, from_str::<TestEnum>("1: {x: 2}").unwrap());
+        assert_eq!(TestEnum::VariantA(3), from_str::<TestEnum>("0: 3").unwrap());
+        assert_eq!(TestEnum::VariantB { x: 2 }, from_str::<TestEnum>("VariantB: {x: 2}").unwrap());
+        assert!(from_str::<TestEnum>("2: {x: 2}").is_err());
+
+        // a variant named by a number is matched by its name, other numbers out of range are names as well
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Numbered {
+            #[serde(rename = "7")]
+            Seven,
+            #[serde(rename = "8")]
+            Eight(i32),
+            #[serde(rename = "0")]
+            Zero(i32),
+        }
+
+        assert_eq!(Numbered::Seven, from_str::<Numbered>("7").unwrap());
+        assert_eq!(Numbered::Eight(3), from_str::<Numbered>("8: 3").unwrap());
+        assert_eq!(Numbered::Zero(3), from_str::<Numbered>("0: 3").unwrap());
+        assert_eq!(Numbered::Zero(3), from_str::<Numbered>("2: 3").unwrap());
+        assert!(from_str::<Numbered>("9: 3").unwrap_err().to_string().starts_with("unknown variant `9`"));
+
+        // struct fields are still matched by name only
+        assert!(from_str::<TestStruct>("0: 1").is_err());
+
+        // the variant is read as a string, the flag of the variant identifier doesn't reach the payload
+        use std::fmt::Formatter;
+        use serde::de::{EnumAccess, VariantAccess, Visitor};
+
+        #[derive(Debug, PartialEq)]
+        struct Named(String, TestStruct);
+
+        impl<'de> Deserialize<'de> for Named {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct NamedVisitor;
+
+                impl<'de> Visitor<'de> for NamedVisitor {
+                    type Value = Named;
+
+                    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                        formatter.write_str("a named struct")
+                    }
+
+                    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Named, A::Error> {
+                        let (name, variant) = data.variant::<String>()?;
+                        variant.newtype_variant().map(|value| Named(name, value))
+                    }
+                }
+
+                deserializer.deserialize_enum("Named", &[], NamedVisitor)
+            }
+        }
+
+        assert_eq!(Named(String::from("web"), TestStruct { first: 1 }), from_str::<Named>("web: {first: 1}").unwrap());
+        assert!(from_str::<Named>("web: {0: 1}").is_err());
+    }
+
+    #[test]
+    fn sets() {
+        use crate::de::{from_str, from_str_with_options};
+        use std::collections::{BTreeSet, HashSet};
+
+        let expected = BTreeSet::from([String::from("a"), String::from("b")]);
+        assert_eq!(expected, from_str::<BTreeSet<String>>("--- !!set\n? a\n? b\n").unwrap());
+        assert_eq!(expected, from_str::<BTreeSet<String>>("!!set {a, b: ~}").unwrap());
+        assert_eq!(HashSet::from([1, 2]), from_str::<HashSet<i32>>("!!set\n? 1\n? 2\n").unwrap());
+        assert!(from_str::<BTreeSet<String>>("!!set {a: 1}").is_err());
+
+        assert!(from_str::<BTreeSet<String>>("? a\n? b\n").is_err());
+        let options = DeserializerOptions::new().untagged_sets(true);
+        assert_eq!(expected, from_str_with_options::<BTreeSet<String>>("? a\n? b\n", options.clone()).unwrap());
+        assert_eq!(expected, from_str_with_options::<BTreeSet<String>>("- a\n- b\n", options).unwrap());
+    }
+
+    #[test]
+    fn tagged_scalars() {
+        use crate::de::from_str;
+
+        let string = |v: &str| YamlNodeWrapper::new(Yaml::String(String::from(v)));
+
+        test!(YamlNodeWrapper, string("123"), "!!str 123");
+        test!(YamlNodeWrapper, string("true"), "!!str true");
+        test!(YamlNodeWrapper, string("~"), "!!str ~");
+        test!(YamlNodeWrapper, string("123"), "'123'");
+        test!(YamlNodeWrapper, string("true"), "\"true\"");
+        test!(YamlNodeWrapper, YamlNodeWrapper::new(Yaml::Integer(42)), "!!int \"42\"");
+        test!(YamlNodeWrapper, YamlNodeWrapper::new(Yaml::Integer(16)), "!!int 0x10");
+        test!(YamlNodeWrapper, YamlNodeWrapper::new(Yaml::Real(String::from("42"))), "!!float 42");
+        test!(YamlNodeWrapper, YamlNodeWrapper::new(Yaml::Real(String::from("0.5"))), "!!float '0.5'");
+        test!(YamlNodeWrapper, YamlNodeWrapper::new(Yaml::Boolean(true)), "!!bool 'true'");
+        test!(YamlNodeWrapper, YamlNodeWrapper::new(Yaml::Null), "!!null ''");
+        test!(YamlNodeWrapper, YamlNodeWrapper::new(Yaml::Integer(7)), "!custom 7");
+
+        let error = from_str::<std::collections::HashMap<String, YamlNodeWrapper>>("x: 1\ny: !!int abc").unwrap_err().to_string();
+        assert!(error.contains("Scalar abc at position Line: 2, Column: 10"), "{error}");
+        assert!(error.contains("can not be interpreted as !!int"), "{error}");
+        let error = from_str::<YamlNodeWrapper>("\n!!float this scalar is longer than thirty two characters").unwrap_err().to_string();
+        assert_eq!("Scalar this scalar is longer than thirt... at position Line: 2, Column: 9, Index: 9 can not be interpreted as !!float", error);
+        assert!(from_str::<YamlNodeWrapper>("!!bool yes").is_err());
+        assert!(from_str::<YamlNodeWrapper>("!!null value").is_err());
+    }
+
+    #[test]
+    fn bytes() {
+        use crate::de::from_str;
+        use serde_bytes::ByteBuf;
+
+        test!(ByteBuf, ByteBuf::from(vec![1, 2, 255]), "[1, 2, 255]");
+        test!(ByteBuf, ByteBuf::from(vec![1, 2, 3]), "- 1\n- 2\n- 3");
+        test!(ByteBuf, ByteBuf::from(vec![]), "[]");
+        test!(ByteBuf, ByteBuf::from(b"hello".to_vec()), "hello");
+        test!(ByteBuf, ByteBuf::from(b"123".to_vec()), "'123'");
+        test!(ByteBuf, ByteBuf::from(b"hello".to_vec()), "!!binary aGVsbG8=");
+        test!(ByteBuf, ByteBuf::from(b"hello world".to_vec()), "!!binary |\n  aGVsbG8g\n  d29ybGQ=");
+
+        assert!(from_str::<ByteBuf>("[1, 256]").is_err());
+        assert_eq!(
+            "Scalar aGVsbG8 at position Line: 1, Column: 10, Index: 9 can not be interpreted as !!binary",
+            from_str::<ByteBuf>("!!binary aGVsbG8").unwrap_err().to_string(),
+        );
+        assert!(from_str::<ByteBuf>("{a: 1}").is_err());
+    }
+
+    #[test]
+    fn aliases() {
+        use crate::de::{from_str, from_str_with_options, DeserializerOptions};
+        use serde::de::IgnoredAny;
+        use std::collections::HashMap;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Config {
+            defaults: HashMap<String, u32>,
+            production: HashMap<String, u32>,
+            ports: Vec<u16>,
+            backup: Vec<u16>,
+        }
+
+        let expected = Config {
+            defaults: HashMap::from([(String::from("timeout"), 30), (String::from("retries"), 3)]),
+            production: HashMap::from([(String::from("timeout"), 30), (String::from("retries"), 3)]),
+            ports: vec![80, 3],
+            backup: vec![80, 3],
+        };
+        test!(Config, expected, "
+defaults: &defaults
+  timeout: 30
+  retries: &retries 3
+production: *defaults
+ports: &ports [80, *retries]
+backup: *ports
+");
+
+        let error = from_str::<Vec<Vec<u8>>>("- &a [1, *a]").unwrap_err().to_string();
+        assert!(error.contains("refers to an anchor which is undefined or contains the alias"), "{error}");
+
+        let laughs = "
+a: &a [lol, lol, lol, lol, lol, lol, lol, lol, lol]
+b: &b [*a, *a, *a, *a, *a, *a, *a, *a, *a]
+c: &c [*b, *b, *b, *b, *b, *b, *b, *b, *b]
+d: &d [*c, *c, *c, *c, *c, *c, *c, *c, *c]
+e: &e [*d, *d, *d, *d, *d, *d, *d, *d, *d]
+f: &f [*e, *e, *e, *e, *e, *e, *e, *e, *e]
+g: &g [*f, *f, *f, *f, *f, *f, *f, *f, *f]
+h: &h [*g, *g, *g, *g, *g, *g, *g, *g, *g]
+i: &i [*h, *h, *h, *h, *h, *h, *h, *h, *h]
+";
+        let error = from_str::<HashMap<String, IgnoredAny>>(laughs).unwrap_err().to_string();
+        assert!(error.contains("Alias expansion limit of 1000000 exceeded at position Line: 8"), "{error}");
+
+        let options = DeserializerOptions::new().max_alias_events(None).max_nodes(Some(1000));
+        let error = from_str_with_options::<HashMap<String, IgnoredAny>>(laughs, options).unwrap_err().to_string();
+        assert!(error.contains("Node count limit of 1000 exceeded"), "{error}");
+
+        let options = DeserializerOptions::new().max_alias_events(Some(2)).max_nodes(None);
+        assert!(from_str_with_options::<Vec<u8>>("[&x 1, *x, *x]", options.clone()).is_ok());
+        assert!(from_str_with_options::<Vec<u8>>("[&x 1, *x, *x, *x]", options).is_err());
+    }
+
+    #[test]
+    fn visitor_error_positions() {
+        use crate::de::from_str;
+
+        #[derive(Deserialize, Debug)]
+        #[serde(deny_unknown_fields)]
+        #[allow(dead_code)]
+        struct Inner {
+            id: i64,
+            name: String,
+            address: Option<std::net::Ipv4Addr>,
+        }
+
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Outer {
+            items: Vec<Inner>,
+        }
+
+        let error = from_str::<Outer>("items:\n  - id: 1\n    name: a\n  - id: 2\n    name: b\n    address: localhost\n").unwrap_err().to_string();
+        assert_eq!("invalid IPv4 address syntax at position Line: 6, Column: 14, Index: 64", error);
+
+        let error = from_str::<Outer>("items:\n  - id: 1\n    name: a\n  - name: b\n").unwrap_err().to_string();
+        assert!(error.starts_with("missing field `id` at position Line: 4"), "{error}");
+
+        let error = from_str::<Outer>("items:\n  - id: 1\n    nmae: a\n").unwrap_err().to_string();
+        assert!(error.starts_with("unknown field `nmae`, expected one of `id`, `name`, `address` at position Line: 3, Column: 5"), "{error}");
+
+        // errors which already have a position are not wrapped again
+        let error = from_str::<Outer>("items:\n  - id: x\n").unwrap_err().to_string();
+        assert_eq!(1, error.matches("position").count(), "{error}");
+    }
+
+    #[test]
+    fn borrowed_strings() {
+        use crate::de::from_str;
+        use std::borrow::Cow;
+
+        #[derive(Deserialize, Debug)]
+        struct Record<'a> {
+            #[serde(borrow)]
+            plain: Cow<'a, str>,
+            #[serde(borrow)]
+            single: Cow<'a, str>,
+            #[serde(borrow)]
+            double: Cow<'a, str>,
+            #[serde(borrow)]
+            escaped: Cow<'a, str>,
+            #[serde(borrow)]
+            doubled_quote: Cow<'a, str>,
+            #[serde(borrow)]
+            literal: Cow<'a, str>,
+            name: &'a str,
+        }
+
+        let record: Record = from_str("
+plain: Hello world
+single: 'it is'
+double: \"quoted\"
+escaped: \"line\\nbreak\"
+doubled_quote: 'it''s'
+literal: |
+  text
+name: ünïcode
+").unwrap();
+
+        assert!(matches!(record.plain, Cow::Borrowed("Hello world")), "{:?}", record.plain);
+        assert!(matches!(record.single, Cow::Borrowed("it is")), "{:?}", record.single);
+        assert!(matches!(record.double, Cow::Borrowed("quoted")), "{:?}", record.double);
+        assert!(matches!(record.escaped, Cow::Owned(ref v) if v == "line\nbreak"), "{:?}", record.escaped);
+        assert!(matches!(record.doubled_quote, Cow::Owned(ref v) if v == "it's"), "{:?}", record.doubled_quote);
+        assert!(matches!(record.literal, Cow::Owned(ref v) if v == "text\n"), "{:?}", record.literal);
+        assert_eq!("ünïcode", record.name);
+    }
+
+    #[test]
+    fn borrowed_strings_in_large_input() {
+        // every scalar is borrowed at the byte offset of its marker, see `ByteOffsets`
+        let yaml: String = (0..80_000).map(|i| format!("- ünïcode {i}\n")).collect();
+        let items: Vec<&str> = crate::de::from_str(&yaml).unwrap();
+
+        assert_eq!(80_000, items.len());
+        assert_eq!("ünïcode 79999", items[79_999]);
+    }
+
+    #[test]
+    fn from_parser() {
+        use crate::RawYaml;
+        use std::collections::HashMap;
+        use yaml_rust2::parser::Parser;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Record {
+            name: String,
+            tags: Vec<String>,
+        }
+
+        let deserializer = &mut YamlDeserializer::from_iter("name: Alice\ntags: [a, b]".chars()).unwrap();
+        let record = Record::deserialize(&mut *deserializer).unwrap();
+        deserializer.end().unwrap();
+        assert_eq!(Record { name: String::from("Alice"), tags: vec![String::from("a"), String::from("b")] }, record);
+
+        let lowercase = "NAME: ALICE\nTAGS: [A, B]".chars().flat_map(char::to_lowercase);
+        let deserializer = &mut YamlDeserializer::from_iter(lowercase).unwrap();
+        assert_eq!(Record { name: String::from("alice"), tags: vec![String::from("a"), String::from("b")] }, Record::deserialize(deserializer).unwrap());
+
+        let parser = Parser::new("%TAG !e! tag:example.com,2024:\n---\nx: !e!point 1".chars()).keep_tags(true);
+        let deserializer = &mut YamlDeserializer::from_parser(parser).unwrap();
+        assert_eq!(HashMap::from([(String::from("x"), 1)]), HashMap::<String, u8>::deserialize(deserializer).unwrap());
+
+        // the source text is only known when deserializing from a string
+        let deserializer = &mut YamlDeserializer::from_iter("x: 1".chars()).unwrap();
+        assert!(RawYaml::deserialize(deserializer).is_err());
+    }
+
+    #[test]
+    fn json_value() {
+        use crate::de::from_str;
+        use serde_json::{json, Value};
+
+        let yaml = "
+name: demo
+version: 1.5
+count: 3
+enabled: true
+nothing: ~
+empty:
+quoted: '42'
+'123': numeric key
+7: plain numeric key
+true: boolean key
+nested:
+  list:
+    - 1
+    - two
+    - [3, [4, {five: 5}]]
+    - {six: 6, seven: [7]}
+    - null
+  deeper:
+    map: {a: {b: {c: []}}}
+    empty_map: {}
+anchored: &anchor [x, y]
+aliased: *anchor
+block: |
+  line 1
+  line 2
+";
+
+        let value: Value = from_str(yaml).unwrap();
+
+        assert_eq!(json!({
+            "name": "demo",
+            "version": 1.5,
+            "count": 3,
+            "enabled": true,
+            "nothing": null,
+            "empty": null,
+            "quoted": "42",
+            "123": "numeric key",
+            "7": "plain numeric key",
+            "true": "boolean key",
+            "nested": {
+                "list": [1, "two", [3, [4, {"five": 5}]], {"six": 6, "seven": [7]}, null],
+                "deeper": {
+                    "map": {"a": {"b": {"c": []}}},
+                    "empty_map": {},
+                },
+            },
+            "anchored": ["x", "y"],
+            "aliased": ["x", "y"],
+            "block": "line 1\nline 2\n",
+        }), value);
+
+        assert_eq!(json!([1, null, "a"]), from_str::<Value>("[1, ~, a]").unwrap());
+        assert_eq!(json!(null), from_str::<Value>("").unwrap());
+        assert_eq!(json!("text"), from_str::<Value>("text").unwrap());
+    }
+
+    #[test]
+    fn schemas() {
+        use crate::de::{from_str_with_options, DeserializerOptions, Schema};
+        use serde_json::{json, Value};
+
+        let yaml = "[null, ~, true, True, 12, 012, 0x1F, 1.5, .5, text, !!int '7']";
+        let resolve = |schema| from_str_with_options::<Value>(yaml, DeserializerOptions::new().schema(schema)).unwrap();
+
+        assert_eq!(json!([null, null, true, true, 12, 12, 31, 1.5, 0.5, "text", 7]), resolve(Schema::Core));
+        assert_eq!(json!([null, "~", true, "True", 12, "012", "0x1F", 1.5, ".5", "text", 7]), resolve(Schema::Json));
+        assert_eq!(json!(["null", "~", "true", "True", "12", "012", "0x1F", "1.5", ".5", "text", 7]), resolve(Schema::Failsafe));
+        assert_eq!(json!([null, null, true, true, 12, 10, 31, 1.5, 0.5, "text", 7]), resolve(Schema::Yaml11));
+        assert_eq!(json!({"country": false}), from_str_with_options::<Value>("country: NO", DeserializerOptions::new().schema(Schema::Yaml11)).unwrap());
+        assert_eq!(json!({"country": "NO"}), from_str_with_options::<Value>("country: NO", DeserializerOptions::new()).unwrap());
+    }
+
+    #[test]
+    fn case_insensitive_enums() {
+        use crate::de::{from_str, from_str_with_options, DeserializerOptions};
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Level {
+            Debug,
+            Info,
+            Custom { name: String },
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Ambiguous {
+            Value,
+            #[serde(rename = "VALUE")]
+            Loud,
+        }
+
+        let options = || DeserializerOptions::new().case_insensitive_enums(true);
+
+        assert_eq!(Level::Debug, from_str_with_options::<Level>("debug", options()).unwrap());
+        assert_eq!(Level::Debug, from_str_with_options::<Level>("DEBUG", options()).unwrap());
+        assert_eq!(Level::Info, from_str_with_options::<Level>("info: ~", options()).unwrap());
+        assert_eq!(Level::Custom { name: String::from("x") }, from_str_with_options::<Level>("custom: {name: x}", options()).unwrap());
+        assert_eq!(vec![Level::Info, Level::Debug], from_str_with_options::<Vec<Level>>("[Info, deBug]", options()).unwrap());
+
+        let error = from_str_with_options::<Level>("trace", options()).unwrap_err().to_string();
+        assert!(error.starts_with("unknown variant `trace`, expected one of `Debug`, `Info`, `Custom`"), "{error}");
+
+        assert!(from_str::<Level>("debug").is_err());
+
+        assert_eq!(Ambiguous::Loud, from_str_with_options::<Ambiguous>("VALUE", options()).unwrap());
+        let error = from_str_with_options::<Ambiguous>("value", options()).unwrap_err().to_string();
+        assert!(error.starts_with("Variant value at position Line: 1, Column: 1, Index: 0 matches more than one variant"), "{error}");
+    }
+
+    #[test]
+    fn restricted() {
+        use crate::de::{from_str_with_options, DeserializerOptions};
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Config {
+            name: String,
+            ports: Vec<u16>,
+        }
+
+        let options = || DeserializerOptions::new().restricted(true);
+        let error = |yaml| from_str_with_options::<Config>(yaml, options()).unwrap_err().to_string();
+
+        let expected = Config { name: String::from("app"), ports: vec![80, 443] };
+        assert_eq!(expected, from_str_with_options("name: !!str app\nports: !!seq [80, !!int 443]", options()).unwrap());
+
+        assert_eq!("Anchor at position Line: 2, Column: 11, Index: 20 is not allowed in restricted mode", error("name: app\nports: &p [80]"));
+        assert_eq!("Tag !custom at position Line: 1, Column: 15, Index: 14 is not allowed in restricted mode", error("name: !custom app\nports: []"));
+        assert_eq!("Tag tag:yaml.org,2002:binary at position Line: 1, Column: 16, Index: 15 is not allowed in restricted mode", error("name: !!binary YQ==\nports: []"));
+
+        // ignored values are checked as well
+        assert!(error("name: app\nports: []\nextra: {a: &x 1, b: *x}").starts_with("Anchor at position Line: 3"));
+        assert!(from_str_with_options::<Config>("name: app\nports: []\nextra: {a: &x 1, b: *x}", DeserializerOptions::new()).is_ok());
+    }
+
+    #[test]
+    fn positions() {
+        use crate::de::{from_str_with_positions, Position};
+
+        #[derive(Deserialize, Debug)]
+        struct Deployment {
+            spec: Spec,
+        }
+
+        #[derive(Deserialize, Debug)]
+        struct Spec {
+            replicas: u32,
+            containers: Vec<Container>,
+        }
+
+        #[derive(Deserialize, Debug)]
+        struct Container {
+            image: String,
+        }
+
+        let yaml = "spec:
+  replicas: 0
+  containers:
+    - image: nginx # web server
+      unknown: {ports: [80, 'ü 443']}
+    - image: \"redis\"
+";
+        let (deployment, positions) = from_str_with_positions::<Deployment>(yaml).unwrap();
+        assert_eq!(0, deployment.spec.replicas);
+        assert_eq!("redis", deployment.spec.containers[1].image);
+
+        let position = |line, column, index| Position { line, column, index };
+
+        assert_eq!(Some((position(2, 13, 18), position(2, 14, 19))), positions.get("/spec/replicas"));
+        assert_eq!(Some((position(2, 3, 8), position(2, 11, 16))), positions.key("/spec/replicas"));
+        assert_eq!(Some((position(4, 14, 47), position(4, 19, 52))), positions.get("/spec/containers/0/image"));
+        assert_eq!(Some((position(6, 14, 117), position(6, 21, 124))), positions.get("/spec/containers/1/image"));
+        assert_eq!(Some((position(4, 5, 38), position(6, 21, 124))), positions.get("/spec/containers"));
+
+        // ignored values, the index counts characters
+        assert_eq!(Some((position(5, 29, 94), position(5, 36, 101))), positions.get("/spec/containers/0/unknown/ports/1"));
+        assert_eq!(Some((position(5, 16, 81), position(5, 38, 103))), positions.get("/spec/containers/0/unknown"));
+
+        assert_eq!(Some((position(1, 1, 0), position(6, 21, 124))), positions.get(""));
+        assert_eq!(None, positions.get("/spec/containers/2"));
+        assert_eq!(12, positions.iter().count());
+
+        assert!(from_str_with_positions::<Option<Deployment>>("").unwrap().1.get("").is_none());
+
+        // keys are escaped, so they can't be mistaken for nested keys
+        let yaml = "a.b: 1\na: {b: 2}\nc/d~: [x, {e: y}]\n";
+        let (_, positions) = from_str_with_positions::<YamlNodeWrapper>(yaml).unwrap();
+        assert_eq!(Some((position(1, 6, 5), position(1, 7, 6))), positions.get("/a.b"));
+        assert_eq!(Some((position(2, 8, 14), position(2, 9, 15))), positions.get("/a/b"));
+        assert_eq!(Some((position(3, 7, 23), position(3, 18, 34))), positions.get("/c~1d~0"));
+        assert_eq!(Some((position(3, 15, 31), position(3, 16, 32))), positions.get("/c~1d~0/1/e"));
+        assert_eq!(Some((position(3, 1, 17), position(3, 5, 21))), positions.key("/c~1d~0"));
+        assert_eq!(None, positions.get("/a.b/c"));
+    }
+
+    #[test]
+    fn validate() {
+        use crate::de::validate;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(deny_unknown_fields)]
+        struct Service {
+            name: String,
+            enabled: bool,
+            ports: Vec<u16>,
+            limits: Limits,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Limits {
+            cpus: u8,
+            memory: u64,
+        }
+
+        let yaml = "name: web
+enabled: maybe
+ports: [80, 70000, 443]
+replica: 3
+limits:
+  cpus: 2
+  memory: 512
+";
+        let errors: Vec<String> = validate::<Service>(yaml).unwrap_err().iter().map(ToString::to_string).collect();
+        assert_eq!(vec![
+            "Unexpected scalar value at position Line: 2, Column: 10, Index: 19. Expected: 'true' or 'false', got: maybe",
+            "Error while parsing scalar 70000 into u16 at position Line: 3, Column: 13, Index: 37. Number out of range",
+            "unknown field `replica`, expected one of `name`, `enabled`, `ports`, `limits` at position Line: 4, Column: 1, Index: 49",
+        ], errors);
+
+        let service = validate::<Service>("name: web\nenabled: true\nports: []\nlimits: {cpus: 1, memory: 2}").unwrap();
+        assert_eq!(Limits { cpus: 1, memory: 2 }, service.limits);
+
+        // scan errors stop at the first error
+        let errors = validate::<Service>("name: web\nenabled: maybe\nports: [80\n").unwrap_err();
+        assert_eq!(2, errors.len());
+        assert!(errors[1].to_string().starts_with("Scan error"), "{}", errors[1]);
+
+        // a default which can't be deserialized stops as well
+        assert_eq!(1, validate::<Service>("name: web\nenabled: true\nports: []\nlimits: 5").unwrap_err().len());
+
+        // values of the wrong type are recovered from while reading, the first unknown key needs one more run
+        thread_local!(static RUNS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) });
+
+        #[derive(Debug)]
+        struct Counted;
+
+        impl<'de> Deserialize<'de> for Counted {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                RUNS.with(|runs| runs.set(runs.get() + 1));
+                Vec::<Service>::deserialize(deserializer).map(|_| Counted)
+            }
+        }
+
+        let service = "- name: web\n  enabled: maybe\n  ports: [x, 70000]\n  replica: 3\n  limits: {cpus: 1, memory: many}\n";
+        let errors = validate::<Counted>(&service.repeat(3)).unwrap_err();
+        assert_eq!(15, errors.len());
+        assert_eq!(2, RUNS.with(|runs| runs.get()));
+    }
+
+    #[test]
+    fn peek_kind() {
+        use std::collections::HashMap;
+        use crate::de::{NodeKind, NodeTag};
+        use crate::ScalarStyle;
+
+        let deserializer = &mut YamlDeserializer::from_str("'quoted'").unwrap();
+        assert_eq!(NodeKind::Scalar { style: ScalarStyle::SingleQuoted, tag: None }, deserializer.peek_kind().unwrap());
+        assert_eq!(NodeKind::Scalar { style: ScalarStyle::SingleQuoted, tag: None }, deserializer.peek_kind().unwrap());
+        assert_eq!("quoted", String::deserialize(&mut *deserializer).unwrap());
+        assert_eq!(NodeKind::DocumentEnd, deserializer.peek_kind().unwrap());
+
+        let tag = Some(NodeTag { handle: String::from("tag:yaml.org,2002:"), suffix: String::from("str") });
+        assert_eq!(NodeKind::Scalar { style: ScalarStyle::Plain, tag }, YamlDeserializer::from_str("!!str 5").unwrap().peek_kind().unwrap());
+        assert_eq!(NodeKind::DocumentEnd, YamlDeserializer::from_str("# empty").unwrap().peek_kind().unwrap());
+
+        #[derive(Debug, PartialEq)]
+        enum Source {
+            Path(String),
+            Inline(HashMap<String, String>),
+            Paths(Vec<String>),
+        }
+
+        let source = |yaml| {
+            let deserializer = &mut YamlDeserializer::from_str(yaml).unwrap();
+
+            match deserializer.peek_kind().unwrap() {
+                NodeKind::Mapping { .. } => Source::Inline(HashMap::deserialize(deserializer).unwrap()),
+                NodeKind::Sequence { .. } => Source::Paths(Vec::deserialize(deserializer).unwrap()),
+                _ => Source::Path(String::deserialize(deserializer).unwrap()),
+            }
+        };
+
+        assert_eq!(Source::Path(String::from("config.yaml")), source("config.yaml"));
+        assert_eq!(Source::Inline(HashMap::from([(String::from("a"), String::from("1"))])), source("a: 1"));
+        assert_eq!(Source::Paths(vec![String::from("a.yaml"), String::from("b.yaml")]), source("- a.yaml\n- b.yaml"));
+    }
+
+    #[test]
+    fn tagged_enum_variants() {
+        use std::collections::BTreeMap;
+        use crate::de::{from_str, from_str_with_options};
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Intrinsic {
+            Ref(String),
+            GetAtt(Vec<String>),
+            Sub(String),
+            Join(String, Vec<Intrinsic>),
+            FindInMap { map: String, key: String },
+            NoValue,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "PascalCase")]
+        struct Resource {
+            #[serde(rename = "Type")]
+            kind: String,
+            properties: BTreeMap<String, Intrinsic>,
+        }
+
+        let yaml = "
+Bucket:
+  Type: AWS::S3::Bucket
+  Properties:
+    BucketName: !Sub \"${AWS::StackName}-data\"
+    Owner: !Ref OwnerParameter
+    Arn: !GetAtt [Bucket, Arn]
+    Path: !Join ['/', [!Ref Prefix, !Sub '${Name}']]
+    Region: !FindInMap
+      map: Regions
+      key: eu-west-1
+    Policy: !NoValue
+    Legacy:
+      Ref: Plain
+";
+        let resources: BTreeMap<String, Resource> = from_str(yaml).unwrap();
+        let properties = &resources["Bucket"].properties;
+
+        assert_eq!("AWS::S3::Bucket", resources["Bucket"].kind);
+        assert_eq!(Intrinsic::Sub(String::from("${AWS::StackName}-data")), properties["BucketName"]);
+        assert_eq!(Intrinsic::Ref(String::from("OwnerParameter")), properties["Owner"]);
+        assert_eq!(Intrinsic::GetAtt(vec![String::from("Bucket"), String::from("Arn")]), properties["Arn"]);
+        assert_eq!(Intrinsic::Join(String::from("/"), vec![Intrinsic::Ref(String::from("Prefix")), Intrinsic::Sub(String::from("${Name}"))]), properties["Path"]);
+        assert_eq!(Intrinsic::FindInMap { map: String::from("Regions"), key: String::from("eu-west-1") }, properties["Region"]);
+        assert_eq!(Intrinsic::NoValue, properties["Policy"]);
+        assert_eq!(Intrinsic::Ref(String::from("Plain")), properties["Legacy"]);
+
+        let error = from_str::<Intrinsic>("!If [a, b]").unwrap_err().to_string();
+        assert!(error.starts_with("unknown variant `If`, expected one of"), "{error}");
+        assert!(error.ends_with("at position Line: 1, Column: 5, Index: 4"), "{error}");
+
+        let options = DeserializerOptions::new().case_insensitive_enums(true);
+        assert_eq!(Intrinsic::Ref(String::from("x")), from_str_with_options::<Intrinsic>("!ref x", options).unwrap());
+
+        // the tag names the outer variant alone, an enum in its value is read from the untagged value
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Wrapper {
+            Wrapped(Intrinsic),
+        }
+
+        assert_eq!(Wrapper::Wrapped(Intrinsic::Sub(String::from("x"))), from_str::<Wrapper>("!Wrapped {Sub: x}").unwrap());
+        let wrapped: Vec<Wrapper> = from_str("- !Wrapped {Ref: a}\n- !Wrapped NoValue").unwrap();
+        assert_eq!(vec![Wrapper::Wrapped(Intrinsic::Ref(String::from("a"))), Wrapper::Wrapped(Intrinsic::NoValue)], wrapped);
+    }
 }
@@ -0,0 +1,123 @@
+/// Options that tune how lenient [`YamlDeserializer`](super::YamlDeserializer) is.
+///
+/// Every leniency option is off by default, which keeps the deserializer strict. Aliases are expanded within
+/// generous limits which protect against documents whose aliases expand exponentially ("billion laughs").
+#[derive(Debug, Clone)]
+pub struct DeserializerOptions {
+    pub(crate) null_as_empty_collections: bool,
+    pub(crate) null_as_default: bool,
+    pub(crate) lenient_numbers: bool,
+    pub(crate) lenient_booleans: bool,
+    pub(crate) untagged_sets: bool,
+    pub(crate) case_insensitive_enums: bool,
+    pub(crate) max_alias_events: Option<usize>,
+    pub(crate) max_nodes: Option<usize>,
+    pub(crate) restricted: bool,
+    pub(crate) schema: Schema,
+}
+
+/// Schema which resolves the type of untagged plain scalars in self-describing deserialization, e.g. into
+/// [`YamlNodeWrapper`](crate::wrapper::YamlNodeWrapper). Explicitly tagged scalars are resolved by their tag.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Schema {
+    /// Every plain scalar is a string
+    Failsafe,
+    /// Only `null`, `true`, `false` and JSON numbers are resolved, other plain scalars are strings
+    Json,
+    /// YAML 1.2 core schema, which also resolves e.g. `~`, `True`, `0x1F` and `.inf`
+    #[default]
+    Core,
+    /// Types of YAML 1.1 for documents written for older tools, which also resolve e.g. `yes`, `off` and `y` as
+    /// booleans and `010` as octal. `country: NO` becomes `false`, so prefer [`Schema::Core`] where possible.
+    Yaml11,
+}
+
+impl Default for DeserializerOptions {
+    fn default() -> Self {
+        DeserializerOptions {
+            null_as_empty_collections: false,
+            null_as_default: false,
+            lenient_numbers: false,
+            lenient_booleans: false,
+            untagged_sets: false,
+            case_insensitive_enums: false,
+            max_alias_events: Some(1_000_000),
+            max_nodes: Some(10_000_000),
+            restricted: false,
+            schema: Schema::Core,
+        }
+    }
+}
+
+impl DeserializerOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deserialize a null value (`~`, `null` or a key without value) into an empty sequence or map
+    /// instead of failing, e.g. `items:` into `Vec<T>`.
+    pub fn null_as_empty_collections(mut self, value: bool) -> Self {
+        self.null_as_empty_collections = value;
+        self
+    }
+
+    /// Treat a struct field whose value is null as missing, so `#[serde(default)]` applies to it, e.g.
+    /// `replicas: null` into a `u32` field. A null field without default is reported as missing.
+    pub fn null_as_default(mut self, value: bool) -> Self {
+        self.null_as_default = value;
+        self
+    }
+
+    /// Accept quoted scalars for numeric types and parse their contents, e.g. `port: "8080"` into `u16`
+    pub fn lenient_numbers(mut self, value: bool) -> Self {
+        self.lenient_numbers = value;
+        self
+    }
+
+    /// Accept `1`/`0` and quoted `'true'`/`'false'` scalars for booleans
+    pub fn lenient_booleans(mut self, value: bool) -> Self {
+        self.lenient_booleans = value;
+        self
+    }
+
+    /// Deserialize a mapping without `!!set` tag into a sequence of its keys when its values are null,
+    /// e.g. `? a` into `BTreeSet<String>`
+    pub fn untagged_sets(mut self, value: bool) -> Self {
+        self.untagged_sets = value;
+        self
+    }
+
+    /// Match enum variant names ignoring case, e.g. `debug` and `DEBUG` for `Debug`. A name which matches several
+    /// variants only by ignoring case is an error.
+    pub fn case_insensitive_enums(mut self, value: bool) -> Self {
+        self.case_insensitive_enums = value;
+        self
+    }
+
+    /// Maximum number of events replayed from aliases in a document, `None` disables the limit.
+    /// Defaults to 1 000 000.
+    pub fn max_alias_events(mut self, value: Option<usize>) -> Self {
+        self.max_alias_events = value;
+        self
+    }
+
+    /// Maximum number of nodes in a document, counting nodes replayed from aliases. `None` disables the limit.
+    /// Defaults to 10 000 000.
+    pub fn max_nodes(mut self, value: Option<usize>) -> Self {
+        self.max_nodes = value;
+        self
+    }
+
+    /// Reject documents with anchors, aliases or tags other than `!!str`, `!!int`, `!!float`, `!!bool`, `!!null`,
+    /// `!!seq`, `!!map` and `!!set`, e.g. for untrusted input. Also applies to values which are skipped.
+    pub fn restricted(mut self, value: bool) -> Self {
+        self.restricted = value;
+        self
+    }
+
+    /// Schema resolving untagged plain scalars, see [`Schema`]. Defaults to [`Schema::Core`].
+    pub fn schema(mut self, value: Schema) -> Self {
+        self.schema = value;
+        self
+    }
+}
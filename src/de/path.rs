@@ -0,0 +1,147 @@
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use serde::de::Error;
+use serde::Deserialize;
+use yaml_rust2::Event;
+use super::positions::Place;
+use super::{Errors, YamlDeserializer};
+
+/// Deserializes only the node at the path, e.g. `/spec/template/spec/containers/0/image`, skipping everything
+/// else without building it.
+///
+/// The path is a JSON pointer: segments start with `/` and are keys of mappings or indices of sequences,
+/// `~1` and `~0` stand for `/` and `~` in keys. The empty path is the whole document.
+pub fn from_str_path<'de, T: Deserialize<'de>>(data: &'de str, path: &str) -> Result<T, serde::de::value::Error> {
+    if !path.is_empty() && !path.starts_with('/') {
+        return Err(serde::de::value::Error::custom(format!("Invalid path {path}, it must be empty or start with /")));
+    }
+
+    let deserializer = &mut YamlDeserializer::from_str(data)?;
+    let mut end = 0;
+    // position of the node found last, i.e. the key or sequence element named by the previous segment
+    let mut position = deserializer.parser.peek().map(|(_, marker)| *marker).map_err(|e| Errors::read_error(e).into())?;
+
+    for raw_segment in path.split('/').skip(1) {
+        let found = match end {
+            0 => "the document",
+            _ => &path[..end],
+        };
+        end += raw_segment.len() + 1;
+
+        let segment = unescape(raw_segment);
+        let (event, _) = deserializer.parser.next_token().map_err(|e| Errors::read_error(e).into())?;
+        let not_found = || Errors::path_not_found_error(raw_segment, found, position).into();
+
+        match event {
+            Event::MappingStart(..) => loop {
+                let (key, marker) = deserializer.parser.next_token().map_err(|e| Errors::read_error(e).into())?;
+
+                match key {
+                    Event::MappingEnd => return Err(not_found()),
+                    Event::Scalar(ref value, ..) if *value == segment => {
+                        position = marker;
+                        break;
+                    },
+                    _ => {
+                        deserializer.skip_replayed_node(&key)?;
+                        deserializer.skip_node()?;
+                    },
+                }
+            },
+            Event::SequenceStart(..) => {
+                let index = segment.parse::<usize>().map_err(|_| not_found())?;
+
+                for _ in 0..index {
+                    if let Ok((Event::SequenceEnd, ..)) = deserializer.parser.peek() {
+                        return Err(not_found());
+                    }
+                    deserializer.skip_node()?;
+                }
+
+                match deserializer.parser.peek() {
+                    Ok((Event::SequenceEnd, ..)) => return Err(not_found()),
+                    Ok((_, marker)) => position = *marker,
+                    Err(_) => {},
+                }
+            },
+            _ => return Err(not_found()),
+        }
+    }
+
+    deserializer.deserialize_node(PhantomData::<T>, Place::Document)
+}
+
+fn unescape(segment: &str) -> Cow<'_, str> {
+    match segment.contains('~') {
+        true => Cow::Owned(segment.replace("~1", "/").replace("~0", "~")),
+        false => Cow::Borrowed(segment),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use serde::de::IgnoredAny;
+    use serde::Deserialize;
+    use super::from_str_path;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Container {
+        name: String,
+        image: String,
+    }
+
+    const MANIFEST: &str = r#"
+apiVersion: apps/v1
+metadata:
+  name: web
+  labels:
+    app.kubernetes.io/name: web
+spec:
+  replicas: 3
+  template:
+    spec:
+      containers:
+        - name: sidecar
+          image: envoy:1.29
+          args: [--config, /etc/envoy.yaml]
+        - &web
+          name: web
+          image: nginx:1.25
+      initContainers:
+        - *web
+"#;
+
+    #[test]
+    fn should_work() {
+        assert_eq!(3, from_str_path::<u32>(MANIFEST, "/spec/replicas").unwrap());
+        assert_eq!("nginx:1.25", from_str_path::<String>(MANIFEST, "/spec/template/spec/containers/1/image").unwrap());
+        assert_eq!("web", from_str_path::<String>(MANIFEST, "/metadata/labels/app.kubernetes.io~1name").unwrap());
+        assert_eq!(
+            Container { name: String::from("web"), image: String::from("nginx:1.25") },
+            from_str_path(MANIFEST, "/spec/template/spec/initContainers/0").unwrap()
+        );
+        assert_eq!(3, from_str_path::<HashMap<String, IgnoredAny>>(MANIFEST, "").unwrap().len());
+    }
+
+    #[test]
+    fn errors() {
+        let error = from_str_path::<u32>(MANIFEST, "/spec/replica").unwrap_err().to_string();
+        assert_eq!("Path segment replica not found in /spec at position Line: 7, Column: 1, Index: 85", error);
+
+        let error = from_str_path::<String>(MANIFEST, "/spec/template/spec/containers/2/image").unwrap_err().to_string();
+        assert_eq!("Path segment 2 not found in /spec/template/spec/containers at position Line: 11, Column: 7, Index: 133", error);
+
+        let error = from_str_path::<String>(MANIFEST, "/spec/replicas/count").unwrap_err().to_string();
+        assert_eq!("Path segment count not found in /spec/replicas at position Line: 8, Column: 3, Index: 93", error);
+
+        let error = from_str_path::<String>("", "/spec").unwrap_err().to_string();
+        assert!(error.starts_with("Path segment spec not found in the document at position Line: "), "{error}");
+
+        let error = from_str_path::<u32>(MANIFEST, "/metadata/name").unwrap_err().to_string();
+        assert!(error.contains("at position Line: 4, Column: 9"), "{error}");
+
+        let error = from_str_path::<String>(MANIFEST, "spec").unwrap_err().to_string();
+        assert_eq!("Invalid path spec, it must be empty or start with /", error);
+    }
+}
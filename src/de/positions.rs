@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+/// Place in the input. Line and column count from 1, the index counts characters from 0.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub index: usize,
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line: {}, Column: {}, Index: {}", self.line, self.column, self.index)
+    }
+}
+
+/// Source locations of the nodes of a document, returned by [`from_str_with_positions`](super::from_str_with_positions).
+///
+/// Nodes are looked up by their path, a JSON pointer: keys of mappings and indexes of sequence elements,
+/// each preceded by `/`, e.g. `/spec/containers/0/image`. As in a JSON pointer, `~` in a key is written
+/// as `~0` and `/` as `~1`. The document itself has the empty path. Every node has a location, including
+/// values which were ignored while deserializing. A location is the start and the (exclusive) end of the
+/// source text of the node, including its anchor and tag.
+#[derive(Debug, Clone, Default)]
+pub struct Positions {
+    values: HashMap<String, (Position, Position)>,
+    keys: HashMap<String, (Position, Position)>,
+}
+
+impl Positions {
+    /// Location of the node at the path
+    pub fn get(&self, path: &str) -> Option<(Position, Position)> {
+        self.values.get(path).copied()
+    }
+
+    /// Location of the key of the mapping entry at the path, e.g. `name` for `/spec/name`
+    pub fn key(&self, path: &str) -> Option<(Position, Position)> {
+        self.keys.get(path).copied()
+    }
+
+    /// Paths and locations of all nodes in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, (Position, Position))> {
+        self.values.iter().map(|(path, location)| (path.as_str(), *location))
+    }
+
+    pub(super) fn new(input: &str, recorder: PositionRecorder) -> Self {
+        let mut offsets: Vec<usize> = recorder.values.iter().chain(&recorder.keys).flat_map(|(_, range)| [range.start, range.end]).collect();
+        offsets.sort_unstable();
+        offsets.dedup();
+
+        let positions = positions_at(input, &offsets);
+        let location = |range: Range<usize>| (positions[&range.start], positions[&range.end]);
+
+        Positions {
+            values: recorder.values.into_iter().map(|(path, range)| (path, location(range))).collect(),
+            keys: recorder.keys.into_iter().map(|(path, range)| (path, location(range))).collect(),
+        }
+    }
+}
+
+/// Place of a node in the document, which gives the last segment of its path
+#[derive(Clone, Copy)]
+pub(super) enum Place {
+    Document,
+    Key,
+    Value,
+    Element(usize),
+    /// Name of the variant in a mapping of a variant name to its value, which is a key as far as paths go
+    Variant,
+}
+
+/// Byte ranges of the nodes and keys consumed while deserializing, by their path
+pub(super) struct PositionRecorder {
+    /// Path of the node which is consumed
+    pub(super) path: String,
+    /// Path of the value of the last consumed key
+    pub(super) key_path: String,
+    /// Earliest start of the nodes recorded since the current node started, the first key of a block mapping
+    /// starts before the mapping itself
+    pub(super) earliest: usize,
+    /// End of the last recorded node
+    pub(super) end: usize,
+    pub(super) values: Vec<(String, Range<usize>)>,
+    pub(super) keys: Vec<(String, Range<usize>)>,
+}
+
+impl Default for PositionRecorder {
+    fn default() -> Self {
+        PositionRecorder { path: String::new(), key_path: String::new(), earliest: usize::MAX, end: 0, values: vec![], keys: vec![] }
+    }
+}
+
+impl PositionRecorder {
+    /// Makes the child at the place the current node and returns the path of its parent
+    pub(super) fn enter(&mut self, place: Place) -> Option<String> {
+        let path = match place {
+            Place::Document => String::new(),
+            Place::Value => std::mem::take(&mut self.key_path),
+            Place::Element(index) => format!("{}/{index}", self.path),
+            // a key isn't the parent of anything, its path is only known once it is consumed
+            Place::Key | Place::Variant => return None,
+        };
+
+        Some(std::mem::replace(&mut self.path, path))
+    }
+
+    /// Records the consumed node, `name` is the text of a key
+    pub(super) fn leave(&mut self, place: Place, parent: Option<String>, range: Range<usize>, name: &str) {
+        match place {
+            Place::Key | Place::Variant => {
+                self.key_path = child_path(&self.path, name);
+                self.keys.push((self.key_path.clone(), range));
+            },
+            _ => self.values.push((self.path.clone(), range)),
+        }
+
+        if let Some(parent) = parent {
+            self.path = parent;
+        }
+    }
+}
+
+/// Path of the value of the key in the mapping at the path, with `~` and `/` escaped
+pub(super) fn child_path(path: &str, key: &str) -> String {
+    format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+/// Positions of the sorted byte offsets, counted in a single pass over the input
+fn positions_at(input: &str, offsets: &[usize]) -> HashMap<usize, Position> {
+    let mut result = HashMap::with_capacity(offsets.len());
+    let mut offsets = offsets.iter().peekable();
+    let mut position = Position { line: 1, column: 1, index: 0 };
+
+    for (offset, c) in input.char_indices().chain([(input.len(), '\0')]) {
+        while let Some(&&next) = offsets.peek() {
+            if next > offset {
+                break;
+            }
+            result.insert(next, position);
+            offsets.next();
+        }
+
+        position.index += 1;
+        if c == '\n' {
+            position.line += 1;
+            position.column = 1;
+        } else {
+            position.column += 1;
+        }
+    }
+
+    result
+}
@@ -0,0 +1,408 @@
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::ops::Range;
+use yaml_rust2::Event;
+use yaml_rust2::scanner::{Marker, TScalarStyle};
+use super::positions::{child_path, PositionRecorder};
+use super::{Errors, YamlDeserializer};
+
+impl<'de, I: Iterator<Item = char>> YamlDeserializer<'de, I> {
+    /// Same as [`raw_node`](Self::raw_node), also recording the range of the node itself
+    fn node_range(&mut self, input: &'de str, recorder: &mut PositionRecorder) -> Result<Range<usize>, serde::de::value::Error> {
+        let range = self.raw_node(input, Some(&mut *recorder))?;
+        recorder.values.push((recorder.path.clone(), range.clone()));
+
+        Ok(range)
+    }
+
+    /// Consumes the next node and returns the byte range of its source text, including its anchor and tag. The
+    /// ranges of the children of the node and their keys are recorded if there is a recorder.
+    pub(super) fn raw_node(&mut self, input: &'de str, mut recorder: Option<&mut PositionRecorder>) -> Result<Range<usize>, serde::de::value::Error> {
+        let (event, marker) = self.parser.next_token().map_err(|e| Errors::read_error(e).into())?;
+        let start = self.offsets.byte_offset(input, marker.index());
+
+        // events replayed for an alias carry its position, the source text is the alias itself
+        if input[start..].starts_with('*') {
+            self.skip_replayed_node(&event)?;
+            return Ok(start..alias_end(input, start));
+        }
+
+        match event {
+            Event::Scalar(value, style, ..) => {
+                let end = self.scalar_end(input, start, marker, &value, style)?;
+                Ok(properties_start(input, start)..end)
+            },
+            Event::SequenceStart(..) | Event::MappingStart(..) => {
+                let flow = input[start..].starts_with(['[', '{']);
+                let mapping = matches!(event, Event::MappingStart(..));
+                let mut node_start = start;
+                let mut end = start;
+                let mut index = 0;
+                let mut key = mapping;
+
+                loop {
+                    match self.parser.peek() {
+                        Ok((Event::SequenceEnd | Event::MappingEnd, marker)) => {
+                            if flow {
+                                end = self.offsets.byte_offset(input, marker.index()) + 1;
+                            }
+                            self.parser.next_token().map_err(|e| Errors::read_error(e).into())?;
+                            break;
+                        },
+                        Ok(_) => {
+                            let child = match recorder.as_deref_mut() {
+                                Some(recorder) => self.child_range(input, recorder, mapping, key, index)?,
+                                None => self.raw_node(input, None)?,
+                            };
+                            index += 1;
+                            key = mapping && !key;
+
+                            // the first key of a block mapping starts before the mapping itself
+                            node_start = node_start.min(child.start);
+                            end = child.end;
+                        },
+                        Err(scan_error) => {
+                            return Err(Errors::read_error(scan_error).into());
+                        },
+                    }
+                }
+
+                Ok(properties_start(input, node_start)..end)
+            },
+            event => {
+                Err(Errors::unexpected_event_error("Scalar, SequenceStart or MappingStart", event, marker).into())
+            },
+        }
+    }
+
+    /// Consumes a child of a collection, `index` is the index of an element in a sequence
+    fn child_range(&mut self, input: &'de str, recorder: &mut PositionRecorder, mapping: bool, key: bool, index: usize) -> Result<Range<usize>, serde::de::value::Error> {
+        if key {
+            let name = match self.parser.peek() {
+                Ok((Event::Scalar(value, ..), _)) => Some(value.clone()),
+                _ => None,
+            };
+
+            // a collection used as key is named by its source text
+            let range = self.raw_node(input, None)?;
+            let name = name.unwrap_or_else(|| input[range.clone()].trim().to_string());
+
+            recorder.key_path = child_path(&recorder.path, &name);
+            recorder.keys.push((recorder.key_path.clone(), range.clone()));
+
+            return Ok(range);
+        }
+
+        let path = match mapping {
+            true => std::mem::take(&mut recorder.key_path),
+            false => format!("{}/{index}", recorder.path),
+        };
+        let parent_path = std::mem::replace(&mut recorder.path, path);
+        let range = self.node_range(input, recorder);
+        recorder.path = parent_path;
+
+        range
+    }
+
+    /// Start of the source text of the node at the marker, including its anchor and tag
+    pub(super) fn node_start(&self, input: &str, marker: Marker) -> usize {
+        properties_start(input, self.offsets.byte_offset(input, marker.index()))
+    }
+
+    /// End of the source text of the node which was consumed last. `node` is the byte offset of its marker,
+    /// `last_child_end` the end of its last child, which is where a block collection ends.
+    pub(super) fn consumed_node_end(&mut self, input: &'de str, node: usize, last_child_end: usize) -> Result<usize, serde::de::value::Error> {
+        let Some((event, marker)) = self.parser.last_event().cloned() else {
+            return Ok(last_child_end);
+        };
+        let start = self.offsets.byte_offset(input, marker.index());
+
+        if input[node..].starts_with('*') {
+            return Ok(alias_end(input, node));
+        }
+
+        match event {
+            Event::Scalar(value, style, ..) => self.scalar_end(input, start, marker, &value, style),
+            Event::SequenceEnd | Event::MappingEnd if input[node..].starts_with(['[', '{']) => Ok(start + 1),
+            _ => Ok(last_child_end),
+        }
+    }
+
+    /// Returns the source text of a plain or quoted scalar if it is the same as its value, i.e. it contains no
+    /// escapes or line folding
+    pub(super) fn verbatim_scalar(&self, value: &str, style: TScalarStyle, marker: Marker) -> Option<&'de str> {
+        let input = self.input?;
+        let text = &input[self.offsets.byte_offset(input, marker.index())..];
+
+        let quote = match style {
+            TScalarStyle::Plain => return text.starts_with(value).then(|| &text[..value.len()]),
+            TScalarStyle::SingleQuoted => '\'',
+            TScalarStyle::DoubleQuoted => '"',
+            _ => return None,
+        };
+
+        let content = text.strip_prefix(quote)?;
+        let rest = content.strip_prefix(value)?;
+
+        // a doubled single quote is an escaped quote, not the end of the scalar
+        match rest.strip_prefix(quote) {
+            Some(after) if !(quote == '\'' && after.starts_with('\'')) => Some(&content[..value.len()]),
+            _ => None,
+        }
+    }
+
+    /// Consumes the next node
+    pub(super) fn skip_node(&mut self) -> Result<(), serde::de::value::Error> {
+        let (event, _) = self.parser.next_token().map_err(|e| Errors::read_error(e).into())?;
+        self.skip_replayed_node(&event)
+    }
+
+    /// Consumes the rest of the node which starts with the event
+    pub(super) fn skip_replayed_node(&mut self, event: &Event) -> Result<(), serde::de::value::Error> {
+        let mut depth = match event {
+            Event::SequenceStart(..) | Event::MappingStart(..) => 1,
+            _ => 0,
+        };
+
+        while depth > 0 {
+            match self.parser.next_token().map_err(|e| Errors::read_error(e).into())? {
+                (Event::SequenceStart(..) | Event::MappingStart(..), ..) => depth += 1,
+                (Event::SequenceEnd | Event::MappingEnd, ..) => depth -= 1,
+                _ => {},
+            }
+        }
+
+        Ok(())
+    }
+
+    fn scalar_end(&mut self, input: &'de str, start: usize, marker: Marker, value: &str, style: TScalarStyle) -> Result<usize, serde::de::value::Error> {
+        let text = &input[start..];
+
+        match style {
+            TScalarStyle::SingleQuoted | TScalarStyle::DoubleQuoted => {
+                // the parser already rejected unterminated quotes
+                return Ok(start + closing_quote(text, style).unwrap_or(text.len()));
+            },
+            // source text of a single line plain scalar is its value, an empty value has no source text
+            TScalarStyle::Plain if text.starts_with(value) => {
+                return Ok(start + value.len());
+            },
+            TScalarStyle::Plain if value == "~" => {
+                return Ok(start);
+            },
+            _ => {},
+        }
+
+        // multi line plain scalars and block scalars end before whatever follows them
+        let next = match self.parser.peek() {
+            Ok((_, next_marker)) => self.offsets.byte_offset(input, next_marker.index()),
+            Err(scan_error) => return Err(Errors::read_error(scan_error).into()),
+        };
+        let mut text = &input[start..next.max(start)];
+
+        match style {
+            TScalarStyle::Literal | TScalarStyle::Folded => {
+                // lines indented less than the content belong to the following node
+                while let Some(index) = text.trim_end().rfind('\n') {
+                    let line = &text[index + 1..];
+                    let indent = line.len() - line.trim_start().len();
+                    if indent >= marker.col() || line.trim().is_empty() {
+                        break;
+                    }
+                    text = &text[..index];
+                }
+            },
+            _ => {
+                loop {
+                    let trimmed = text.trim_end();
+                    let line_start = trimmed.rfind('\n').map_or(0, |index| index + 1);
+                    let line = &trimmed[line_start..];
+
+                    // comment lines and a comment after the scalar
+                    if let Some(index) = line.find(" #").or_else(|| line.find("\t#")).or_else(|| line.trim_start().starts_with('#').then_some(0)) {
+                        text = &trimmed[..line_start + index];
+                        continue;
+                    }
+
+                    // indicator and properties of the following node
+                    let word_start = trimmed.rfind(char::is_whitespace).map_or(0, |index| index + 1);
+                    let word = &trimmed[word_start..];
+                    if word_start > 0 && (word == "-" || word == "?" || word.starts_with(['&', '!'])) {
+                        text = &trimmed[..word_start];
+                        continue;
+                    }
+
+                    text = trimmed;
+                    break;
+                }
+            },
+        }
+
+        Ok(start + text.trim_end().len())
+    }
+}
+
+/// Converts character indexes of markers into byte offsets of the input. Markers mostly move forward, so the
+/// conversion continues from the previous one instead of counting the characters from the start every time.
+pub(super) struct ByteOffsets {
+    ascii: bool,
+    /// Last converted character index and its byte offset
+    last: Cell<(usize, usize)>,
+}
+
+impl ByteOffsets {
+    pub(super) fn new(input: Option<&str>) -> Self {
+        ByteOffsets { ascii: input.is_some_and(str::is_ascii), last: Cell::new((0, 0)) }
+    }
+
+    pub(super) fn byte_offset(&self, input: &str, index: usize) -> usize {
+        if self.ascii {
+            return index.min(input.len());
+        }
+
+        let (last_index, last_offset) = self.last.get();
+        let offset = if index >= last_index {
+            input[last_offset..].char_indices().nth(index - last_index).map(|(offset, _)| last_offset + offset)
+        } else {
+            input[..last_offset].char_indices().nth_back(last_index - index - 1).map(|(offset, _)| offset)
+        };
+
+        match offset {
+            Some(offset) => {
+                self.last.set((index, offset));
+                offset
+            },
+            None => input.len(),
+        }
+    }
+}
+
+/// Source text of the block collection at the range, which can be parsed on its own: the indicators in front of
+/// it on its first line are left out, the indentation of that line is removed from all lines, and comment lines
+/// right before and after it are kept
+pub(super) fn block_text(input: &str, range: Range<usize>) -> Cow<'_, str> {
+    let mut first_line = input[..range.start].rfind('\n').map_or(0, |index| index + 1);
+    let prefix = &input[first_line..range.start];
+
+    // properties on the line of a key, e.g. `key: &anchor`, are followed by the collection on the next line
+    if !prefix.chars().all(|c| " -?".contains(c)) {
+        return Cow::Borrowed(&input[range]);
+    }
+
+    let indentation = prefix.chars().count();
+    let is_comment = |line: &str| line.trim_start().starts_with('#') && line.len() - line.trim_start().len() >= indentation;
+
+    while first_line > 0 {
+        let previous = input[..first_line - 1].rfind('\n').map_or(0, |index| index + 1);
+        if !is_comment(&input[previous..first_line - 1]) {
+            break;
+        }
+        first_line = previous;
+    }
+
+    // a comment after the last value and comment lines after the collection
+    let line_end = |offset: usize| input[offset..].find('\n').map_or(input.len(), |index| offset + index);
+    let mut end = range.end;
+    if input[end..line_end(end)].trim_start().starts_with('#') {
+        end = line_end(end);
+    }
+    while end < input.len() && is_comment(&input[end + 1..line_end(end + 1)]) {
+        end = line_end(end + 1);
+    }
+
+    if indentation == 0 {
+        return Cow::Borrowed(&input[first_line..end]);
+    }
+
+    let mut lines = vec![];
+    let mut line_start = first_line;
+
+    for line in input[first_line..end].split('\n') {
+        let spaces = line.len() - line.trim_start_matches(' ').len();
+
+        lines.push(match (line_start..=line_start + line.len()).contains(&range.start) {
+            true => &input[range.start..line_start + line.len()],
+            false => &line[spaces.min(indentation)..],
+        });
+        line_start += line.len() + 1;
+    }
+
+    Cow::Owned(lines.join("\n"))
+}
+
+/// End of an alias which starts at the offset
+fn alias_end(input: &str, start: usize) -> usize {
+    start + input[start..].find(|c: char| c.is_whitespace() || ",[]{}".contains(c)).unwrap_or(input.len() - start)
+}
+
+/// Moves the start of a node back over its anchor, tag and block scalar header
+fn properties_start(input: &str, mut start: usize) -> usize {
+    loop {
+        let before = input[..start].trim_end();
+        let word_start = before.rfind(|c: char| c.is_whitespace() || "[{,".contains(c)).map_or(0, |index| index + 1);
+        let word = &before[word_start..];
+
+        if word.starts_with(['&', '!', '|', '>']) {
+            start = word_start;
+        } else {
+            return start;
+        }
+    }
+}
+
+/// Byte offset after the closing quote of a quoted scalar
+fn closing_quote(text: &str, style: TScalarStyle) -> Option<usize> {
+    let mut chars = text.char_indices().skip(1).peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match (style, c) {
+            // '' is an escaped quote
+            (TScalarStyle::SingleQuoted, '\'') if chars.next_if(|(_, next)| *next == '\'').is_none() => {
+                return Some(index + 1);
+            },
+            (TScalarStyle::DoubleQuoted, '\\') => {
+                chars.next();
+            },
+            (TScalarStyle::DoubleQuoted, '"') => {
+                return Some(index + 1);
+            },
+            _ => {},
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ByteOffsets;
+
+    #[test]
+    fn byte_offsets() {
+        let input = "aé€😀b\nc";
+        let offsets = ByteOffsets::new(Some(input));
+
+        // each conversion continues from the one before, forward and backward
+        assert_eq!(0, offsets.byte_offset(input, 0));
+        assert_eq!(1, offsets.byte_offset(input, 1));
+        assert_eq!(3, offsets.byte_offset(input, 2));
+        assert_eq!((2, 3), offsets.last.get());
+        assert_eq!(10, offsets.byte_offset(input, 4));
+        assert_eq!((4, 10), offsets.last.get());
+        assert_eq!(6, offsets.byte_offset(input, 3));
+        assert_eq!(12, offsets.byte_offset(input, 6));
+        assert_eq!(1, offsets.byte_offset(input, 1));
+        assert_eq!((1, 1), offsets.last.get());
+
+        // the end of the input and beyond it
+        assert_eq!(input.len(), offsets.byte_offset(input, 7));
+        assert_eq!(input.len(), offsets.byte_offset(input, 100));
+        assert_eq!(11, offsets.byte_offset(input, 5));
+
+        // ASCII input needs no counting
+        let offsets = ByteOffsets::new(Some("abc"));
+        assert_eq!(2, offsets.byte_offset("abc", 2));
+        assert_eq!((0, 0), offsets.last.get());
+        assert_eq!(3, offsets.byte_offset("abc", 5));
+    }
+}
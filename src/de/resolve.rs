@@ -0,0 +1,449 @@
+//! Resolution of plain scalars following the schemas of YAML 1.2, see
+//! <https://yaml.org/spec/1.2.2/#chapter-10-recommended-schemas>, and the types of YAML 1.1, see
+//! <https://yaml.org/type/>
+
+use super::Schema;
+
+/// Value of a plain scalar according to a schema
+#[derive(Debug, PartialEq)]
+pub(crate) enum Resolved {
+    Null,
+    Bool(bool),
+    Int(i64),
+    /// Integer which is too big for `i64`
+    UInt(u64),
+    Float(f64),
+    String,
+}
+
+pub(crate) fn resolve(value: &str, schema: Schema) -> Resolved {
+    match schema {
+        Schema::Failsafe => Resolved::String,
+        Schema::Json => resolve_json(value),
+        Schema::Core => resolve_core(value),
+        Schema::Yaml11 => resolve_yaml11(value),
+    }
+}
+
+fn resolve_core(value: &str) -> Resolved {
+    if is_null(value) {
+        return Resolved::Null;
+    }
+
+    if let Some(value) = resolve_bool(value) {
+        return Resolved::Bool(value);
+    }
+
+    if let Some(resolved) = resolve_int(value) {
+        return resolved;
+    }
+
+    match resolve_float(value) {
+        Some(value) => Resolved::Float(value),
+        None => Resolved::String,
+    }
+}
+
+/// `null`, `true`, `false`, `-?(0|[1-9][0-9]*)` and the same followed by `(\.[0-9]*)?([eE][-+]?[0-9]+)?`.
+/// Scalars which don't match are strings instead of errors.
+fn resolve_json(value: &str) -> Resolved {
+    match value {
+        "null" => return Resolved::Null,
+        "true" => return Resolved::Bool(true),
+        "false" => return Resolved::Bool(false),
+        _ => {},
+    }
+
+    let unsigned = value.strip_prefix('-').unwrap_or(value);
+    let integer_end = unsigned.find(['.', 'e', 'E']).unwrap_or(unsigned.len());
+    let integer = &unsigned[..integer_end];
+
+    let valid_integer = integer == "0" || (!integer.starts_with('0') && !integer.is_empty() && integer.chars().all(|c| c.is_ascii_digit()));
+    if !valid_integer {
+        return Resolved::String;
+    }
+
+    if integer_end == unsigned.len() {
+        // digits were validated above, so the only failure is overflow
+        return resolve_int(value).unwrap_or(Resolved::String);
+    }
+
+    match resolve_float(value) {
+        // `.5` and `1.` style floats and infinity are not JSON numbers, which is caught by the integer check above
+        Some(v) => Resolved::Float(v),
+        None => Resolved::String,
+    }
+}
+
+pub(crate) fn is_null(value: &str) -> bool {
+    matches!(value, "" | "~" | "null" | "Null" | "NULL")
+}
+
+/// Checks if the plain scalar is null in the schema, the failsafe schema has no null
+pub(crate) fn is_schema_null(value: &str, schema: Schema) -> bool {
+    match schema {
+        Schema::Failsafe => false,
+        Schema::Json => value == "null",
+        Schema::Core | Schema::Yaml11 => is_null(value),
+    }
+}
+
+pub(crate) fn resolve_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" | "True" | "TRUE" => Some(true),
+        "false" | "False" | "FALSE" => Some(false),
+        _ => None,
+    }
+}
+
+/// `[-+]?[0-9]+`, `0o[0-7]+` or `0x[0-9a-fA-F]+`. Decimal integers out of the range of `u64` are floats.
+pub(crate) fn resolve_int(value: &str) -> Option<Resolved> {
+    let (digits, radix) = if let Some(digits) = value.strip_prefix("0o") {
+        (digits, 8)
+    } else if let Some(digits) = value.strip_prefix("0x") {
+        (digits, 16)
+    } else {
+        (value.strip_prefix(['-', '+']).unwrap_or(value), 10)
+    };
+
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+
+    match u64::from_str_radix(digits, radix) {
+        Ok(unsigned) => with_sign(unsigned, value.starts_with('-')).or_else(|| value.parse().ok().map(Resolved::Float)),
+        Err(_) if radix == 10 => value.parse().ok().map(Resolved::Float),
+        Err(_) => None,
+    }
+}
+
+/// Integer with the sign, `None` if a negative integer is out of the range of `i64`
+fn with_sign(unsigned: u64, negative: bool) -> Option<Resolved> {
+    match negative {
+        true => 0i64.checked_sub_unsigned(unsigned).map(Resolved::Int),
+        false => Some(i64::try_from(unsigned).map_or(Resolved::UInt(unsigned), Resolved::Int)),
+    }
+}
+
+/// `[-+]?(\.[0-9]+|[0-9]+(\.[0-9]*)?)([eE][-+]?[0-9]+)?`, `[-+]?\.(inf|Inf|INF)` or `\.(nan|NaN|NAN)`
+pub(crate) fn resolve_float(value: &str) -> Option<f64> {
+    if let Some(special) = resolve_special_float(value) {
+        return Some(special);
+    }
+
+    let unsigned = value.strip_prefix(['-', '+']).unwrap_or(value);
+    let is_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+
+    let valid_mantissa = match mantissa.split_once('.') {
+        Some(("", fraction)) => is_digits(fraction),
+        Some((integer, fraction)) => is_digits(integer) && (fraction.is_empty() || is_digits(fraction)),
+        None => is_digits(mantissa),
+    };
+
+    let valid_exponent = match exponent {
+        Some(exponent) => is_digits(exponent.strip_prefix(['-', '+']).unwrap_or(exponent)),
+        None => true,
+    };
+
+    if valid_mantissa && valid_exponent {
+        value.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// `[-+]?\.(inf|Inf|INF)` or `\.(nan|NaN|NAN)`, which YAML 1.1 and 1.2 share
+fn resolve_special_float(value: &str) -> Option<f64> {
+    match value {
+        "-.inf" | "-.Inf" | "-.INF" => Some(f64::NEG_INFINITY),
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => Some(f64::INFINITY),
+        ".nan" | ".NaN" | ".NAN" => Some(f64::NAN),
+        _ => None,
+    }
+}
+
+/// Types of YAML 1.1, which resolve e.g. `yes`, `off` and `y` as booleans, `010` as octal, `0b101` as binary,
+/// digits separated by `_` and base 60 numbers like `1:20`. Floats need a dot, e.g. `1e5` is a string.
+fn resolve_yaml11(value: &str) -> Resolved {
+    if is_null(value) {
+        return Resolved::Null;
+    }
+
+    match value {
+        "y" | "Y" | "yes" | "Yes" | "YES" | "on" | "On" | "ON" => return Resolved::Bool(true),
+        "n" | "N" | "no" | "No" | "NO" | "off" | "Off" | "OFF" => return Resolved::Bool(false),
+        _ => {},
+    }
+
+    if let Some(value) = resolve_bool(value) {
+        return Resolved::Bool(value);
+    }
+
+    if let Some(resolved) = resolve_yaml11_int(value) {
+        return resolved;
+    }
+
+    match resolve_yaml11_float(value) {
+        Some(value) => Resolved::Float(value),
+        None => Resolved::String,
+    }
+}
+
+/// `[-+]?0b[0-1_]+`, `[-+]?0[0-7_]+`, `[-+]?(0|[1-9][0-9_]*)`, `[-+]?0x[0-9a-fA-F_]+` or
+/// `[-+]?[1-9][0-9_]*(:[0-5]?[0-9])+`
+fn resolve_yaml11_int(value: &str) -> Option<Resolved> {
+    let negative = value.starts_with('-');
+    let unsigned = value.strip_prefix(['-', '+']).unwrap_or(value);
+
+    if unsigned.contains(':') {
+        return match unsigned.starts_with('0') {
+            true => None,
+            false => base60(unsigned).and_then(|unsigned| with_sign(unsigned, negative)),
+        };
+    }
+
+    let (digits, radix) = if let Some(digits) = unsigned.strip_prefix("0b") {
+        (digits, 2)
+    } else if let Some(digits) = unsigned.strip_prefix("0x") {
+        (digits, 16)
+    } else if let Some(digits) = unsigned.strip_prefix('0').filter(|digits| !digits.is_empty()) {
+        (digits, 8)
+    } else {
+        (unsigned, 10)
+    };
+
+    if digits.is_empty() || digits.starts_with('_') && radix == 10 || !digits.chars().all(|c| c == '_' || c.is_digit(radix)) {
+        return None;
+    }
+
+    let digits = digits.replace('_', "");
+    let float = || match radix {
+        10 => format!("{}{digits}", if negative { "-" } else { "" }).parse().ok().map(Resolved::Float),
+        _ => None,
+    };
+
+    match u64::from_str_radix(&digits, radix) {
+        Ok(unsigned) => with_sign(unsigned, negative).or_else(float),
+        // only underscores after the prefix, e.g. `0b_`
+        Err(_) if digits.is_empty() => Some(Resolved::Int(0)),
+        Err(_) => float(),
+    }
+}
+
+/// `[-+]?([0-9][0-9_]*)?\.[0-9_]*([eE][-+][0-9]+)?`, `[-+]?[0-9][0-9_]*(:[0-5]?[0-9])+\.[0-9_]*` or the
+/// special floats. The specification allows dots in the fraction, which no float has, so it allows `_` instead.
+fn resolve_yaml11_float(value: &str) -> Option<f64> {
+    if let Some(special) = resolve_special_float(value) {
+        return Some(special);
+    }
+
+    let unsigned = value.strip_prefix(['-', '+']).unwrap_or(value);
+    let sign = if value.starts_with('-') { -1.0 } else { 1.0 };
+    let is_digits = |s: &str| s.chars().all(|c| c == '_' || c.is_ascii_digit());
+
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+
+    let (integer, fraction) = mantissa.split_once('.')?;
+    let fraction = fraction.replace('_', "");
+
+    if !is_digits(&fraction) || integer.starts_with('_') {
+        return None;
+    }
+
+    if integer.contains(':') {
+        let fraction = format!("0.{fraction}").parse::<f64>().ok()?;
+        return match exponent {
+            Some(_) => None,
+            None => base60(integer).map(|integer| sign * (integer as f64 + fraction)),
+        };
+    }
+
+    let valid_exponent = match exponent.map(|exponent| exponent.strip_prefix(['-', '+'])) {
+        Some(Some(digits)) => !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()),
+        Some(None) => false,
+        None => true,
+    };
+
+    if !is_digits(integer) || integer.is_empty() && fraction.is_empty() || !valid_exponent {
+        return None;
+    }
+
+    let exponent = exponent.map(|exponent| format!("e{exponent}")).unwrap_or_default();
+    format!("{}.{fraction}{exponent}", integer.replace('_', "")).parse::<f64>().ok().map(|v| sign * v)
+}
+
+/// Base 60 number like `190:20:30`, `[0-9][0-9_]*(:[0-5]?[0-9])+`
+fn base60(value: &str) -> Option<u64> {
+    let mut parts = value.split(':');
+    let first = parts.next().unwrap_or_default().replace('_', "");
+
+    if value.starts_with('_') || !value.contains(':') {
+        return None;
+    }
+
+    parts.try_fold(first.parse::<u64>().ok()?, |total, part| {
+        let valid = matches!(part.as_bytes(), [b'0'..=b'9'] | [b'0'..=b'5', b'0'..=b'9']);
+        total.checked_mul(60)?.checked_add(part.parse().ok().filter(|_| valid)?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve, Resolved};
+    use crate::de::Schema;
+
+    #[test]
+    fn core_schema() {
+        // examples 10.8 and 10.9 of the specification and values which YAML 1.1 resolves differently
+        let table = [
+            ("null", Resolved::Null),
+            ("Null", Resolved::Null),
+            ("NULL", Resolved::Null),
+            ("~", Resolved::Null),
+            ("", Resolved::Null),
+            ("true", Resolved::Bool(true)),
+            ("True", Resolved::Bool(true)),
+            ("false", Resolved::Bool(false)),
+            ("FALSE", Resolved::Bool(false)),
+            ("0", Resolved::Int(0)),
+            ("0o7", Resolved::Int(7)),
+            ("0x3A", Resolved::Int(58)),
+            ("-19", Resolved::Int(-19)),
+            ("+12", Resolved::Int(12)),
+            ("08", Resolved::Int(8)),
+            ("-9223372036854775808", Resolved::Int(i64::MIN)),
+            ("18446744073709551615", Resolved::UInt(u64::MAX)),
+            ("0.", Resolved::Float(0.0)),
+            ("-0.0", Resolved::Float(-0.0)),
+            (".5", Resolved::Float(0.5)),
+            ("+12e03", Resolved::Float(12000.0)),
+            ("-2E+05", Resolved::Float(-200000.0)),
+            ("1e5", Resolved::Float(100000.0)),
+            ("18446744073709551616", Resolved::Float(18446744073709551616.0)),
+            (".inf", Resolved::Float(f64::INFINITY)),
+            ("-.Inf", Resolved::Float(f64::NEG_INFINITY)),
+            ("+.INF", Resolved::Float(f64::INFINITY)),
+            ("NO", Resolved::String),
+            ("Off", Resolved::String),
+            ("yes", Resolved::String),
+            ("y", Resolved::String),
+            ("tRUE", Resolved::String),
+            ("nULL", Resolved::String),
+            ("0b101", Resolved::String),
+            ("0o8", Resolved::String),
+            ("0x", Resolved::String),
+            ("-0x10", Resolved::String),
+            ("1_000", Resolved::String),
+            ("1:20", Resolved::String),
+            ("inf", Resolved::String),
+            ("nan", Resolved::String),
+            ("infinity", Resolved::String),
+            ("-.nan", Resolved::String),
+            (".", Resolved::String),
+            ("1e", Resolved::String),
+            ("e5", Resolved::String),
+            ("12.5.1", Resolved::String),
+        ];
+
+        for (value, expected) in table {
+            assert_eq!(expected, resolve(value, Schema::Core), "{value}");
+        }
+
+        assert!(matches!(resolve(".nan", Schema::Core), Resolved::Float(v) if v.is_nan()));
+        assert!(matches!(resolve(".NaN", Schema::Core), Resolved::Float(v) if v.is_nan()));
+    }
+
+    #[test]
+    fn json_schema() {
+        // example 10.4 of the specification and values which only the core schema resolves
+        let table = [
+            ("null", Resolved::Null),
+            ("true", Resolved::Bool(true)),
+            ("false", Resolved::Bool(false)),
+            ("0", Resolved::Int(0)),
+            ("-0", Resolved::Int(0)),
+            ("3", Resolved::Int(3)),
+            ("-19", Resolved::Int(-19)),
+            ("0.", Resolved::Float(0.0)),
+            ("-0.0", Resolved::Float(-0.0)),
+            ("12e03", Resolved::Float(12000.0)),
+            ("-2E+05", Resolved::Float(-200000.0)),
+            ("~", Resolved::String),
+            ("Null", Resolved::String),
+            ("True", Resolved::String),
+            ("+12", Resolved::String),
+            ("012", Resolved::String),
+            ("0x3A", Resolved::String),
+            ("0o7", Resolved::String),
+            (".5", Resolved::String),
+            (".inf", Resolved::String),
+            ("-.inf", Resolved::String),
+            (".nan", Resolved::String),
+            ("1e", Resolved::String),
+            ("-", Resolved::String),
+        ];
+
+        for (value, expected) in table {
+            assert_eq!(expected, resolve(value, Schema::Json), "{value}");
+        }
+    }
+
+    #[test]
+    fn yaml11_types() {
+        // examples of https://yaml.org/type/ and values which the core schema resolves differently
+        let table = [
+            ("~", Resolved::Null),
+            ("", Resolved::Null),
+            ("y", Resolved::Bool(true)),
+            ("Yes", Resolved::Bool(true)),
+            ("ON", Resolved::Bool(true)),
+            ("True", Resolved::Bool(true)),
+            ("n", Resolved::Bool(false)),
+            ("NO", Resolved::Bool(false)),
+            ("Off", Resolved::Bool(false)),
+            ("false", Resolved::Bool(false)),
+            ("685230", Resolved::Int(685230)),
+            ("+685_230", Resolved::Int(685230)),
+            ("02472256", Resolved::Int(685230)),
+            ("0x_0A_74_AE", Resolved::Int(685230)),
+            ("0b1010_0111_0100_1010_1110", Resolved::Int(685230)),
+            ("190:20:30", Resolved::Int(685230)),
+            ("-1:20", Resolved::Int(-80)),
+            ("0", Resolved::Int(0)),
+            ("-0x10", Resolved::Int(-16)),
+            ("18446744073709551615", Resolved::UInt(u64::MAX)),
+            ("6.8523015e+5", Resolved::Float(685230.15)),
+            ("685.230_15e+03", Resolved::Float(685230.15)),
+            ("685_230.15", Resolved::Float(685230.15)),
+            ("190:20:30.15", Resolved::Float(685230.15)),
+            ("-.5", Resolved::Float(-0.5)),
+            ("1.", Resolved::Float(1.0)),
+            ("-.inf", Resolved::Float(f64::NEG_INFINITY)),
+            ("08", Resolved::String),
+            ("0o7", Resolved::String),
+            ("_1", Resolved::String),
+            ("1e5", Resolved::String),
+            ("1.5e5", Resolved::String),
+            ("1:60", Resolved::String),
+            ("01:20", Resolved::String),
+            ("1:2:", Resolved::String),
+            (".", Resolved::String),
+            ("yES", Resolved::String),
+            ("Norway", Resolved::String),
+        ];
+
+        for (value, expected) in table {
+            assert_eq!(expected, resolve(value, Schema::Yaml11), "{value}");
+        }
+
+        assert!(matches!(resolve(".NaN", Schema::Yaml11), Resolved::Float(v) if v.is_nan()));
+    }
+}
@@ -1,6 +1,14 @@
+pub mod anchor;
+pub mod comment;
 pub mod de;
+pub mod raw;
 pub mod ser;
 pub mod wrapper;
+pub mod with;
 
-pub use de::from_str;
-pub use ser::to_string;
+pub use anchor::Anchored;
+pub use comment::Commented;
+pub use de::{from_file_with_includes, from_reader, from_slice, from_str, from_str_multi, from_str_path, from_str_with_options, from_str_with_positions, seq_iter, validate, DeserializerOptions, NodeKind, NodeTag, Position, Positions, Schema, SeqIter, StreamDeserializer, YamlDeserializer, YamlLoader, YamlLoaderBuilder};
+pub use raw::RawYaml;
+pub use wrapper::from_value;
+pub use ser::{to_string, to_string_canonical, to_string_compact, to_string_multi, to_string_multi_with_options, to_string_with_options, to_vec, to_writer, to_writer_multi, to_writer_multi_with_options, to_writer_seq, to_writer_seq_with_options, to_writer_with_options, to_value, to_yaml, to_yaml_with, DefaultStyler, EnumRepr, LineEnding, NonFiniteFloats, NullStyle, QuoteStyle, ScalarContext, ScalarStyle, ScalarStyler, SerializerOptions, Style, YamlNodeSerializer};
@@ -0,0 +1,167 @@
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use serde::de::{Error, Visitor};
+use serde::{Deserialize, Deserializer};
+
+/// Name of the newtype struct which asks [`YamlDeserializer`](crate::YamlDeserializer) for the source text of a node
+pub(crate) const TOKEN: &str = "$serde_yaml2::private::RawYaml";
+
+/// Source text of a YAML node, captured without interpreting it.
+///
+/// The text includes comments inside the node, as well as its anchor and tag. It is borrowed from the input,
+/// except for a nested block collection: the indicators in front of it on its first line are left out and its
+/// indentation is removed, e.g. `- c: 1\n  d: x` in a sequence is captured as `c: 1\nd: x`. Comment lines
+/// right before and after a block collection are kept.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct RawYaml<'a>(Cow<'a, str>);
+
+impl RawYaml<'_> {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for RawYaml<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+struct RawYamlVisitor {}
+
+impl<'de> Visitor<'de> for RawYamlVisitor {
+    type Value = RawYaml<'de>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("source text of a YAML node")
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E> where E: Error {
+        Ok(RawYaml(Cow::Borrowed(v)))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: Error {
+        Ok(RawYaml(Cow::Owned(v.to_string())))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E> where E: Error {
+        Ok(RawYaml(Cow::Owned(v)))
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for RawYaml<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        deserializer.deserialize_newtype_struct(TOKEN, RawYamlVisitor {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde::Deserialize;
+    use crate::{from_str, RawYaml};
+
+    #[derive(Deserialize, Debug)]
+    struct Config<'a> {
+        name: String,
+        #[serde(borrow)]
+        settings: RawYaml<'a>,
+        #[serde(borrow)]
+        tags: Vec<RawYaml<'a>>,
+    }
+
+    #[test]
+    fn captures_nested_mapping() {
+        let yaml = "name: app
+settings:
+  # database connection
+  db:
+    host: localhost # default host
+    port: 5432
+  retries: [1, 2, 3]
+  # trailing comment
+tags:
+  - plain text # comment
+  - 'it''s quoted'
+  - &anchor !custom {a: 1, b: [2]}
+  - *anchor
+  - |
+    literal
+    text
+  - multi line
+    plain scalar
+";
+        let config: Config = from_str(yaml).unwrap();
+
+        assert_eq!("app", config.name);
+        assert_eq!("# database connection\ndb:\n  host: localhost # default host\n  port: 5432\nretries: [1, 2, 3]\n# trailing comment", config.settings.as_str());
+        assert_eq!(vec![
+            "plain text",
+            "'it''s quoted'",
+            "&anchor !custom {a: 1, b: [2]}",
+            "*anchor",
+            "|\n    literal\n    text",
+            "multi line\n    plain scalar",
+        ], config.tags.iter().map(RawYaml::as_str).collect::<Vec<_>>());
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Settings {
+            db: std::collections::HashMap<String, String>,
+            retries: Vec<u8>,
+        }
+
+        let settings: Settings = from_str(config.settings.as_str()).unwrap();
+        assert_eq!(vec![1, 2, 3], settings.retries);
+        assert_eq!("5432", settings.db["port"]);
+    }
+
+    #[test]
+    fn captures_flow_and_sequence_nodes() {
+        let raw: Vec<RawYaml> = from_str("[1, 'a, b', {x: [y]}, \"q\\\"\"]").unwrap();
+        assert_eq!(vec!["1", "'a, b'", "{x: [y]}", "\"q\\\"\""], raw.iter().map(RawYaml::as_str).collect::<Vec<_>>());
+
+        let raw: Vec<RawYaml> = from_str("- - a\n  - b\n- c: 1\n  d: ü\n- last").unwrap();
+        assert_eq!(vec!["- a\n- b", "c: 1\nd: ü", "last"], raw.iter().map(RawYaml::as_str).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn nested_block_collections_parse_again() {
+        let raw: Vec<RawYaml> = from_str("- - a\n  - b\n- c: 1\n  d: x\n").unwrap();
+        assert_eq!(vec!["a", "b"], from_str::<Vec<String>>(raw[0].as_str()).unwrap());
+        assert_eq!(BTreeMap::from([("c", "1"), ("d", "x")]), from_str::<BTreeMap<&str, &str>>(raw[1].as_str()).unwrap());
+
+        let yaml = "outer:
+  - name: first
+    # the items
+    items:
+      - - 1 # one
+        - 2
+      - [3]
+  - ? complex
+    : key
+";
+        let raw: BTreeMap<&str, Vec<RawYaml>> = from_str(yaml).unwrap();
+        assert_eq!("name: first\n# the items\nitems:\n  - - 1 # one\n    - 2\n  - [3]", raw["outer"][0].as_str());
+        assert_eq!("? complex\n: key", raw["outer"][1].as_str());
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Item {
+            name: String,
+            items: Vec<Vec<u8>>,
+        }
+
+        let item: Item = from_str(raw["outer"][0].as_str()).unwrap();
+        assert_eq!(Item { name: String::from("first"), items: vec![vec![1, 2], vec![3]] }, item);
+
+        #[derive(Deserialize, Debug)]
+        struct RawItems<'a> {
+            #[serde(borrow)]
+            items: Vec<RawYaml<'a>>,
+        }
+
+        let raw_items: RawItems = from_str(raw["outer"][0].as_str()).unwrap();
+        assert_eq!(vec!["- 1 # one\n- 2", "[3]"], raw_items.items.iter().map(RawYaml::as_str).collect::<Vec<_>>());
+        assert_eq!(vec![1, 2], from_str::<Vec<u8>>(raw_items.items[0].as_str()).unwrap());
+        assert_eq!(BTreeMap::from([("complex", "key")]), from_str::<BTreeMap<&str, &str>>(raw["outer"][1].as_str()).unwrap());
+    }
+}
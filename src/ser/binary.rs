@@ -0,0 +1,85 @@
+//! Standard base64 of `!!binary` scalars, see <https://yaml.org/type/binary.html>
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Length of a line of a long `!!binary` scalar, the one MIME uses
+pub(crate) const LINE_LENGTH: usize = 76;
+
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (index, byte)| group | (*byte as u32) << (16 - index * 8));
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                result.push(ALPHABET[(group >> (18 - index * 6)) as usize & 0x3F] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+
+    result
+}
+
+/// Decodes base64 ignoring whitespace, which a block scalar wrapping it into lines contains. Returns None if
+/// the text isn't valid base64.
+pub(crate) fn decode(text: &str) -> Option<Vec<u8>> {
+    let digits = text.bytes().filter(|c| !c.is_ascii_whitespace()).collect::<Vec<_>>();
+
+    if digits.len() % 4 != 0 {
+        return None;
+    }
+
+    let mut result = Vec::with_capacity(digits.len() / 4 * 3);
+
+    for (index, chunk) in digits.chunks(4).enumerate() {
+        let last = index == digits.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|c| **c == b'=').count();
+
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+
+        let mut group = 0u32;
+
+        for (position, c) in chunk[..4 - padding].iter().enumerate() {
+            let value = ALPHABET.iter().position(|a| a == c)? as u32;
+            group |= value << (18 - position * 6);
+        }
+
+        result.extend_from_slice(&group.to_be_bytes()[1..4 - padding]);
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+
+    #[test]
+    fn should_work() {
+        let cases: [(&[u8], &str); 7] = [
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"fooba", "Zm9vYmE="),
+            (&[0, 0xFF, 0xFE, 0x3E, 0x3F], "AP/+Pj8="),
+        ];
+
+        for (bytes, text) in cases {
+            assert_eq!(text, encode(bytes));
+            assert_eq!(Some(bytes.to_vec()), decode(text));
+        }
+
+        assert_eq!(Some(b"foobar".to_vec()), decode("Zm9v\n  YmFy\n"));
+
+        for text in ["Zm9", "Zm9v=", "Zg==Zg==", "Zm9*", "Z===", "===="] {
+            assert_eq!(None, decode(text), "{text}");
+        }
+    }
+}
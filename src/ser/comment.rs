@@ -0,0 +1,178 @@
+//! Finds the comment of a [`Commented`](crate::Commented) value without writing it, so it can be written before
+//! the key or dash of the node
+
+use std::fmt;
+use serde::ser::{Impossible, Serialize, SerializeTupleStruct, Serializer};
+use crate::comment::COMMENT_TOKEN;
+
+/// Returns the comment if the value is a [`Commented`](crate::Commented), or a newtype struct around one
+pub(crate) fn comment_of<T: Serialize + ?Sized>(value: &T) -> Option<String> {
+    value.serialize(CommentProbe { text: false }).ok().flatten()
+}
+
+/// Returns the string the value serializes as
+pub(crate) fn comment_text<T: Serialize + ?Sized>(value: &T) -> Option<String> {
+    value.serialize(CommentProbe { text: true }).ok().flatten()
+}
+
+/// Serializes nothing and succeeds only for a commented value, or for a string if `text` is set. Any other value
+/// stops it with [`NotComment`] at its first call.
+struct CommentProbe {
+    text: bool,
+}
+
+#[derive(Debug)]
+struct NotComment {}
+
+impl fmt::Display for NotComment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("not a comment")
+    }
+}
+
+impl std::error::Error for NotComment {}
+
+impl serde::ser::Error for NotComment {
+    fn custom<T: fmt::Display>(_msg: T) -> Self {
+        NotComment {}
+    }
+}
+
+macro_rules! not_comment {
+    ($($method:ident($($arg:ty),*);)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<Self::Ok, Self::Error> {
+                Err(NotComment {})
+            }
+        )*
+    }
+}
+
+impl Serializer for CommentProbe {
+    type Ok = Option<String>;
+    type Error = NotComment;
+    type SerializeSeq = Impossible<Option<String>, NotComment>;
+    type SerializeTuple = Impossible<Option<String>, NotComment>;
+    type SerializeTupleStruct = CommentFields;
+    type SerializeTupleVariant = Impossible<Option<String>, NotComment>;
+    type SerializeMap = Impossible<Option<String>, NotComment>;
+    type SerializeStruct = Impossible<Option<String>, NotComment>;
+    type SerializeStructVariant = Impossible<Option<String>, NotComment>;
+
+    not_comment! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_bytes(&[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+        serialize_unit_variant(&'static str, u32, &'static str);
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        if !self.text {
+            return Err(NotComment {});
+        }
+
+        Ok(Some(v.to_owned()))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<Self::Ok, Self::Error> {
+        Err(NotComment {})
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<Self::Ok, Self::Error> {
+        Err(NotComment {})
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(NotComment {})
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(NotComment {})
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        if self.text || name != COMMENT_TOKEN {
+            return Err(NotComment {});
+        }
+
+        Ok(CommentFields { comment: None })
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(NotComment {})
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(NotComment {})
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(NotComment {})
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(NotComment {})
+    }
+}
+
+/// Keeps the first field of a commented value, the comment, and skips the value
+struct CommentFields {
+    comment: Option<String>,
+}
+
+impl SerializeTupleStruct for CommentFields {
+    type Ok = Option<String>;
+    type Error = NotComment;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        if self.comment.is_none() {
+            self.comment = value.serialize(CommentProbe { text: true })?;
+        }
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.comment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+    use crate::Commented;
+    use super::{comment_of, comment_text};
+
+    #[derive(Serialize)]
+    struct Wrapper(Commented<i32>);
+
+    #[test]
+    fn should_work() {
+        assert_eq!(Some(String::from("port")), comment_of(&Commented::new("port", 80)));
+        assert_eq!(Some(String::from("port")), comment_of(&Wrapper(Commented::new("port", 80))));
+        assert_eq!(None, comment_of(&80));
+        assert_eq!(None, comment_of("port"));
+        assert_eq!(None, comment_of(&(String::from("port"), 80)));
+        assert_eq!(Some(String::from("port")), comment_text("port"));
+        assert_eq!(None, comment_text(&80));
+    }
+}
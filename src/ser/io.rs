@@ -0,0 +1,177 @@
+use std::cell::Cell;
+use std::fmt;
+use std::io;
+use serde::ser::SerializeSeq;
+use serde::{Serialize, Serializer};
+use super::multi::write_multi;
+use super::{Errors, SerializerOptions, YamlSerializer};
+
+/// Serializes the value into the writer. Output is written in small pieces, so wrap unbuffered writers like
+/// a `File` or a `TcpStream` into a [`BufWriter`](std::io::BufWriter).
+pub fn to_writer<W: io::Write, T: Serialize + ?Sized>(writer: W, value: &T) -> Result<(), Errors> {
+    to_writer_with_options(writer, value, SerializerOptions::default())
+}
+
+pub fn to_writer_with_options<W: io::Write, T: Serialize + ?Sized>(writer: W, value: &T, options: SerializerOptions) -> Result<(), Errors> {
+    let mut adapter = IoWriter { inner: writer, error: None };
+    let result = YamlSerializer::with_options(&mut adapter, options).write(value);
+
+    match adapter.error {
+        Some(error) => Err(Errors::IoError(error)),
+        None => result,
+    }
+}
+
+/// Writes every item as a document of a multi-document stream, see [`to_string_multi`](super::to_string_multi)
+pub fn to_writer_multi<W: io::Write, I: IntoIterator<Item = T>, T: Serialize>(writer: W, documents: I) -> Result<(), Errors> {
+    to_writer_multi_with_options(writer, documents, SerializerOptions::default())
+}
+
+pub fn to_writer_multi_with_options<W: io::Write, I: IntoIterator<Item = T>, T: Serialize>(writer: W, documents: I, options: SerializerOptions) -> Result<(), Errors> {
+    let mut adapter = IoWriter { inner: writer, error: None };
+    let result = write_multi(&mut adapter, documents, &options);
+
+    match adapter.error {
+        Some(error) => Err(Errors::IoError(error)),
+        None => result,
+    }
+}
+
+/// Writes the items as a sequence while the iterator produces them, without collecting them first. Every item is
+/// written into the writer before the next one is produced.
+pub fn to_writer_seq<W: io::Write, I: IntoIterator<Item = T>, T: Serialize>(writer: W, items: I) -> Result<(), Errors> {
+    to_writer_seq_with_options(writer, items, SerializerOptions::default())
+}
+
+pub fn to_writer_seq_with_options<W: io::Write, I: IntoIterator<Item = T>, T: Serialize>(writer: W, items: I, options: SerializerOptions) -> Result<(), Errors> {
+    to_writer_with_options(writer, &IterSeq(Cell::new(Some(items.into_iter()))), options)
+}
+
+/// Serializes the items of the iterator as a sequence of unknown length, once
+struct IterSeq<I>(Cell<Option<I>>);
+
+impl<I: Iterator<Item = T>, T: Serialize> Serialize for IterSeq<I> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let mut items = self.0.take().into_iter().flatten().peekable();
+
+        // an empty sequence is written as `[]`, which only a sequence known to be empty is
+        let len = items.peek().is_none().then_some(0);
+        let mut seq = serializer.serialize_seq(len)?;
+
+        for item in items {
+            seq.serialize_element(&item)?;
+        }
+
+        seq.end()
+    }
+}
+
+/// Serializes the value into UTF-8 bytes, the same as [`to_string`](super::to_string) returns
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Errors> {
+    let mut output = Vec::with_capacity(128);
+    to_writer(&mut output, value)?;
+    Ok(output)
+}
+
+/// Writes formatted output into an `io::Write`, keeping the error which `fmt::Write` can't carry
+struct IoWriter<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::io;
+    use serde::Serialize;
+    use super::{to_vec, to_writer, to_writer_seq};
+
+    #[derive(Serialize)]
+    struct TestStruct {
+        name: String,
+        ports: Vec<u16>,
+    }
+
+    struct FailingWriter {}
+
+    impl io::Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "connection closed"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn should_work() {
+        let value = TestStruct { name: String::from("web"), ports: vec![80, 443] };
+
+        let mut output: Vec<u8> = vec![];
+        to_writer(&mut output, &value).unwrap();
+        assert_eq!(crate::to_string(&value).unwrap().as_bytes(), output.as_slice());
+
+        let error = to_writer(FailingWriter {}, &value).unwrap_err();
+        assert_eq!("IO error: connection closed", error.to_string());
+        let source = error.source().and_then(|source| source.downcast_ref::<io::Error>()).unwrap();
+        assert_eq!(io::ErrorKind::BrokenPipe, source.kind());
+    }
+
+    #[test]
+    fn to_vec_matches_to_string() {
+        let value = TestStruct { name: String::from("Grüße\nworld"), ports: vec![80, 443] };
+        assert_eq!(crate::to_string(&value).unwrap().into_bytes(), to_vec(&value).unwrap());
+
+        let value = vec![TestStruct { name: String::from("a"), ports: vec![] }];
+        assert_eq!(crate::to_string(&value).unwrap().into_bytes(), to_vec(&value).unwrap());
+    }
+
+    /// Keeps the first bytes written and counts the rest
+    struct Sample {
+        prefix: Vec<u8>,
+        len: usize,
+    }
+
+    impl io::Write for Sample {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let kept = buf.len().min(256 - self.prefix.len().min(256));
+            self.prefix.extend_from_slice(&buf[..kept]);
+            self.len += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writes_iterator_as_sequence() {
+        let items = (0..100_000).map(|id| TestStruct { name: format!("item-{id}"), ports: vec![id as u16] });
+        let mut sample = Sample { prefix: vec![], len: 0 };
+        to_writer_seq(&mut sample, items).unwrap();
+
+        let prefix = String::from_utf8(sample.prefix).unwrap();
+        assert!(prefix.starts_with("-\n  name: item-0\n  ports:\n    - 0\n-\n  name: item-1\n  ports:\n    - 1\n"), "{prefix}");
+
+        let items = (0..1000).map(|id| TestStruct { name: format!("item-{id}"), ports: vec![id as u16] }).collect::<Vec<_>>();
+        let mut output: Vec<u8> = vec![];
+        to_writer_seq(&mut output, &items).unwrap();
+        assert_eq!(crate::to_string(&items).unwrap().into_bytes(), output);
+        assert!(sample.len > output.len() * 100, "{}", sample.len);
+
+        let mut output: Vec<u8> = vec![];
+        to_writer_seq(&mut output, Vec::<i32>::new()).unwrap();
+        assert_eq!(b"[]\n", output.as_slice());
+    }
+}
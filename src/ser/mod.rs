@@ -1,27 +1,83 @@
+use std::collections::HashSet;
 use std::fmt::{Display, Write};
 use serde::{Serialize, Serializer};
+use yaml_rust2::parser::Parser;
+use yaml_rust2::Event;
 use serde::ser::{SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple, SerializeTupleStruct, SerializeTupleVariant};
+use comment::{comment_of, comment_text};
+use none::is_none;
+use plain::is_plain_safe;
+use crate::anchor::{ALIAS_TOKEN, ANCHOR_TOKEN};
+use crate::comment::COMMENT_TOKEN;
+use crate::with::flow::FLOW_TOKEN;
+use crate::with::hex_int::{HEX_INT_TOKEN, OCTAL_INT_TOKEN};
+use crate::with::one_or_many::SINGLE_TOKEN;
+use crate::with::quoted::QUOTED_TOKEN;
+
+pub(crate) mod binary;
+mod comment;
+mod io;
+mod multi;
+mod node;
+mod none;
+mod options;
+mod plain;
+mod style;
+
+pub use io::{to_vec, to_writer, to_writer_multi, to_writer_multi_with_options, to_writer_seq, to_writer_seq_with_options, to_writer_with_options};
+pub use multi::{to_string_multi, to_string_multi_with_options};
+pub use node::{to_value, to_yaml, to_yaml_with, YamlNodeSerializer};
+pub use options::{EnumRepr, LineEnding, NonFiniteFloats, NullStyle, QuoteStyle, SerializerOptions, Style};
+pub use style::{DefaultStyler, ScalarContext, ScalarStyle, ScalarStyler};
+
+/// Longest implicit key YAML allows, in characters
+const MAX_IMPLICIT_KEY_LENGTH: usize = 1024;
 
 macro_rules! serialize_value {
     ($self:ident, $value:ident) => {
+        $self.begin_node(false)?;
         write!($self.writer, "{}", $value)?;
         return Ok(());
     }
 }
 
+/// Serializes the value into a YAML document, which ends with a single line break
 pub fn to_string<T: Serialize>(value: T) -> Result<String, Errors> {
+    to_string_with_options(value, SerializerOptions::default())
+}
+
+pub fn to_string_with_options<T: Serialize>(value: T, options: SerializerOptions) -> Result<String, Errors> {
     let mut out = String::new();
-    let mut serializer = YamlSerializer::new(&mut out);
+    let mut serializer = YamlSerializer::with_options(&mut out, options);
     serializer.write(value)?;
     Ok(out)
 }
 
+/// Serializes the value in the canonical form of [`SerializerOptions::canonical`], e.g. to hash it or to compare
+/// it with a golden file. A `HashMap` and a `BTreeMap`, or a struct and a map of the same entries, are written the
+/// same way.
+pub fn to_string_canonical<T: Serialize>(value: T) -> Result<String, Errors> {
+    to_string_with_options(value, SerializerOptions::canonical())
+}
+
+/// Serializes the value in the compact form of [`SerializerOptions::compact`], a single line without a line break,
+/// e.g. for a log line or an annotation: `{x: 1, y: [a, b], z: null}`
+pub fn to_string_compact<T: Serialize>(value: T) -> Result<String, Errors> {
+    let mut output = to_string_with_options(value, SerializerOptions::compact())?;
+    output.pop();
+    Ok(output)
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Errors {
     #[error("Format arguments error")]
     FormArgsError,
     #[error("{0}")]
     UnsupportedSerializationError(String),
+    #[error("IO error: {0}")]
+    IoError(#[source] std::io::Error),
+    #[error("Serializer wrote invalid YAML, {error}:\n{output}")]
+    InvalidOutputError { error: String, output: String },
 }
 
 impl serde::ser::Error for Errors {
@@ -41,38 +97,286 @@ fn write_indent(level: i32, writer: &mut dyn Write) -> Result<(), Errors> {
         return Ok(());
     }
 
-    for _ in 0..=level {
-        write!(writer, " ")?;
+    for _ in 0..level {
+        writer.write_str("  ")?;
+    }
+
+    Ok(())
+}
+
+/// Writes every line of the comment as a comment line, followed by the indentation of the node the comment is
+/// written before
+fn write_comment(comment: &str, level: i32, writer: &mut dyn Write) -> Result<(), Errors> {
+    for line in comment.lines() {
+        if line.is_empty() {
+            writer.write_str("#\n")?;
+        } else {
+            writeln!(writer, "# {line}")?;
+        }
+
+        write_indent(level, writer)?;
     }
 
     Ok(())
 }
 
+/// Escapes a string for single-quoted style, in which a quote is escaped by doubling it
 fn escape_str(source: &str) -> String {
-    source.replace('\'', r#"\'"#)
+    source.replace('\'', "''")
+}
+
+/// Escapes a string for double-quoted style, which can represent any string on a single line, in ASCII alone if
+/// `ascii` is set
+fn escape_double_quoted(source: &str, ascii: bool) -> String {
+    let mut result = String::with_capacity(source.len());
+
+    for c in source.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '"' => result.push_str("\\\""),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            '\0' => result.push_str("\\0"),
+            '\u{7}' => result.push_str("\\a"),
+            '\u{8}' => result.push_str("\\b"),
+            '\u{B}' => result.push_str("\\v"),
+            '\u{C}' => result.push_str("\\f"),
+            '\u{1B}' => result.push_str("\\e"),
+            '\u{85}' => result.push_str("\\N"),
+            '\u{2028}' => result.push_str("\\L"),
+            '\u{2029}' => result.push_str("\\P"),
+            c if ascii && c > '\u{FFFF}' => result.push_str(&format!("\\U{:08X}", c as u32)),
+            c if needs_escape(c) || (ascii && !c.is_ascii()) => result.push_str(&format!("\\u{:04X}", c as u32)),
+            c => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Characters which neither single-quoted nor block style can carry: control characters other than the line
+/// feed, line separators and the byte order mark. A tab is allowed there, but other tools tend to mangle it.
+fn needs_escape(c: char) -> bool {
+    (c.is_control() && c != '\n') || matches!(c, '\u{2028}' | '\u{2029}' | '\u{FEFF}')
+}
+
+/// Returns the chomping indicator of a block scalar carrying the string: `-` strips the final line break, none
+/// keeps a single one, `+` keeps all of them. Returns None if a block scalar can't carry the string without an
+/// indentation indicator or trailing whitespace: an empty string or line breaks alone, a first line starting with
+/// a space, or a line ending with one.
+fn block_chomping(v: &str) -> Option<&'static str> {
+    let content = v.trim_end_matches('\n');
+    let first_line = content.lines().find(|line| !line.is_empty())?;
+
+    if first_line.starts_with(' ') || content.lines().any(|line| line.ends_with(' ')) {
+        return None;
+    }
+
+    match v.len() - content.len() {
+        0 => Some("-"),
+        1 => Some(""),
+        _ => Some("+"),
+    }
+}
+
+/// Makes the shortest representation of a float, e.g. `45` or `1e300`, read back as a float by YAML 1.1 and 1.2
+/// parsers alike, which need a fraction and a signed exponent: `45.0`, `1.0e+300`
+fn format_float(repr: String) -> String {
+    let (mantissa, exponent) = match repr.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (repr.as_str(), None),
+    };
+
+    let mut result = String::from(mantissa);
+
+    if !mantissa.contains('.') {
+        result.push_str(".0");
+    }
+
+    if let Some(exponent) = exponent {
+        result.push('e');
+
+        if !exponent.starts_with('-') {
+            result.push('+');
+        }
+
+        result.push_str(exponent);
+    }
+
+    result
 }
 
 pub struct SequenceSerializer<'a, 'se> {
     len: Option<usize>,
     ser: &'a mut YamlSerializer<'se>,
+    flow: bool,
+    first: bool,
+    /// The sequence is the value of a flow mapping of a tuple variant, e.g. `{Variant: [1, 2]}`
+    variant: bool,
+    /// The sequence is a tuple variant in tag form, e.g. `!Variant [1, 2]`, which isn't nested in a mapping
+    tagged: bool,
+    /// The elements are the name of an anchor and the node written with it, see [`Anchored`](crate::Anchored)
+    anchored: bool,
+    /// Elements rendered in flow and in block style while the sequence may still fit on the line of its key or
+    /// dash, see [`SerializerOptions::compact_leaf_seqs`]
+    leaves: Option<Vec<(String, String)>>,
+    /// The elements are a comment and the node written after it, see [`Commented`](crate::Commented)
+    commented: bool,
 }
 
 impl<'a, 'se> SequenceSerializer<'a, 'se> {
     fn process_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Errors> {
-        self.ser.writer.write_str("- \n")?;
+        if self.anchored {
+            return self.process_anchored(value);
+        }
+
+        if self.commented {
+            return self.process_commented(value);
+        }
+
+        // the comment of an element goes on the lines before its dash
+        let comment = if self.flow { None } else { comment_of(value) };
+
+        if self.leaves.is_some() {
+            if comment.is_none() {
+                return self.process_leaf(value);
+            }
+
+            self.write_block_leaves()?;
+        }
+
+        if self.flow {
+            if !std::mem::replace(&mut self.first, false) {
+                self.ser.writer.write_str(", ")?;
+            }
+
+            return T::serialize(value, &mut *self.ser);
+        }
+
+        self.ser.write_line_break()?;
+
+        if let Some(comment) = comment {
+            write_comment(&comment, self.ser.level, self.ser.writer)?;
+            self.ser.comment_written = true;
+        }
+
+        self.ser.writer.write_char('-')?;
+        self.ser.pending = Some(Pending::Element);
         self.ser.incr_level();
-        write_indent(self.ser.level, self.ser.writer)?;
         T::serialize(value, &mut *self.ser)?;
         self.ser.decr_level();
-        self.ser.writer.write_char('\n')?;
-        write_indent(self.ser.level, self.ser.writer)
+        self.ser.end_line();
+        Ok(())
+    }
+
+    /// Keeps the name of the anchor, then writes the node with it
+    fn process_anchored<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Errors> {
+        if !std::mem::replace(&mut self.first, false) {
+            return self.ser.write_anchored(value);
+        }
+
+        let name = self.ser.read_anchor_name(value)?;
+
+        if let Some(anchor) = &self.ser.anchor {
+            return Err(Errors::UnsupportedSerializationError(format!("Node can have a single anchor, {name} follows {anchor}")));
+        }
+
+        self.ser.anchor = Some(name);
+        Ok(())
+    }
+
+    /// Writes the comment unless it was written before the key or dash of the node already, then the node. A
+    /// comment can only stand on its own lines before the root of a block document anywhere else.
+    fn process_commented<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Errors> {
+        if std::mem::replace(&mut self.first, false) {
+            let written = std::mem::take(&mut self.ser.comment_written);
+
+            if !written && !self.flow && !self.ser.key && self.ser.pending.is_none() {
+                let comment = comment_text(value).unwrap_or_default();
+                self.ser.write_line_break()?;
+                write_comment(&comment, self.ser.level, self.ser.writer)?;
+            }
+
+            return Ok(());
+        }
+
+        T::serialize(value, &mut *self.ser)
+    }
+
+    /// Buffers the element in both styles, and writes the elements so far in block style once one of them is a
+    /// collection or they don't fit within the width anymore
+    fn process_leaf<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Errors> {
+        let mut flow = String::new();
+        let mut serializer = self.ser.nested(&mut flow);
+        serializer.flow = true;
+        T::serialize(value, &mut serializer)?;
+        let collection = serializer.collection;
+
+        let mut block = String::new();
+        let mut serializer = self.ser.nested(&mut block);
+        serializer.pending = Some(Pending::Element);
+        serializer.incr_level();
+        T::serialize(value, &mut serializer)?;
+        self.ser.anchors = serializer.anchors;
+
+        let leaves = self.leaves.get_or_insert_with(Vec::new);
+        leaves.push((flow, block));
+        let width = leaves.iter().map(|(flow, _)| flow.chars().count() + 2).sum::<usize>();
+
+        if collection || width > self.ser.options.compact_leaf_seqs.unwrap_or(0) {
+            self.write_block_leaves()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the buffered elements in block style, the elements following them are written as they come
+    fn write_block_leaves(&mut self) -> Result<(), Errors> {
+        let Some(leaves) = self.leaves.take() else {
+            return Ok(());
+        };
+
+        self.ser.begin_collection()?;
+
+        for (_, block) in leaves {
+            self.ser.write_line_break()?;
+            self.ser.writer.write_char('-')?;
+            self.ser.writer.write_str(&block)?;
+            self.ser.end_line();
+        }
+
+        Ok(())
     }
 
-    fn process_end(self) -> Result<(), Errors> {
-        if let Some(0) = self.len {
+    fn process_end(mut self) -> Result<(), Errors> {
+        if self.anchored || self.commented {
+            return Ok(());
+        }
+
+        if let Some(leaves) = self.leaves.take() {
+            self.ser.begin_node(false)?;
+            self.ser.writer.write_char('[')?;
+
+            for (i, (flow, _)) in leaves.iter().enumerate() {
+                if i > 0 {
+                    self.ser.writer.write_str(", ")?;
+                }
+
+                self.ser.writer.write_str(flow)?;
+            }
+
+            self.ser.writer.write_char(']')?;
+        }
+
+        if self.flow || self.len == Some(0) {
             self.ser.writer.write_char(']')?;
         }
 
+        if self.variant {
+            self.ser.writer.write_char('}')?;
+        }
+
         Ok(())
     }
 }
@@ -125,11 +429,8 @@ impl<'a, 'se> SerializeTupleVariant for SequenceSerializer<'a, 'se> {
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-
-        if let Some(l) = self.len {
-            if l > 0 {
-                self.ser.decr_level();
-            }
+        if !self.flow && !self.tagged && self.len.is_some_and(|len| len > 0) {
+            self.ser.decr_level();
         }
 
         self.process_end()
@@ -138,24 +439,231 @@ impl<'a, 'se> SerializeTupleVariant for SequenceSerializer<'a, 'se> {
 
 pub struct MapSerializer<'a, 'se> {
     ser: &'a mut YamlSerializer<'se>,
+    flow: bool,
+    first: bool,
+    /// The mapping is the value of a flow mapping of a struct variant, e.g. `{Variant: {x: 1}}`
+    variant: bool,
+    /// The mapping is a struct variant in tag form, e.g. `!Variant {x: 1}`, which isn't nested in a mapping
+    tagged: bool,
+    /// Entries written so far along with their keys, if they are sorted by key before they are written
+    entries: Option<Vec<(String, String)>>,
+    /// Key of the entry whose value comes next, when keys are written along with their values
+    buffered_key: Option<(String, bool)>,
+    /// Comment of the entry serialized next, written before its key, see [`Commented`](crate::Commented)
+    comment: Option<String>,
+    /// The comment of the value coming next was written before its key
+    comment_written: bool,
+    /// A key was serialized and its value comes next
+    awaiting_value: bool,
 }
 
 impl<'a, 'se> MapSerializer<'a, 'se> {
+    /// Serializes the key and the value of an entry. The comment of a [`Commented`](crate::Commented) value goes on
+    /// the lines before the key.
+    fn process_entry<K: Serialize + ?Sized, V: Serialize + ?Sized>(&mut self, key: &K, value: &V) -> Result<(), Errors> {
+        if !self.flow {
+            self.comment = comment_of(value);
+        }
+
+        self.process_key(key)?;
+        self.process_value(value)
+    }
+
+    /// Writes the comment of the entry, the line break before it is written already
+    fn write_entry_comment(&mut self) -> Result<(), Errors> {
+        if let Some(comment) = self.comment.take() {
+            write_comment(&comment, self.ser.level, self.ser.writer)?;
+            self.comment_written = true;
+        }
+
+        Ok(())
+    }
+
     fn process_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Errors> {
-        T::serialize(key, &mut *self.ser)?;
-        self.ser.writer.write_str(":\n")?;
+        if std::mem::replace(&mut self.awaiting_value, true) {
+            return Err(Errors::UnsupportedSerializationError(String::from("Map key serialized before the value of the key before it")));
+        }
+
+        // sorting needs the key of every entry, omitting none values needs to see the value before the key
+        if self.entries.is_some() || self.ser.options.omit_none_values {
+            let mut output = String::new();
+            let mut serializer = self.ser.nested(&mut output);
+            serializer.key = true;
+            T::serialize(key, &mut serializer)?;
+            let explicit_key = serializer.explicit_key;
+            self.ser.anchors = serializer.anchors;
+            self.buffered_key = Some((output, explicit_key));
+            return Ok(());
+        }
+
+        self.begin_entry()?;
+
+        if self.comment.is_some() {
+            self.ser.write_line_break()?;
+            self.write_entry_comment()?;
+        }
+
+        self.ser.key = true;
+        let result = T::serialize(key, &mut *self.ser);
+        self.ser.key = false;
+        self.ser.flow = self.flow;
+        result?;
+        self.end_key()
+    }
+
+    /// Separates the entry from the one before it in a flow mapping
+    fn begin_entry(&mut self) -> Result<(), Errors> {
+        let first = std::mem::replace(&mut self.first, false);
+
+        if !first && self.flow {
+            self.ser.writer.write_str(", ")?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the indicator between the key and its value
+    fn end_key(&mut self) -> Result<(), Errors> {
+        if self.flow {
+            self.ser.writer.write_str(": ")?;
+            return Ok(());
+        }
+
+        // the value of an explicit key starts on the next line
+        if std::mem::take(&mut self.ser.explicit_key) {
+            self.ser.writer.write_char('\n')?;
+            write_indent(self.ser.level, self.ser.writer)?;
+        }
+
+        self.ser.writer.write_char(':')?;
+        self.ser.pending = Some(Pending::Value);
         self.ser.incr_level();
-        write_indent(self.ser.level, self.ser.writer)
+        Ok(())
     }
 
     fn process_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Errors> {
+        if !std::mem::take(&mut self.awaiting_value) {
+            return Err(Errors::UnsupportedSerializationError(String::from("Map value serialized before its key")));
+        }
+
+        let buffered_key = self.buffered_key.take();
+
+        if self.ser.options.omit_none_values && is_none(value) {
+            return Ok(());
+        }
+
+        if let Some(entries) = &mut self.entries {
+            let Some((key, explicit_key)) = buffered_key else {
+                return Err(Errors::UnsupportedSerializationError(String::from("Map value serialized before its key")));
+            };
+
+            // the entry as process_key and process_value would write it
+            let mut output = String::new();
+            let commented = self.comment.is_some();
+
+            if let Some(comment) = self.comment.take() {
+                write_comment(&comment, self.ser.level, &mut output)?;
+            }
+
+            output.push_str(&key);
+
+            if self.flow {
+                output.push_str(": ");
+            } else {
+                if explicit_key {
+                    output.push('\n');
+                    write_indent(self.ser.level, &mut output)?;
+                }
+
+                output.push(':');
+            }
+
+            let mut serializer = self.ser.nested(&mut output);
+            serializer.comment_written = commented;
+
+            if !self.flow {
+                serializer.pending = Some(Pending::Value);
+                serializer.incr_level();
+            }
+
+            T::serialize(value, &mut serializer)?;
+            self.ser.anchors = serializer.anchors;
+            entries.push((key, output));
+            self.first = false;
+            return Ok(());
+        }
+
+        if let Some((key, explicit_key)) = buffered_key {
+            self.begin_entry()?;
+            self.ser.write_line_break()?;
+            self.write_entry_comment()?;
+            self.ser.writer.write_str(&key)?;
+
+            // the flag belongs to the key of this mapping, a key around the mapping keeps its own
+            let outer_explicit_key = std::mem::replace(&mut self.ser.explicit_key, explicit_key);
+            self.end_key()?;
+            self.ser.explicit_key = outer_explicit_key;
+        }
+
+        self.ser.comment_written = std::mem::take(&mut self.comment_written);
         T::serialize(value, &mut *self.ser)?;
+
+        if self.flow {
+            return Ok(());
+        }
+
         self.ser.decr_level();
-        self.ser.writer.write_char('\n')?;
-        write_indent(self.ser.level, self.ser.writer)
+        self.ser.end_line();
+        Ok(())
+    }
+
+    /// Writes the entries sorted by key
+    fn write_sorted_entries(&mut self) -> Result<(), Errors> {
+        let Some(mut entries) = self.entries.take() else {
+            return Ok(());
+        };
+
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (index, (_, entry)) in entries.iter().enumerate() {
+            if self.flow {
+                if index > 0 {
+                    self.ser.writer.write_str(", ")?;
+                }
+
+                self.ser.writer.write_str(entry)?;
+            } else {
+                self.ser.write_line_break()?;
+                self.ser.writer.write_str(entry)?;
+                self.ser.end_line();
+            }
+        }
+
+        Ok(())
     }
 
-    fn process_end(self) -> Result<(), Errors> {
+    fn process_end(mut self) -> Result<(), Errors> {
+        if self.awaiting_value {
+            return Err(Errors::UnsupportedSerializationError(String::from("Map ended before the value of its last key")));
+        }
+
+        self.write_sorted_entries()?;
+
+        if self.flow {
+            self.ser.writer.write_char('}')?;
+        } else if self.first {
+            // an empty mapping is written in flow style, on the same line as the dash or key before it
+            if std::mem::take(&mut self.ser.line_break) {
+                self.ser.writer.write_char(' ')?;
+            }
+
+            self.ser.writer.write_str("{}")?;
+        }
+
+        if self.variant {
+            self.ser.writer.write_char('}')?;
+        }
+
         Ok(())
     }
 }
@@ -172,6 +680,10 @@ impl<'a, 'se> SerializeMap for MapSerializer<'a, 'se> {
         self.process_value(value)
     }
 
+    fn serialize_entry<K: Serialize + ?Sized, V: Serialize + ?Sized>(&mut self, key: &K, value: &V) -> Result<(), Self::Error> {
+        self.process_entry(key, value)
+    }
+
     fn end(self) -> Result<Self::Ok, Self::Error> {
         self.process_end()
     }
@@ -182,8 +694,7 @@ impl<'a, 'se> SerializeStruct for MapSerializer<'a, 'se> {
     type Error = Errors;
 
     fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> {
-        self.process_key(key)?;
-        self.process_value(value)
+        self.process_entry(key, value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
@@ -196,62 +707,267 @@ impl<'a, 'se> SerializeStructVariant for MapSerializer<'a, 'se> {
     type Error = Errors;
 
     fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> {
-        self.process_key(key)?;
-        self.process_value(value)
+        self.process_entry(key, value)
     }
 
-    fn end(self) -> Result<Self::Ok, Self::Error> {
-        self.ser.decr_level();
+    fn end(mut self) -> Result<Self::Ok, Self::Error> {
+        // the entries belong to the level of the variant
+        self.write_sorted_entries()?;
+
+        if !self.flow && !self.tagged {
+            self.ser.decr_level();
+        }
+
         self.process_end()
     }
 }
 
-trait TypeWriter<'se> {
-    type Ok;
-    type Error;
-
-    fn write_str(v: &str, level: i32, writer: &'se mut dyn Write) -> Result<Self::Ok, Self::Error>;
-}
+/// Writes the string in the style, or double-quoted if the style can't carry it where it is written
+fn write_scalar(v: &str, style: ScalarStyle, context: ScalarContext, writer: &mut dyn Write) -> Result<(), Errors> {
+    let escapes = v.chars().any(needs_escape);
+    let single_line = !escapes && !v.contains('\n');
+    let chomping = if escapes || context.key || context.flow { None } else { block_chomping(v) };
+
+    match (style, chomping) {
+        (ScalarStyle::Plain, _) if single_line && is_plain_safe(v, context.flow) => {
+            writer.write_str(v)?;
+        },
+        (ScalarStyle::SingleQuoted, _) if single_line => {
+            write!(writer, "'{}'", escape_str(v))?;
+        },
+        (ScalarStyle::Literal, Some(chomping)) => {
+            write_block_scalar(&format!("|{chomping}"), v.lines(), context.level, writer)?;
+        },
+        // lines starting with a space aren't folded
+        (ScalarStyle::Folded, Some(chomping)) if !v.lines().any(|line| line.starts_with(' ')) => {
+            let content = v.trim_end_matches('\n');
+            let mut lines = vec![];
+
+            // a line break between two lines is folded into a space, an empty line stands for a line break
+            for (index, line) in content.split('\n').enumerate() {
+                if index > 0 && !lines.last().is_some_and(|last: &&str| last.is_empty()) {
+                    lines.push("");
+                }
+
+                lines.push(line);
+            }
 
-struct InHouseTypeWriter {}
+            // trailing line breaks aren't folded
+            let trailing = (v.len() - content.len()).saturating_sub(1);
+            lines.extend(std::iter::repeat_n("", trailing));
+            write_block_scalar(&format!(">{chomping}"), lines.into_iter(), context.level, writer)?;
+        },
+        _ => {
+            write!(writer, "\"{}\"", escape_double_quoted(v, false))?;
+        },
+    }
 
-impl<'se> TypeWriter<'se> for InHouseTypeWriter {
-    type Ok = ();
-    type Error = Errors;
+    Ok(())
+}
 
-    fn write_str(v: &str, level: i32, writer: &'se mut dyn Write) -> Result<Self::Ok, Self::Error> {
-        if v.contains('\n') {
-            write!(writer, "|-")?;
+/// Writes the header of a block scalar and its lines. The line break ending the last line is written by whatever
+/// follows the scalar.
+fn write_block_scalar<'a>(header: &str, lines: impl Iterator<Item = &'a str>, level: i32, writer: &mut dyn Write) -> Result<(), Errors> {
+    writer.write_str(header)?;
 
-            for line in v.lines() {
-                writeln!(writer)?;
-                write_indent(level + 1, writer)?;
-                write!(writer, "{line}")?;
-            }
+    for line in lines {
+        writer.write_char('\n')?;
 
-            Ok(())
-        } else {
-            write!(writer, "'{}'", escape_str(v))?;
-            Ok(())
+        // empty lines have no indentation, which would be trailing whitespace
+        if !line.is_empty() {
+            write_indent(level.max(1), writer)?;
+            writer.write_str(line)?;
         }
     }
+
+    Ok(())
 }
 
 pub struct YamlSerializer<'se> {
     level: i32,
     writer: &'se mut dyn Write,
+    options: SerializerOptions,
+    /// The node is a mapping key, which is written on a single line
+    key: bool,
+    /// The key is a collection, written after the `?` indicator
+    explicit_key: bool,
+    /// Collections are written in flow style
+    flow: bool,
+    /// Sequence dash or mapping key written last, the node decides whether it continues on the same line
+    pending: Option<Pending>,
+    /// An entry ended last. Its line break and the indentation of the next line are written once more content
+    /// follows, so no line ends with spaces and the document ends with a single line break.
+    line_break: bool,
+    /// Anchor of the node written next, e.g. `&limits`
+    anchor: Option<String>,
+    /// Local tag of the node written next, which names the variant of an enum, e.g. `!Variant`
+    tag: Option<&'static str>,
+    /// Anchors written so far, which aliases may refer to
+    anchors: HashSet<String>,
+    /// The string serialized next is the name of an anchor or alias, which is kept in `anchor` instead
+    anchor_name: bool,
+    /// A collection was started, which tells a sequence buffering its elements that they aren't all scalars
+    collection: bool,
+    /// The comment of the node serialized next was written before its key or dash already
+    comment_written: bool,
+    /// Radix of the notation the integer serialized next is written in, 16 or 8, see
+    /// [`with::hex_int`](crate::with::hex_int)
+    radix: Option<u32>,
+    /// The string serialized next is quoted whatever style it would have, see [`with::quoted`](crate::with::quoted)
+    quoted: bool,
+}
+
+/// Writes `\r\n` for every `\n` written into it
+struct CrLfWriter<'w> {
+    inner: &'w mut dyn Write,
+}
+
+impl Write for CrLfWriter<'_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let mut lines = s.split('\n');
+
+        if let Some(line) = lines.next() {
+            self.inner.write_str(line)?;
+        }
+
+        for line in lines {
+            self.inner.write_str("\r\n")?;
+            self.inner.write_str(line)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Fails with the parse error if the output isn't well-formed YAML
+fn check_output(output: String) -> Result<(), Errors> {
+    let mut parser = Parser::new_from_str(&output);
+
+    loop {
+        match parser.next_token() {
+            Ok((Event::StreamEnd, _)) => return Ok(()),
+            Ok(_) => {},
+            Err(error) => return Err(Errors::InvalidOutputError { error: error.to_string(), output }),
+        }
+    }
+}
+
+/// Writes into the writer and keeps a copy of what it wrote
+struct TeeWriter<'w> {
+    inner: &'w mut dyn Write,
+    copy: &'w mut String,
+}
+
+impl Write for TeeWriter<'_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.copy.push_str(s);
+        self.inner.write_str(s)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pending {
+    Element,
+    Value,
 }
 
 impl<'se> YamlSerializer<'se> {
     pub fn new(writer: &'se mut dyn std::fmt::Write) -> Self {
+        Self::with_options(writer, SerializerOptions::default())
+    }
+
+    pub fn with_options(writer: &'se mut dyn std::fmt::Write, options: SerializerOptions) -> Self {
         YamlSerializer {
             level: 0,
-            writer
+            writer,
+            flow: options.style == Style::Flow,
+            options,
+            key: false,
+            explicit_key: false,
+            pending: None,
+            line_break: false,
+            anchor: None,
+            tag: None,
+            anchors: HashSet::new(),
+            anchor_name: false,
+            collection: false,
+            comment_written: false,
+            radix: None,
+            quoted: false,
+        }
+    }
+
+    /// Writes the value as a document, which ends with a single line break. The serializer may write any number
+    /// of documents, with other text, e.g. comments, written in between through [`writer`](Self::writer).
+    pub fn write<T: Serialize>(&mut self, data: T) -> Result<(), Errors> {
+        if self.options.line_ending == LineEnding::CrLf {
+            // every line feed the serializer writes is a line break, strings with one are escaped or written as
+            // lines of a block scalar
+            let mut writer = CrLfWriter { inner: &mut *self.writer };
+            let options = SerializerOptions { line_ending: LineEnding::Lf, ..self.options.clone() };
+            return YamlSerializer::with_options(&mut writer, options).write(data);
+        }
+
+        if self.options.validate_output {
+            return self.write_validated(data);
+        }
+
+        self.write_document(data)
+    }
+
+    /// Writes the document along with a copy of it, which yaml_rust2 parses once the document is complete
+    fn write_validated<T: Serialize>(&mut self, data: T) -> Result<(), Errors> {
+        let mut output = String::new();
+        let mut writer = TeeWriter { inner: &mut *self.writer, copy: &mut output };
+        YamlSerializer::with_options(&mut writer, self.options.clone()).write_document(data)?;
+        check_output(output)
+    }
+
+    fn write_document<T: Serialize>(&mut self, data: T) -> Result<(), Errors> {
+        self.reset();
+
+        if let Some(comment) = &self.options.header_comment {
+            write_comment(comment, 0, self.writer)?;
+        }
+
+        if self.options.explicit_document_start {
+            self.writer.write_str("---\n")?;
+        }
+
+        data.serialize(&mut *self)?;
+        debug_assert_eq!(self.level, 0, "every collection restores the level it started at");
+        self.line_break = false;
+        self.writer.write_char('\n')?;
+
+        if self.options.explicit_document_end {
+            self.writer.write_str("...\n")?;
         }
+
+        Ok(())
     }
 
-    pub fn write<T: Serialize>(&'se mut self, data: T) -> Result<(), Errors> {
-        data.serialize(self)
+    /// The writer the documents are written into
+    pub fn writer(&mut self) -> &mut dyn Write {
+        self.writer
+    }
+
+    /// Clears what the document written before left behind, including its anchors and whatever state a failed
+    /// document ended in
+    fn reset(&mut self) {
+        self.level = 0;
+        self.flow = self.options.style == Style::Flow;
+        self.key = false;
+        self.explicit_key = false;
+        self.pending = None;
+        self.line_break = false;
+        self.anchor = None;
+        self.tag = None;
+        self.anchors.clear();
+        self.anchor_name = false;
+        self.collection = false;
+        self.comment_written = false;
+        self.radix = None;
+        self.quoted = false;
     }
 
     fn incr_level(&mut self) -> i32 {
@@ -264,20 +980,280 @@ impl<'se> YamlSerializer<'se> {
         self.level -= 1;
         self.level
     }
-}
 
-impl<'a, 'se> Serializer for &'a mut YamlSerializer<'se> {
-    type Ok = ();
-    type Error = Errors;
-    type SerializeSeq = SequenceSerializer<'a, 'se>;
-    type SerializeTuple = SequenceSerializer<'a, 'se>;
-    type SerializeTupleStruct = SequenceSerializer<'a, 'se>;
-    type SerializeTupleVariant = SequenceSerializer<'a, 'se>;
-    type SerializeMap = MapSerializer<'a, 'se>;
-    type SerializeStruct = MapSerializer<'a, 'se>;
+    /// Completes a pending sequence element or mapping value: a scalar follows the dash or key on the same
+    /// line, a collection starts on the next line
+    fn begin_node(&mut self, collection: bool) -> Result<(), Errors> {
+        self.write_line_break()?;
+        let properties = self.take_properties();
+
+        if self.pending.take().is_none() {
+            match properties {
+                // a block collection at the root of the document starts on the next line, the properties would
+                // belong to its first key otherwise
+                Some(properties) if collection && !self.flow => {
+                    writeln!(self.writer, "{properties}")?;
+                    write_indent(self.level, self.writer)?;
+                },
+                Some(properties) => write!(self.writer, "{properties} ")?,
+                None => {},
+            }
+
+            return Ok(());
+        }
+
+        if let Some(properties) = properties {
+            write!(self.writer, " {properties}")?;
+        }
+
+        if collection {
+            self.writer.write_char('\n')?;
+            write_indent(self.level, self.writer)
+        } else {
+            self.writer.write_char(' ')?;
+            Ok(())
+        }
+    }
+
+    /// Starts a block collection, whose entries start on the next line if it follows a dash or a key. The line
+    /// break is written along with the first entry, so an empty collection leaves no indentation behind.
+    fn begin_collection(&mut self) -> Result<(), Errors> {
+        self.write_line_break()?;
+        let pending = self.pending.take().is_some();
+
+        if let Some(properties) = self.take_properties() {
+            let separator = if pending { " " } else { "" };
+            write!(self.writer, "{separator}{properties}")?;
+            self.line_break = true;
+        } else if pending {
+            self.line_break = true;
+        }
+
+        Ok(())
+    }
+
+    /// Creates a serializer writing a node nested at the current level into another writer
+    fn nested<'w>(&self, writer: &'w mut dyn Write) -> YamlSerializer<'w> {
+        YamlSerializer {
+            level: self.level,
+            flow: self.flow,
+            anchors: self.anchors.clone(),
+            ..YamlSerializer::with_options(writer, self.options.clone())
+        }
+    }
+
+    /// Starts a collection which is a mapping key. An implicit key can't span lines, so the collection is written
+    /// in flow style as an explicit key, e.g. `? [a, 1]`.
+    fn begin_complex_key(&mut self) -> Result<(), Errors> {
+        if self.key && !self.flow {
+            self.begin_explicit_key()?;
+            self.flow = true;
+        }
+
+        Ok(())
+    }
+
+    /// Starts a scalar which is a mapping key. An implicit key is at most 1024 characters long, so a longer one is
+    /// written as an explicit key, e.g. `? 'long key'`.
+    fn begin_scalar_key(&mut self, v: &str) -> Result<(), Errors> {
+        // escapes are at most 6 characters long, so a shorter key fits in any style
+        if v.chars().count() * 6 + 2 <= MAX_IMPLICIT_KEY_LENGTH {
+            return Ok(());
+        }
+
+        let mut key = String::new();
+        let mut serializer = self.nested(&mut key);
+        serializer.key = true;
+        serializer.quoted = self.quoted;
+        serializer.write_str(v)?;
+
+        if key.chars().count() > MAX_IMPLICIT_KEY_LENGTH {
+            self.begin_explicit_key()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the indicator of an explicit key, whose value starts on the next line in a block mapping
+    fn begin_explicit_key(&mut self) -> Result<(), Errors> {
+        self.write_line_break()?;
+        self.writer.write_str("? ")?;
+        self.explicit_key = !self.flow;
+        Ok(())
+    }
+
+    /// Ends the line of a sequence element or mapping entry. Entries of nested collections ending together
+    /// share the line break.
+    fn end_line(&mut self) {
+        self.line_break = true;
+    }
+
+    /// Writes the line break of the entry ended last and the indentation of the content following it
+    fn write_line_break(&mut self) -> Result<(), Errors> {
+        if std::mem::take(&mut self.line_break) {
+            self.writer.write_char('\n')?;
+            write_indent(self.level, self.writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a string in the style the styler of the options chooses
+    fn write_str(&mut self, v: &str) -> Result<(), Errors> {
+        if self.options.escape_non_ascii && !v.is_ascii() {
+            // only escapes can represent other characters in ASCII
+            write!(self.writer, "\"{}\"", escape_double_quoted(v, true))?;
+            return Ok(());
+        }
+
+        let context = ScalarContext { level: self.level, key: self.key, flow: self.flow };
+
+        let style = match &self.options.scalar_styler {
+            Some(styler) => styler.style(v, context),
+            None => DefaultStyler::new(self.options.quote_style).style(v, context),
+        };
+
+        // a quoted string is single-quoted unless it needs double quotes, which carry any string
+        let style = match style {
+            ScalarStyle::Plain | ScalarStyle::Literal | ScalarStyle::Folded if self.quoted => ScalarStyle::SingleQuoted,
+            style => style,
+        };
+
+        write_scalar(v, style, context, self.writer)
+    }
+
+    /// Takes the anchor and the tag of the node written next, e.g. `&limits !Variant`
+    fn take_properties(&mut self) -> Option<String> {
+        let properties = match (self.anchor.take(), self.tag.take()) {
+            (Some(anchor), Some(tag)) => format!("&{anchor} !{tag}"),
+            (Some(anchor), None) => format!("&{anchor}"),
+            (None, Some(tag)) => format!("!{tag}"),
+            (None, None) => return None,
+        };
+
+        Some(properties)
+    }
+
+    /// Writes a node without content, which is null, along with its properties, e.g. `!Variant` of a unit variant
+    fn write_empty_node(&mut self) -> Result<(), Errors> {
+        self.write_line_break()?;
+        let pending = self.pending.take().is_some();
+
+        if let Some(properties) = self.take_properties() {
+            let separator = if pending { " " } else { "" };
+            write!(self.writer, "{separator}{properties}")?;
+
+            // `:` right after the properties would be part of them
+            if self.key {
+                self.writer.write_char(' ')?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes null in the style the options say. Only the value of a block mapping entry can be left empty, null
+    /// is written anywhere else.
+    fn write_null(&mut self) -> Result<(), Errors> {
+        let v = match self.options.null_style {
+            NullStyle::Tilde => "~",
+            NullStyle::Null => "null",
+            NullStyle::Empty if self.pending == Some(Pending::Value) => return self.write_empty_node(),
+            NullStyle::Empty => "null",
+        };
+
+        serialize_value!(self, v);
+    }
+
+    /// Writes a float from its shortest representation, or with the precision of the options, NaN and infinities
+    /// as the options say
+    fn write_float(&mut self, repr: String, v: f64) -> Result<(), Errors> {
+        if v.is_finite() {
+            let repr = match self.options.float_precision {
+                Some(precision) => format!("{v:.precision$}"),
+                None => repr,
+            };
+
+            let v = format_float(repr);
+            serialize_value!(self, v);
+        }
+
+        let v = match self.options.non_finite_floats {
+            NonFiniteFloats::Special if v.is_nan() => ".nan",
+            NonFiniteFloats::Special if v > 0.0 => ".inf",
+            NonFiniteFloats::Special => "-.inf",
+            NonFiniteFloats::Null => return self.write_null(),
+            NonFiniteFloats::Error => {
+                return Err(Errors::UnsupportedSerializationError(format!("Can not serialize {v}, only finite floats are allowed")));
+            },
+        };
+
+        serialize_value!(self, v);
+    }
+
+    /// Serializes the name of an anchor or an alias, which must be a string
+    fn read_anchor_name<T: Serialize + ?Sized>(&self, value: &T) -> Result<String, Errors> {
+        let mut ignored = String::new();
+        let mut serializer = self.nested(&mut ignored);
+        serializer.anchor_name = true;
+        value.serialize(&mut serializer)?;
+
+        let name = match serializer.anchor {
+            Some(name) if !serializer.anchor_name => name,
+            _ => return Err(Errors::UnsupportedSerializationError(String::from("Anchor name must be a string"))),
+        };
+
+        // names can't contain spaces or flow indicators, which would end them
+        let valid = !name.is_empty() && !name.contains(|c: char| c.is_whitespace() || c.is_control() || matches!(c, ',' | '[' | ']' | '{' | '}' | '\u{FEFF}'));
+
+        if !valid {
+            return Err(Errors::UnsupportedSerializationError(format!("Invalid anchor name {name:?}")));
+        }
+
+        Ok(name)
+    }
+
+    /// Writes the node with the anchor set before, which aliases written after it may refer to
+    fn write_anchored<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Errors> {
+        let name = self.anchor.clone().unwrap_or_default();
+        value.serialize(&mut *self)?;
+        self.anchors.insert(name);
+        Ok(())
+    }
+
+    /// Tags the node written next with the variant if enums are written in tag form. Returns false if they aren't,
+    /// or if the node is the value of a newtype variant, which has the tag of that variant already.
+    fn tag_variant(&mut self, variant: &'static str) -> bool {
+        if self.options.enum_representation != EnumRepr::Tag || self.tag.is_some() {
+            return false;
+        }
+
+        self.tag = Some(variant);
+        true
+    }
+
+    /// A mapping written on a single line, e.g. `VariantA: ~`, may follow a sequence dash but not a key
+    fn begin_inline_mapping(&mut self) -> Result<(), Errors> {
+        // properties would belong to the key of the mapping on the same line
+        let collection = self.pending == Some(Pending::Value) || self.anchor.is_some() || self.tag.is_some();
+        self.begin_node(collection)
+    }
+}
+
+impl<'a, 'se> Serializer for &'a mut YamlSerializer<'se> {
+    type Ok = ();
+    type Error = Errors;
+    type SerializeSeq = SequenceSerializer<'a, 'se>;
+    type SerializeTuple = SequenceSerializer<'a, 'se>;
+    type SerializeTupleStruct = SequenceSerializer<'a, 'se>;
+    type SerializeTupleVariant = SequenceSerializer<'a, 'se>;
+    type SerializeMap = MapSerializer<'a, 'se>;
+    type SerializeStruct = MapSerializer<'a, 'se>;
     type SerializeStructVariant = MapSerializer<'a, 'se>;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.begin_node(false)?;
+
         match v {
             true => self.writer.write_str("true")?,
             false => self.writer.write_str("false")?,
@@ -302,6 +1278,10 @@ impl<'a, 'se> Serializer for &'a mut YamlSerializer<'se> {
         serialize_value!(self, v);
     }
 
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        serialize_value!(self, v);
+    }
+
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
         serialize_value!(self, v);
     }
@@ -315,32 +1295,72 @@ impl<'a, 'se> Serializer for &'a mut YamlSerializer<'se> {
     }
 
     fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        match self.radix.take() {
+            Some(16) => {
+                let v = format!("0x{v:X}");
+                serialize_value!(self, v);
+            },
+            Some(8) => {
+                let v = format!("0o{v:o}");
+                serialize_value!(self, v);
+            },
+            _ => {
+                serialize_value!(self, v);
+            },
+        }
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
         serialize_value!(self, v);
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        serialize_value!(self, v);
+        self.write_float(format!("{v:?}"), v.into())
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        serialize_value!(self, v);
+        self.write_float(format!("{v:?}"), v)
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
-        serialize_value!(self, v);
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        InHouseTypeWriter::write_str(v, self.level, self.writer)
+        if std::mem::take(&mut self.anchor_name) {
+            self.anchor = Some(v.to_owned());
+            return Ok(());
+        }
+
+        if self.key {
+            self.begin_scalar_key(v)?;
+        }
+
+        self.begin_node(false)?;
+        self.write_str(v)
     }
 
-    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        Err(Errors::UnsupportedSerializationError("Serialization of bytes is not supported".to_owned()))
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let mut encoded = binary::encode(v);
+
+        if self.key && "!!binary ".len() + encoded.len() > MAX_IMPLICIT_KEY_LENGTH {
+            self.begin_explicit_key()?;
+        }
+
+        self.begin_node(false)?;
+        self.writer.write_str("!!binary ")?;
+
+        // long base64 becomes a block scalar of short lines, which a key or a flow collection can't contain
+        if encoded.len() > binary::LINE_LENGTH && !self.key && !self.flow {
+            let lines = encoded.as_bytes().chunks(binary::LINE_LENGTH).map(|line| std::str::from_utf8(line).unwrap_or_default());
+            encoded = lines.collect::<Vec<_>>().join("\n");
+        }
+
+        self.write_str(&encoded)
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        self.writer.write_char('~')?;
-        Ok(())
+        self.write_null()
     }
 
     fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error> {
@@ -356,156 +1376,367 @@ impl<'a, 'se> Serializer for &'a mut YamlSerializer<'se> {
     }
 
     fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<Self::Ok, Self::Error> {
-        write!(self.writer, "{}: ", variant)?;
+        if self.tag_variant(variant) {
+            return self.write_empty_node();
+        }
+
+        if !self.options.unit_variant_mapping {
+            return self.serialize_str(variant);
+        }
+
+        self.collection = true;
+        self.begin_complex_key()?;
+
+        if self.flow {
+            self.begin_node(false)?;
+            write!(self.writer, "{{{}: ", variant)?;
+            self.serialize_none()?;
+            self.writer.write_char('}')?;
+            return Ok(());
+        }
+
+        // null is the value of the entry, written in the null style of entries
+        self.begin_inline_mapping()?;
+        write!(self.writer, "{}:", variant)?;
+        self.pending = Some(Pending::Value);
         self.serialize_none()
     }
 
-    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
-        value.serialize(self)
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, name: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
+        if name == HEX_INT_TOKEN || name == OCTAL_INT_TOKEN {
+            self.radix = Some(if name == HEX_INT_TOKEN { 16 } else { 8 });
+            let result = value.serialize(&mut *self);
+            self.radix = None;
+            return result;
+        }
+
+        if name == FLOW_TOKEN {
+            let flow = std::mem::replace(&mut self.flow, true);
+            let result = value.serialize(&mut *self);
+            self.flow = flow;
+            return result;
+        }
+
+        if name == SINGLE_TOKEN && self.options.one_or_many_sequences {
+            let mut sequence = self.serialize_seq(Some(1))?;
+            SerializeSeq::serialize_element(&mut sequence, value)?;
+            return SerializeSeq::end(sequence);
+        }
+
+        if name == QUOTED_TOKEN {
+            self.quoted = true;
+            let result = value.serialize(&mut *self);
+            self.quoted = false;
+            return result;
+        }
+
+        if name != ALIAS_TOKEN {
+            return value.serialize(self);
+        }
+
+        let name = self.read_anchor_name(value)?;
+
+        if !self.anchors.contains(&name) {
+            return Err(Errors::UnsupportedSerializationError(format!("Alias to undefined anchor {name}")));
+        }
+
+        if let Some(properties) = self.take_properties() {
+            return Err(Errors::UnsupportedSerializationError(format!("Alias {name} can't have properties, found {properties}")));
+        }
+
+        self.begin_node(false)?;
+        write!(self.writer, "*{name}")?;
+
+        // `:` right after the alias would be part of its name
+        if self.key {
+            self.writer.write_char(' ')?;
+        }
+
+        Ok(())
     }
 
     fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _variant_index: u32, variant: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
-        writeln!(self.writer, "{}:", variant)?;
+        if self.tag_variant(variant) {
+            return value.serialize(self);
+        }
+
+        self.collection = true;
+        self.begin_complex_key()?;
+
+        if self.flow {
+            self.begin_node(false)?;
+            write!(self.writer, "{{{}: ", variant)?;
+            value.serialize(&mut *self)?;
+            self.writer.write_char('}')?;
+            return Ok(());
+        }
+
+        self.begin_node(true)?;
+        write!(self.writer, "{}:", variant)?;
+        self.pending = Some(Pending::Value);
         self.incr_level();
-        write_indent(self.level, self.writer)?;
         let result = value.serialize(&mut *self);
         self.decr_level();
         result
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        if let Some(0) = len {
+        self.collection = true;
+        self.begin_complex_key()?;
+
+        // an empty sequence is written in flow style on the same line
+        let flow = self.flow || len == Some(0);
+        // a sequence after a key or dash may fit on its line, which is known once its elements are serialized
+        let compact = !flow && self.pending.is_some() && self.options.compact_leaf_seqs.is_some();
+
+        if flow {
+            self.begin_node(false)?;
             self.writer.write_char('[')?;
-            Ok(SequenceSerializer {
-                len: Some(0),
-                ser: self,
-            })
-        } else {
-            Ok(SequenceSerializer {
-                len,
-                ser: self,
-            })
+        } else if !compact {
+            self.begin_collection()?;
         }
+
+        Ok(SequenceSerializer {
+            len,
+            flow: self.flow,
+            ser: self,
+            first: true,
+            variant: false,
+            tagged: false,
+            anchored: false,
+            leaves: compact.then(Vec::new),
+            commented: false,
+        })
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
         self.serialize_seq(Some(len))
     }
 
-    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct, Self::Error> {
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        if name == ANCHOR_TOKEN {
+            return Ok(SequenceSerializer {
+                len: None,
+                flow: self.flow,
+                ser: self,
+                first: true,
+                variant: false,
+                tagged: false,
+                anchored: true,
+                leaves: None,
+                commented: false,
+            });
+        }
+
+        if name == COMMENT_TOKEN {
+            return Ok(SequenceSerializer {
+                len: None,
+                flow: self.flow,
+                ser: self,
+                first: true,
+                variant: false,
+                tagged: false,
+                anchored: false,
+                leaves: None,
+                commented: true,
+            });
+        }
+
         self.serialize_seq(Some(len))
     }
 
     fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        if self.tag_variant(variant) {
+            let mut serializer = self.serialize_seq(Some(len))?;
+            serializer.tagged = true;
+            return Ok(serializer);
+        }
+
+        self.begin_complex_key()?;
+
+        if self.flow {
+            self.begin_node(false)?;
+            write!(self.writer, "{{{}: ", variant)?;
+            let mut serializer = self.serialize_seq(Some(len))?;
+            serializer.variant = true;
+            return Ok(serializer);
+        }
+
         if len == 0 {
+            self.begin_inline_mapping()?;
             write!(self.writer, "{}: ", variant)?;
             self.serialize_seq(Some(len))
         } else {
-            writeln!(self.writer, "{}:", variant)?;
+            self.begin_node(true)?;
+            write!(self.writer, "{}:", variant)?;
+            self.pending = Some(Pending::Value);
             self.incr_level();
-            write_indent(self.level, self.writer)?;
             self.serialize_seq(Some(len))
         }
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.collection = true;
+        self.begin_complex_key()?;
+
+        let flow = self.flow;
+
+        if flow {
+            self.begin_node(false)?;
+            self.writer.write_char('{')?;
+        } else {
+            self.begin_collection()?;
+        }
+
+        let entries = self.options.sort_keys.then(Vec::new);
+
         Ok(MapSerializer {
-            ser: self
+            ser: self,
+            flow,
+            first: true,
+            variant: false,
+            tagged: false,
+            entries,
+            buffered_key: None,
+            comment: None,
+            comment_written: false,
+            awaiting_value: false,
         })
     }
 
     fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct, Self::Error> {
-        self.serialize_map(Some(len))
+        let sort_fields = self.options.sort_struct_fields;
+        let mut serializer = self.serialize_map(Some(len))?;
+
+        if !sort_fields {
+            serializer.entries = None;
+        }
+
+        Ok(serializer)
     }
 
     fn serialize_struct_variant(self, name: &'static str, _variant_index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeStructVariant, Self::Error> {
-        writeln!(self.writer, "{}:", variant)?;
+        if self.tag_variant(variant) {
+            let mut serializer = self.serialize_struct(name, len)?;
+            serializer.tagged = true;
+            return Ok(serializer);
+        }
+
+        self.begin_complex_key()?;
+
+        if self.flow {
+            self.begin_node(false)?;
+            write!(self.writer, "{{{}: ", variant)?;
+            let mut serializer = self.serialize_struct(name, len)?;
+            serializer.variant = true;
+            return Ok(serializer);
+        }
+
+        self.begin_node(true)?;
+        write!(self.writer, "{}:", variant)?;
+        self.pending = Some(Pending::Value);
         self.incr_level();
-        write_indent(self.level, self.writer)?;
         self.serialize_struct(name, len)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::ser::YamlSerializer;
-    use serde::{Serialize};
+    use crate::ser::{Errors, SerializerOptions, YamlSerializer};
+    use serde::{Deserialize, Serialize};
 
     macro_rules! test {
         ($data:expr, $expected:literal) => {
             let data = $data;
             let mut output = String::new();
-            let mut serializer = YamlSerializer::new(&mut output);
+            let mut serializer = YamlSerializer::with_options(&mut output, SerializerOptions::new().validate_output(true));
             serializer.write(data).unwrap();
             assert_eq!($expected, output);
+            assert!(output.ends_with('\n') && !output.ends_with("\n\n"), "{output:?}");
+            assert!(!output.trim_end_matches('\n').ends_with([' ', '\n']), "{output:?}");
+            assert!(!output.lines().any(|line| line.ends_with([' ', '\t'])), "{output:?}");
         }
     }
 
+    /// Same as the crate's function, but the output is checked to parse again
+    fn to_string<T: Serialize>(value: T) -> Result<String, Errors> {
+        to_string_with_options(value, SerializerOptions::new())
+    }
+
+    /// Same as the crate's function, but the output is checked to parse again
+    fn to_string_with_options<T: Serialize>(value: T, options: SerializerOptions) -> Result<String, Errors> {
+        crate::ser::to_string_with_options(value, options.validate_output(true))
+    }
+
     #[test]
     fn should_work() {
         // primitives
-        test!(123, "123");
-        test!(0, "0");
-        test!(0.0, "0");
-        test!(0.53, "0.53");
-        test!(100u8, "100");
-        test!(100u16, "100");
-        test!(100u32, "100");
-        test!(100u64, "100");
-        test!(-100i16, "-100");
-        test!(-100i32, "-100");
-        test!(-100i64, "-100");
-
-        test!(0.54f32, "0.54");
-        test!(0.54f64, "0.54");
-        test!(-0.54f32, "-0.54");
-        test!(-0.54f64, "-0.54");
-
-        test!(true, "true");
-        test!(false, "false");
-
-        test!((), "~");
-        test!('a', "a");
-        test!("", "''");
+        test!(123, "123\n");
+        test!(0, "0\n");
+        test!(0.0, "0.0\n");
+        test!(0.53, "0.53\n");
+        test!(100u8, "100\n");
+        test!(100u16, "100\n");
+        test!(100u32, "100\n");
+        test!(100u64, "100\n");
+        test!(-100i16, "-100\n");
+        test!(-100i32, "-100\n");
+        test!(-100i64, "-100\n");
+        test!(u128::MAX, "340282366920938463463374607431768211455\n");
+        test!(i128::MIN, "-170141183460469231731687303715884105728\n");
+
+        test!(0.54f32, "0.54\n");
+        test!(0.54f64, "0.54\n");
+        test!(-0.54f32, "-0.54\n");
+        test!(-0.54f64, "-0.54\n");
+
+        test!(true, "true\n");
+        test!(false, "false\n");
+
+        test!((), "~\n");
+        test!('a', "a\n");
+        test!('#', "'#'\n");
+        test!('\t', "\"\\t\"\n");
+        test!("", "''\n");
 
         // strings
-        test!("Hello world", "'Hello world'");
-        test!(":Hello world", "':Hello world'");
-        test!("Hello 'world", r#"'Hello \'world'"#);
-        test!(String::from("Hello world"), "'Hello world'");
-        test!("First\nSecond\nThird", "|-\n  First\n  Second\n  Third");
-        test!("First\nSecond\nThird\n", "|-\n  First\n  Second\n  Third");
+        test!("Hello world", "Hello world\n");
+        test!(":Hello world", ":Hello world\n");
+        test!("Hello 'world", "Hello 'world\n");
+        test!(String::from("Hello world"), "Hello world\n");
+        test!("First\nSecond\nThird", "|-\n  First\n  Second\n  Third\n");
+        test!("First\nSecond\nThird\n", "|\n  First\n  Second\n  Third\n");
 
 
         // vectors
-        test!(Vec::<i32>::from([1,2,3]), "- \n  1\n- \n  2\n- \n  3\n");
-        test!(Vec::<i32>::new(), "[]");
-        test!(Vec::<Vec<i32>>::from([[1,2,3].into(), [4,5,6].into(), [7,8,9].into()]), "- \n  - \n   1\n  - \n   2\n  - \n   3\n  \n- \n  - \n   4\n  - \n   5\n  - \n   6\n  \n- \n  - \n   7\n  - \n   8\n  - \n   9\n  \n");
+        test!(Vec::<i32>::from([1,2,3]), "- 1\n- 2\n- 3\n");
+        test!(Vec::<i32>::new(), "[]\n");
+        test!(Vec::<Vec<i32>>::from([[1,2,3].into(), [4,5,6].into(), [7,8,9].into()]), "-\n  - 1\n  - 2\n  - 3\n-\n  - 4\n  - 5\n  - 6\n-\n  - 7\n  - 8\n  - 9\n");
 
         // tuples
-        test!((123, "Hello world", false), "- \n  123\n- \n  'Hello world'\n- \n  false\n");
-        test!((123, "Hello world", [1,2,3].to_vec()), "- \n  123\n- \n  'Hello world'\n- \n  - \n   1\n  - \n   2\n  - \n   3\n  \n");
+        test!((123, "Hello world", false), "- 123\n- Hello world\n- false\n");
+        test!((123, "Hello world", [1,2,3].to_vec()), "- 123\n- Hello world\n-\n  - 1\n  - 2\n  - 3\n");
 
         // structs
         #[derive(Serialize, Debug)]
         struct TestUnitStruct;
-        test!(TestUnitStruct, "~");
+        test!(TestUnitStruct, "~\n");
 
         #[derive(Serialize, Debug)]
         struct TestEmptyTupleStruct();
-        test!(TestEmptyTupleStruct(), "[]");
+        test!(TestEmptyTupleStruct(), "[]\n");
 
         #[derive(Serialize, Debug)]
         struct TestTupleStruct(i32, bool, String, Option<f64>);
-        test!(TestTupleStruct(123, false, String::from("Hello world"), Some(4.5)), "- \n  123\n- \n  false\n- \n  'Hello world'\n- \n  4.5\n");
-        test!(TestTupleStruct(123, false, String::from("Hello world"), None), "- \n  123\n- \n  false\n- \n  'Hello world'\n- \n  ~\n");
+        test!(TestTupleStruct(123, false, String::from("Hello world"), Some(4.5)), "- 123\n- false\n- Hello world\n- 4.5\n");
+        test!(TestTupleStruct(123, false, String::from("Hello world"), None), "- 123\n- false\n- Hello world\n- ~\n");
 
         #[derive(Serialize, Debug)]
         struct TestStruct {
             x: i32,
             y: String,
         }
-        test!(TestStruct { x: 321, y: String::from("Hello") }, "'x':\n  321\n'y':\n  'Hello'\n");
+        test!(TestStruct { x: 321, y: String::from("Hello") }, "x: 321\ny: Hello\n");
 
         // enums
         #[derive(Serialize, Debug)]
@@ -516,11 +1747,11 @@ mod test {
             VariantD(TestStruct), // new-type variant
             VariantE { x: f64, y: bool }, // struct variant
         }
-        test!(TestEnum::VariantA, "VariantA: ~");
-        test!(TestEnum::VariantB(), "VariantB: []");
-        test!(TestEnum::VariantC(3000, String::from("Hello world")), "VariantC:\n  - \n   3000\n  - \n   'Hello world'\n  ");
-        test!(TestEnum::VariantD(TestStruct { x: 1, y: String::from("Hello world") }), "VariantD:\n  'x':\n   1\n  'y':\n   'Hello world'\n  ");
-        test!(TestEnum::VariantE { x: 45.0, y: false }, "VariantE:\n  'x':\n   45\n  'y':\n   false\n  ");
+        test!(TestEnum::VariantA, "VariantA\n");
+        test!(TestEnum::VariantB(), "VariantB: []\n");
+        test!(TestEnum::VariantC(3000, String::from("Hello world")), "VariantC:\n  - 3000\n  - Hello world\n");
+        test!(TestEnum::VariantD(TestStruct { x: 1, y: String::from("Hello world") }), "VariantD:\n  x: 1\n  y: Hello world\n");
+        test!(TestEnum::VariantE { x: 45.0, y: false }, "VariantE:\n  x: 45.0\n  y: false\n");
 
         // nested struct
         #[derive(Serialize, Debug)]
@@ -543,11 +1774,1535 @@ mod test {
             i: true,
             b: TestEnum::VariantE { x: 12.321, y: true },
             u: (555, String::from("Hello world"), false),
-        }, "'x':\n  123\n'nested':\n  'x':\n   321\n  'y':\n   'Hello world'\n  \n'y':\n  VariantD:\n   'x':\n    444\n   'y':\n    |-\n     Hello\n     world\n   \n'z':\n  - \n   1\n  - \n   2\n  - \n   3\n  \n'i':\n  true\n'b':\n  VariantE:\n   'x':\n    12.321\n   'y':\n    true\n   \n'u':\n  - \n   555\n  - \n   'Hello world'\n  - \n   false\n  \n");
+        }, "x: 123\nnested:\n  x: 321\n  y: Hello world\ny:\n  VariantD:\n    x: 444\n    y: |-\n      Hello\n      world\nz:\n  - 1\n  - 2\n  - 3\ni: true\nb:\n  VariantE:\n    x: 12.321\n    y: true\nu:\n  - 555\n  - Hello world\n  - false\n");
 
         {
             type Map = std::collections::BTreeMap<String, i32>;
-            test!(Map::from([ (String::from("Hello"), 123), (String::from("World"), 10) ]), "'Hello':\n  123\n'World':\n  10\n");
+            test!(Map::from([ (String::from("Hello"), 123), (String::from("World"), 10) ]), "Hello: 123\nWorld: 10\n");
+        }
+    }
+
+    #[test]
+    fn reuse_serializer() {
+        use crate::Anchored;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Service {
+            name: String,
+            ports: Vec<u16>,
+        }
+
+        let web = Service { name: String::from("web"), ports: vec![80, 443] };
+        let db = Service { name: String::from("db"), ports: vec![] };
+
+        let mut output = String::new();
+        let mut serializer = YamlSerializer::with_options(&mut output, SerializerOptions::new().explicit_document_start(true));
+        serializer.write(&web).unwrap();
+        serializer.writer().write_str("# the database\n").unwrap();
+        serializer.write(&db).unwrap();
+
+        // a failed document leaves no state behind, anchors are local to their document
+        assert!(serializer.write(Anchored::<i32>::reference("missing")).is_err());
+        serializer.write(vec![Anchored::define("a", &1), Anchored::reference("a")]).unwrap();
+        assert!(serializer.write(Anchored::<i32>::reference("a")).is_err());
+
+        assert_eq!("---\nname: web\nports:\n  - 80\n  - 443\n# the database\n---\nname: db\nports: []\n---\n---\n- &a 1\n- *a\n---\n", output);
+
+        let mut output = String::new();
+        let mut serializer = YamlSerializer::with_options(&mut output, SerializerOptions::new().explicit_document_start(true));
+        serializer.write(&web).unwrap();
+        serializer.writer().write_str("# the database\n").unwrap();
+        serializer.write(&db).unwrap();
+        assert_eq!(vec![web, db], crate::from_str_multi::<Service>(&output).unwrap(), "{output}");
+    }
+
+    #[test]
+    fn compact_leaf_seqs() {
+        use crate::from_str;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Service {
+            ports: Vec<u16>,
+            hosts: Vec<String>,
+            matrix: Vec<Vec<i32>>,
+        }
+
+        let options = SerializerOptions::new().compact_leaf_seqs(40);
+
+        let service = Service {
+            ports: vec![80, 443, 8080],
+            hosts: vec![String::from("a, b"), String::from("web")],
+            matrix: vec![vec![1, 2], vec![]],
+        };
+        let output = to_string_with_options(&service, options.clone()).unwrap();
+        assert_eq!("ports: [80, 443, 8080]\nhosts: ['a, b', web]\nmatrix:\n  - [1, 2]\n  - []\n", output);
+        assert_eq!(service, from_str(&output).unwrap());
+
+        let service = Service { ports: (0..50).collect(), hosts: vec![], matrix: vec![] };
+        let output = to_string_with_options(&service, options.clone()).unwrap();
+        let expected = (0..50).map(|port| format!("  - {port}\n")).collect::<String>();
+        assert_eq!(format!("ports:\n{expected}hosts: []\nmatrix: []\n"), output);
+        assert_eq!(service, from_str(&output).unwrap());
+
+        // a sequence at the root of the document stays in block style
+        assert_eq!("- 1\n- 2\n", to_string_with_options(vec![1, 2], options).unwrap());
+    }
+
+    #[test]
+    fn escape_non_ascii() {
+        use std::collections::BTreeMap;
+        use crate::from_str;
+
+        let value = BTreeMap::from([
+            (String::from("café"), vec![String::from("naïve résumé"), String::from("😀 ok"), String::from("plain")]),
+            (String::from("multi"), vec![String::from("ligne\ndeux é\n")]),
+        ]);
+
+        let output = to_string_with_options(&value, SerializerOptions::new().escape_non_ascii(true)).unwrap();
+        assert_eq!("\"caf\\u00E9\":
+  - \"na\\u00EFve r\\u00E9sum\\u00E9\"
+  - \"\\U0001F600 ok\"
+  - plain
+multi:
+  - \"ligne\\ndeux \\u00E9\\n\"
+", output);
+        assert!(output.is_ascii());
+        assert_eq!(value, from_str::<BTreeMap<String, Vec<String>>>(&output).unwrap());
+
+        let output = to_string(&value).unwrap();
+        assert_eq!("café:\n  - naïve résumé\n  - 😀 ok\n  - plain\nmulti:\n  - |\n    ligne\n    deux é\n", output);
+        assert_eq!(value, from_str::<BTreeMap<String, Vec<String>>>(&output).unwrap());
+    }
+
+    #[test]
+    fn variant_levels() {
+        use crate::ser::EnumRepr;
+        use crate::from_str;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+        enum Shape {
+            Point,
+            Circle(u32),
+            Line(u32, u32),
+            Empty(),
+            Rect { w: u32, h: u32 },
+            Nested(Box<Shape>),
+            List(Vec<Shape>),
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Layer {
+            first: Shape,
+            second: Shape,
+            name: String,
+        }
+
+        let layer = Layer { first: Shape::Rect { w: 1, h: 2 }, second: Shape::Line(3, 4), name: String::from("top") };
+        let output = to_string_with_options(&layer, SerializerOptions::new()).unwrap();
+        assert_eq!("first:\n  Rect:\n    w: 1\n    h: 2\nsecond:\n  Line:\n    - 3\n    - 4\nname: top\n", output);
+        assert_eq!(layer, from_str(&output).unwrap());
+
+        // after any variant the level is back where it was, so the field following it is aligned with it
+        let shapes = [
+            Shape::Point,
+            Shape::Circle(1),
+            Shape::Line(1, 2),
+            Shape::Empty(),
+            Shape::Rect { w: 1, h: 2 },
+            Shape::Nested(Box::new(Shape::Rect { w: 1, h: 2 })),
+            Shape::Nested(Box::new(Shape::Line(1, 2))),
+            Shape::List(vec![Shape::Line(1, 2), Shape::Rect { w: 1, h: 2 }, Shape::Point]),
+        ];
+
+        for options in [SerializerOptions::new(), SerializerOptions::new().enum_representation(EnumRepr::Tag), SerializerOptions::new().compact_leaf_seqs(40), SerializerOptions::new().unit_variant_mapping(true)] {
+            for first in &shapes {
+                for second in &shapes {
+                    let layer = vec![Layer { first: first.clone(), second: second.clone(), name: String::from("top") }];
+                    let output = to_string_with_options(&layer, options.clone()).unwrap();
+                    assert!(output.ends_with("  name: top\n"), "{output}");
+                    assert_eq!(layer, from_str::<Vec<Layer>>(&output).unwrap(), "{output}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn canonical() {
+        use std::collections::{BTreeMap, HashMap};
+        use crate::{from_str, to_string_canonical};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Limits {
+            memory: String,
+            cpu: f64,
+        }
+
+        let hashed: HashMap<String, HashMap<&str, Vec<f64>>> = (0..20)
+            .map(|i| (format!("key {i}"), HashMap::from([("b", vec![i as f64, 1e300]), ("a", vec![f64::NAN, 0.5])])))
+            .collect();
+        let sorted: BTreeMap<String, BTreeMap<&str, Vec<f64>>> = hashed.iter()
+            .map(|(key, value)| (key.clone(), value.iter().map(|(k, v)| (*k, v.clone())).collect()))
+            .collect();
+
+        let output = to_string_canonical(&hashed).unwrap();
+        assert_eq!(to_string_canonical(&sorted).unwrap(), output);
+        assert!(output.starts_with("\"key 0\":\n  \"a\":\n    - .nan\n    - 0.5\n  \"b\":\n    - 0.0\n    - 1.0e+300\n\"key 1\":\n"), "{output}");
+
+        // the fields of a struct are sorted too, like the entries of a map
+        let limits = Limits { memory: String::from("1Gi"), cpu: 2.0 };
+        let output = to_string_canonical(&limits).unwrap();
+        assert_eq!("\"cpu\": 2.0\n\"memory\": \"1Gi\"\n", output);
+        assert_eq!(limits, from_str(&output).unwrap());
+
+        // multi-line strings are double-quoted as well
+        assert_eq!("\"a\\nb\\n\"\n", to_string_canonical("a\nb\n").unwrap());
+    }
+
+    #[test]
+    fn compact() {
+        use std::collections::BTreeMap;
+        use crate::{from_str, to_string_compact};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Inner {
+            Unit,
+            Pair(i32, String),
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Outer {
+            Wrapped(Inner),
+            Fields { inner: Vec<Inner>, note: Option<String> },
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: i32,
+            y: Vec<String>,
+            z: Option<i32>,
+        }
+
+        let point = Point { x: 1, y: vec![String::from("a"), String::from("b")], z: None };
+        let output = to_string_compact(&point).unwrap();
+        assert_eq!("{x: 1, y: [a, b], z: null}", output);
+        assert_eq!(point, from_str(&output).unwrap());
+
+        // flow indicators and line breaks in strings are quoted, so the output stays on one line
+        let strings = vec!["a,b", "{c", "[d]", "e: f", "g #h", "multi\nline\n", "", " i", "-", "?", "null", "1", "x}"];
+        let output = to_string_compact(&strings).unwrap();
+        assert_eq!(r#"['a,b', '{c', '[d]', 'e: f', 'g #h', "multi\nline\n", '', ' i', '-', '?', 'null', '1', 'x}']"#, output);
+        assert_eq!(strings, from_str::<Vec<String>>(&output).unwrap());
+
+        let value = BTreeMap::from([
+            (String::from("k,1"), vec![Outer::Wrapped(Inner::Unit), Outer::Wrapped(Inner::Pair(2, String::from("a\tb")))]),
+            (String::from("k2"), vec![Outer::Fields { inner: vec![Inner::Unit, Inner::Pair(3, String::from("}"))], note: Some(String::from("two\nlines")) }]),
+            (String::from("k3"), vec![Outer::Fields { inner: vec![], note: None }]),
+        ]);
+        let output = to_string_compact(&value).unwrap();
+        assert_eq!(
+            r#"{'k,1': [{Wrapped: Unit}, {Wrapped: {Pair: [2, "a\tb"]}}], k2: [{Fields: {inner: [Unit, {Pair: [3, '}']}], note: "two\nlines"}}], k3: [{Fields: {inner: [], note: null}}]}"#,
+            output,
+        );
+        assert_eq!(value, from_str::<BTreeMap<String, Vec<Outer>>>(&output).unwrap());
+    }
+
+    #[test]
+    fn line_ending() {
+        use std::collections::BTreeMap;
+        use crate::ser::LineEnding;
+        use crate::{from_str, to_string_multi_with_options};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Document {
+            script: String,
+            folded: String,
+            steps: Vec<BTreeMap<String, Vec<i32>>>,
+        }
+
+        let document = Document {
+            script: String::from("echo a\necho b\n"),
+            folded: String::from("a\r\nb"),
+            steps: vec![BTreeMap::from([(String::from("build"), vec![1, 2])]), BTreeMap::new()],
+        };
+
+        let options = SerializerOptions::new().line_ending(LineEnding::CrLf).explicit_document_start(true);
+        let output = to_string_with_options(&document, options.clone()).unwrap();
+        assert_eq!("---\r\nscript: |\r\n  echo a\r\n  echo b\r\nfolded: \"a\\r\\nb\"\r\nsteps:\r\n  -\r\n    build:\r\n      - 1\r\n      - 2\r\n  - {}\r\n", output);
+        assert!(!output.replace("\r\n", "").contains('\n'), "{output:?}");
+        assert_eq!(document, from_str(&output).unwrap());
+
+        let output = to_string_multi_with_options([1, 2], options).unwrap();
+        assert_eq!("---\r\n1\r\n---\r\n2\r\n", output);
+    }
+
+    #[test]
+    fn explicit_document_end() {
+        use std::collections::BTreeMap;
+        use crate::ser::to_writer_with_options;
+        use crate::{from_str_multi, to_string_multi_with_options};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Event {
+            id: u32,
+            message: String,
+        }
+
+        let events: Vec<Event> = (1..=3).map(|id| Event { id, message: format!("line {id}\n") }).collect();
+        let options = SerializerOptions::new().explicit_document_end(true);
+
+        assert_eq!("id: 1\nmessage: |\n  line 1\n...\n", to_string_with_options(&events[0], options.clone()).unwrap());
+        assert_eq!("1\n...\n---\n2\n...\n", to_string_multi_with_options([1, 2], options.clone()).unwrap());
+
+        // documents appended to a stream one by one, as a log shipper would
+        let options = options.explicit_document_start(true);
+        let mut stream: Vec<u8> = vec![];
+
+        for event in &events {
+            to_writer_with_options(&mut stream, event, options.clone()).unwrap();
         }
+
+        let output = String::from_utf8(stream).unwrap();
+        assert!(output.starts_with("---\nid: 1\nmessage: |\n  line 1\n...\n---\nid: 2\n"), "{output}");
+        assert!(output.ends_with("  line 3\n...\n"), "{output}");
+        assert_eq!(events, from_str_multi::<Event>(&output).unwrap());
+
+        // a block scalar keeping its trailing line breaks ends before the marker
+        let value = BTreeMap::from([(String::from("text"), String::from("a\n\n"))]);
+        let output = to_string_multi_with_options([&value, &value], options).unwrap();
+        assert_eq!("---\ntext: |+\n  a\n\n...\n---\ntext: |+\n  a\n\n...\n", output);
+        assert_eq!(vec![value.clone(), value], from_str_multi::<BTreeMap<String, String>>(&output).unwrap());
+    }
+
+    #[test]
+    fn validate_output() {
+        use std::collections::BTreeMap;
+        use crate::ser::check_output;
+
+        let value = BTreeMap::from([("a", vec!["x: y", "- z", "multi\nline\n"])]);
+        let options = SerializerOptions::new().validate_output(true);
+        assert_eq!(crate::to_string(&value).unwrap(), to_string_with_options(&value, options).unwrap());
+
+        let error = check_output(String::from("a: [1, 2\nb: 3\n")).unwrap_err();
+        assert!(matches!(&error, crate::ser::Errors::InvalidOutputError { output, .. } if output == "a: [1, 2\nb: 3\n"), "{error:?}");
+        assert!(error.to_string().starts_with("Serializer wrote invalid YAML, "), "{error}");
+        assert!(error.to_string().ends_with(":\na: [1, 2\nb: 3\n"), "{error}");
+    }
+
+    #[test]
+    fn unit_variant_positions() {
+        use std::collections::BTreeMap;
+        use crate::ser::{EnumRepr, NullStyle};
+        use crate::from_str;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy, Eq, PartialOrd, Ord)]
+        enum State {
+            Running,
+            Stopped,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Service {
+            state: State,
+            history: Vec<State>,
+            by_host: BTreeMap<String, State>,
+            name: String,
+        }
+
+        let service = Service {
+            state: State::Running,
+            history: vec![State::Stopped, State::Running],
+            by_host: BTreeMap::from([(String::from("a"), State::Running)]),
+            name: String::from("web"),
+        };
+
+        let cases = [
+            (SerializerOptions::new(), "state: Running\nhistory:\n  - Stopped\n  - Running\nby_host:\n  a: Running\nname: web\n"),
+            (
+                SerializerOptions::new().unit_variant_mapping(true),
+                "state:\n  Running: ~\nhistory:\n  - Stopped: ~\n  - Running: ~\nby_host:\n  a:\n    Running: ~\nname: web\n",
+            ),
+            (
+                SerializerOptions::new().unit_variant_mapping(true).null_style(NullStyle::Empty),
+                "state:\n  Running:\nhistory:\n  - Stopped:\n  - Running:\nby_host:\n  a:\n    Running:\nname: web\n",
+            ),
+            (
+                SerializerOptions::new().enum_representation(EnumRepr::Tag),
+                "state: !Running\nhistory:\n  - !Stopped\n  - !Running\nby_host:\n  a: !Running\nname: web\n",
+            ),
+        ];
+
+        for (options, expected) in cases {
+            let output = to_string_with_options(&service, options.clone()).unwrap();
+            assert_eq!(expected, output);
+            assert_eq!(service, from_str(&output).unwrap(), "{output}");
+
+            // a short sequence of unit variants is a collection of mappings with unit_variant_mapping
+            let output = to_string_with_options(&service, options.compact_leaf_seqs(80)).unwrap();
+            assert_eq!(service, from_str(&output).unwrap(), "{output}");
+        }
+
+        // a unit variant as a key is a flow mapping after the explicit key indicator
+        let keys = BTreeMap::from([(State::Running, 1)]);
+        let output = to_string_with_options(&keys, SerializerOptions::new().unit_variant_mapping(true)).unwrap();
+        assert_eq!("? {Running: ~}\n: 1\n", output);
+        assert_eq!(keys, from_str(&output).unwrap());
+    }
+
+    #[test]
+    fn keys() {
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            name: String,
+            #[serde(rename = "app.kubernetes.io/name")]
+            label: String,
+            #[serde(rename = "true")]
+            flag: bool,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum TestEnum {
+            VariantE { x: i32, no: bool },
+        }
+
+        let value = TestStruct { name: String::from("web"), label: String::from("web"), flag: true };
+        let output = crate::to_string(&value).unwrap();
+        assert_eq!("name: web\napp.kubernetes.io/name: web\n'true': true\n", output);
+        assert_eq!(value, crate::from_str(&output).unwrap());
+
+        let value = TestEnum::VariantE { x: 1, no: false };
+        let output = crate::to_string(&value).unwrap();
+        assert_eq!("VariantE:\n  x: 1\n  'no': false\n", output);
+        assert_eq!(value, crate::from_str(&output).unwrap());
+
+        let value = BTreeMap::from([
+            (String::from(""), 1),
+            (String::from("key: value"), 2),
+            (String::from("with space"), 3),
+            (String::from("1"), 4),
+            (String::from("multi\nline"), 5),
+            (String::from("- item"), 6),
+        ]);
+        let output = crate::to_string(&value).unwrap();
+        assert_eq!("'': 1\n'- item': 6\n'1': 4\n'key: value': 2\n\"multi\\nline\": 5\nwith space: 3\n", output);
+        assert_eq!(value, crate::from_str::<BTreeMap<String, i32>>(&output).unwrap());
+
+        let value = BTreeMap::from([(1, String::from("a")), (2, String::from("b"))]);
+        let output = crate::to_string(&value).unwrap();
+        assert_eq!("1: a\n2: b\n", output);
+        assert_eq!(value, crate::from_str::<BTreeMap<i32, String>>(&output).unwrap());
+
+        // an implicit key is at most 1024 characters long
+        let long = "k".repeat(2000);
+        let value = BTreeMap::from([(long.clone(), vec![1]), (String::from("short"), vec![])]);
+        let output = to_string(&value).unwrap();
+        assert_eq!(format!("? {long}\n:\n  - 1\nshort: []\n"), output);
+        assert_eq!(value, crate::from_str::<BTreeMap<String, Vec<i32>>>(&output).unwrap());
+
+        let value = BTreeMap::from([(format!("{}: x", "k".repeat(1022)), 1), ("k".repeat(1024), 2)]);
+        let output = to_string(&value).unwrap();
+        assert_eq!(format!("? '{}: x'\n: 1\n{}: 2\n", "k".repeat(1022), "k".repeat(1024)), output);
+        assert_eq!(value, crate::from_str::<BTreeMap<String, i32>>(&output).unwrap());
+
+        let output = to_string_with_options(&value, SerializerOptions::new().style(crate::ser::Style::Flow)).unwrap();
+        assert_eq!(format!("{{? '{}: x': 1, {}: 2}}\n", "k".repeat(1022), "k".repeat(1024)), output);
+        assert_eq!(value, crate::from_str::<BTreeMap<String, i32>>(&output).unwrap());
+    }
+
+    #[test]
+    fn map_call_order() {
+        use serde::ser::SerializeMap;
+        use serde::Serializer;
+        use crate::ser::Style;
+
+        for options in [SerializerOptions::new(), SerializerOptions::new().style(Style::Flow), SerializerOptions::new().sort_keys(true)] {
+            let flow = options.style == Style::Flow;
+
+            // serialize_key and serialize_value write the same entries as serialize_entry
+            let mut output = String::new();
+            let mut serializer = YamlSerializer::with_options(&mut output, options.clone());
+            let mut map = (&mut serializer).serialize_map(None).unwrap();
+            map.serialize_key("b").unwrap();
+            map.serialize_value(&vec![1, 2]).unwrap();
+            map.serialize_entry("a", &1).unwrap();
+            map.end().unwrap();
+
+            let expected = match (flow, options.sort_keys) {
+                (true, _) => "{b: [1, 2], a: 1}",
+                (false, false) => "b:\n  - 1\n  - 2\na: 1",
+                (false, true) => "a: 1\nb:\n  - 1\n  - 2",
+            };
+            assert_eq!(expected, output);
+
+            // out of order calls fail, or end fails if every call succeeds
+            let errors = [
+                ("Map value serialized before its key", vec!["value"]),
+                ("Map value serialized before its key", vec!["entry", "value"]),
+                ("Map key serialized before the value of the key before it", vec!["key", "key"]),
+                ("Map key serialized before the value of the key before it", vec!["key", "entry"]),
+                ("Map ended before the value of its last key", vec!["entry", "key"]),
+            ];
+
+            for (error, calls) in errors {
+                let mut output = String::new();
+                let mut serializer = YamlSerializer::with_options(&mut output, options.clone());
+                let mut map = (&mut serializer).serialize_map(None).unwrap();
+                let mut result = Ok(());
+
+                for call in calls {
+                    result = match call {
+                        "key" => map.serialize_key("k"),
+                        "value" => map.serialize_value(&1),
+                        _ => map.serialize_entry("k", &1),
+                    };
+
+                    if result.is_err() {
+                        break;
+                    }
+                }
+
+                if result.is_ok() {
+                    result = map.end();
+                }
+
+                assert_eq!(error, result.unwrap_err().to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn complex_keys() {
+        use std::collections::BTreeMap;
+        use crate::ser::Style;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+        enum Shape {
+            Dot(Point),
+            Line(Point, Point),
+        }
+
+        let value = BTreeMap::from([((String::from("a"), 1), String::from("x")), ((String::from("b"), 2), String::from("y"))]);
+        test!(&value, "? [a, 1]\n: x\n? [b, 2]\n: y\n");
+        assert_eq!(value, crate::from_str::<BTreeMap<(String, u32), String>>(&crate::to_string(&value).unwrap()).unwrap());
+
+        let value = BTreeMap::from([(Point { x: 1, y: 2 }, vec![1, 2]), (Point { x: 3, y: 4 }, vec![])]);
+        test!(&value, "? {x: 1, y: 2}\n:\n  - 1\n  - 2\n? {x: 3, y: 4}\n: []\n");
+        assert_eq!(value, crate::from_str::<BTreeMap<Point, Vec<i32>>>(&crate::to_string(&value).unwrap()).unwrap());
+
+        let value = vec![BTreeMap::from([(Shape::Dot(Point { x: 1, y: 2 }), 1), (Shape::Line(Point { x: 0, y: 0 }, Point { x: 1, y: 1 }), 2)])];
+        let output = crate::to_string(&value).unwrap();
+        assert_eq!("-\n  ? {Dot: {x: 1, y: 2}}\n  : 1\n  ? {Line: [{x: 0, y: 0}, {x: 1, y: 1}]}\n  : 2\n", output);
+        assert_eq!(value, crate::from_str::<Vec<BTreeMap<Shape, i32>>>(&output).unwrap());
+
+        let nested = BTreeMap::from([(String::from("points"), BTreeMap::from([(Point { x: 1, y: 2 }, String::from("a"))]))]);
+        let output = crate::to_string(&nested).unwrap();
+        assert_eq!("points:\n  ? {x: 1, y: 2}\n  : a\n", output);
+        assert_eq!(nested, crate::from_str::<BTreeMap<String, BTreeMap<Point, String>>>(&output).unwrap());
+
+        let output = to_string_with_options(&value, SerializerOptions::new().style(Style::Flow)).unwrap();
+        assert_eq!("[{{Dot: {x: 1, y: 2}}: 1, {Line: [{x: 0, y: 0}, {x: 1, y: 1}]}: 2}]\n", output);
+        assert_eq!(value, crate::from_str::<Vec<BTreeMap<Shape, i32>>>(&output).unwrap());
+    }
+
+    #[test]
+    fn sort_keys() {
+        use std::collections::{BTreeMap, HashMap};
+        use crate::ser::Style;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Service {
+            name: String,
+            labels: HashMap<String, String>,
+            ports: Vec<HashMap<String, u16>>,
+            #[serde(flatten)]
+            extra: HashMap<String, i32>,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Kind {
+            Config { version: u8, data: HashMap<String, i32> },
+        }
+
+        let labels = (0..20).map(|i| (format!("label-{i:02}"), format!("value {i}"))).collect::<HashMap<_, _>>();
+        let value = Service {
+            name: String::from("web"),
+            labels: labels.clone(),
+            ports: vec![HashMap::from([(String::from("target"), 8080), (String::from("port"), 80)])],
+            extra: HashMap::from([(String::from("zeta"), 1), (String::from("alpha"), 2)]),
+        };
+
+        let options = SerializerOptions::new().sort_keys(true);
+        let output = to_string_with_options(&value, options.clone()).unwrap();
+        assert_eq!(output, to_string_with_options(&value, options.clone()).unwrap());
+        assert!(output.starts_with("alpha: 2\nlabels:\n  label-00: value 0\n  label-01: value 1\n"), "{output}");
+        assert!(output.ends_with("name: web\nports:\n  -\n    port: 80\n    target: 8080\nzeta: 1\n"), "{output}");
+        assert_eq!(value, crate::from_str::<Service>(&output).unwrap(), "{output}");
+
+        // a struct alone keeps the declaration order unless struct fields are sorted too
+        let value = Kind::Config { version: 1, data: HashMap::from([(String::from("b"), 2), (String::from("a"), 1)]) };
+        assert_eq!("Config:\n  version: 1\n  data:\n    a: 1\n    b: 2\n", to_string_with_options(&value, options.clone()).unwrap());
+        let output = to_string_with_options(&value, options.clone().sort_struct_fields(true)).unwrap();
+        assert_eq!("Config:\n  data:\n    a: 1\n    b: 2\n  version: 1\n", output);
+        assert_eq!(value, crate::from_str::<Kind>(&output).unwrap(), "{output}");
+
+        let value = HashMap::from([((2, 1), vec![1]), ((1, 2), vec![]), ((1, 1), vec![2, 3])]);
+        let output = to_string_with_options(&value, options.clone()).unwrap();
+        assert_eq!("? [1, 1]\n:\n  - 2\n  - 3\n? [1, 2]\n: []\n? [2, 1]\n:\n  - 1\n", output);
+        assert_eq!(value, crate::from_str::<HashMap<(i32, i32), Vec<i32>>>(&output).unwrap(), "{output}");
+
+        let output = to_string_with_options(vec![labels.clone()], options.clone().style(Style::Flow)).unwrap();
+        assert!(output.starts_with("[{label-00: value 0, label-01: value 1, "), "{output}");
+        assert_eq!(vec![labels], crate::from_str::<Vec<HashMap<String, String>>>(&output).unwrap(), "{output}");
+
+        let value = BTreeMap::from([(String::from("empty"), HashMap::<String, i32>::new())]);
+        assert_eq!("empty: {}\n", to_string_with_options(&value, options).unwrap());
+    }
+
+    #[test]
+    fn chars() {
+        use crate::ser::Style;
+
+        let punctuation = (' '..='~').filter(|c| !c.is_ascii_alphanumeric());
+        let chars = punctuation.chain(['\n', '\t', '\r', '\0', '\u{85}', '\u{A0}', 'é', 'ß', 'ж', '中', '😀', '\u{2028}', '\u{FEFF}']);
+
+        for c in chars {
+            let output = crate::to_string(c).unwrap();
+            assert_eq!(c, crate::from_str::<char>(&output).unwrap(), "{output:?}");
+
+            let output = crate::to_string(vec![c, c]).unwrap();
+            assert_eq!(vec![c, c], crate::from_str::<Vec<char>>(&output).unwrap(), "{output:?}");
+
+            let output = to_string_with_options(vec![c, c], SerializerOptions::new().style(Style::Flow)).unwrap();
+            assert_eq!(vec![c, c], crate::from_str::<Vec<char>>(&output).unwrap(), "{output:?}");
+        }
+    }
+
+    #[test]
+    fn block_chomping() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Note {
+            text: String,
+            next: i32,
+        }
+
+        let cases = [
+            ("line1\nline2", "text: |-\n  line1\n  line2\nnext: 1\n"),
+            ("line1\nline2\n", "text: |\n  line1\n  line2\nnext: 1\n"),
+            ("line1\nline2\n\n\n", "text: |+\n  line1\n  line2\n\n\nnext: 1\n"),
+            ("\n\nline1\n", "text: |\n\n\n  line1\nnext: 1\n"),
+            ("one line\n", "text: |\n  one line\nnext: 1\n"),
+            // block scalars can't carry these without an indentation indicator or trailing whitespace
+            ("\n\n", "text: \"\\n\\n\"\nnext: 1\n"),
+            ("  indented\nline\n", "text: \"  indented\\nline\\n\"\nnext: 1\n"),
+            ("trailing \nline", "text: \"trailing \\nline\"\nnext: 1\n"),
+            ("line \n", "text: \"line \\n\"\nnext: 1\n"),
+            ("line\n \nline", "text: \"line\\n \\nline\"\nnext: 1\n"),
+            // leading empty lines are content of a block scalar, indented lines after the first one too
+            ("\nafter a blank line", "text: |-\n\n  after a blank line\nnext: 1\n"),
+            ("line\n  indented\n", "text: |\n  line\n    indented\nnext: 1\n"),
+        ];
+
+        for (text, expected) in cases {
+            let value = Note { text: String::from(text), next: 1 };
+            let output = crate::to_string(&value).unwrap();
+            assert_eq!(expected, output);
+            assert_eq!(value, crate::from_str::<Note>(&output).unwrap(), "{output}");
+        }
+
+        for text in ["a\nb", "a\nb\n", "a\nb\n\n\n", "\na", "\n\na\n  b\n", "a \n", " a\nb", "a\n\t\nb"] {
+            let output = crate::to_string(text).unwrap();
+            assert_eq!(text, crate::from_str::<String>(&output).unwrap(), "{output}");
+
+            let output = crate::to_string(vec![text, text]).unwrap();
+            assert_eq!(vec![text, text], crate::from_str::<Vec<String>>(&output).unwrap(), "{output}");
+        }
+    }
+
+    #[test]
+    fn block_scalar_indentation() {
+        use std::collections::BTreeMap;
+        use crate::ser::EnumRepr;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Step {
+            Run(String),
+            Script { lines: Vec<String>, env: BTreeMap<String, String> },
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Job {
+            steps: Vec<Step>,
+            notes: BTreeMap<String, Vec<String>>,
+        }
+
+        // the lines of a block scalar are indented one level deeper than the key or dash before it, the indented
+        // line keeps its leading spaces when read back
+        let text = || String::from("first\n  indented\nlast\n");
+        let job = Job {
+            steps: vec![
+                Step::Run(text()),
+                Step::Script { lines: vec![text()], env: BTreeMap::from([(String::from("SCRIPT"), text())]) },
+            ],
+            notes: BTreeMap::from([(String::from("a"), vec![text()])]),
+        };
+
+        let output = crate::to_string(&job).unwrap();
+        assert_eq!(concat!(
+            "steps:\n",
+            "  -\n    Run: |\n      first\n        indented\n      last\n",
+            "  -\n    Script:\n",
+            "      lines:\n        - |\n          first\n            indented\n          last\n",
+            "      env:\n        SCRIPT: |\n          first\n            indented\n          last\n",
+            "notes:\n  a:\n    - |\n      first\n        indented\n      last\n",
+        ), output);
+        assert_eq!(job, crate::from_str::<Job>(&output).unwrap());
+
+        let options = SerializerOptions::new().enum_representation(EnumRepr::Tag);
+        let output = to_string_with_options(&job, options).unwrap();
+        assert!(output.starts_with("steps:\n  - !Run |\n    first\n      indented\n    last\n  - !Script\n    lines:\n"), "{output}");
+        assert_eq!(job, crate::from_str::<Job>(&output).unwrap());
+
+        let nested = vec![BTreeMap::from([(String::from("jobs"), vec![vec![job]])])];
+        let output = crate::to_string(&nested).unwrap();
+        assert_eq!(nested, crate::from_str::<Vec<BTreeMap<String, Vec<Vec<Job>>>>>(&output).unwrap(), "{output}");
+    }
+
+    #[test]
+    fn nested_sequences() {
+        use std::collections::HashMap;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Matrix {
+            rows: Vec<Vec<i32>>,
+            cells: Vec<HashMap<String, i32>>,
+        }
+
+        let value = vec![vec![1, 2], vec![3]];
+        test!(&value, "-\n  - 1\n  - 2\n-\n  - 3\n");
+        assert_eq!(value, crate::from_str::<Vec<Vec<i32>>>(&crate::to_string(&value).unwrap()).unwrap());
+
+        let value = vec![HashMap::from([(String::from("a"), 1)]), HashMap::from([(String::from("b"), 2)])];
+        test!(&value, "-\n  a: 1\n-\n  b: 2\n");
+        assert_eq!(value, crate::from_str::<Vec<HashMap<String, i32>>>(&crate::to_string(&value).unwrap()).unwrap());
+
+        let value = Matrix {
+            rows: vec![vec![1, 2], vec![], vec![3]],
+            cells: vec![HashMap::from([(String::from("x"), 1)]), HashMap::new()],
+        };
+
+        test!(&value, "rows:\n  -\n    - 1\n    - 2\n  - []\n  -\n    - 3\ncells:\n  -\n    x: 1\n  - {}\n");
+        assert_eq!(value, crate::from_str::<Matrix>(&crate::to_string(&value).unwrap()).unwrap());
+
+        let value = vec![vec![vec![1], vec![2, 3]]];
+        test!(&value, "-\n  -\n    - 1\n  -\n    - 2\n    - 3\n");
+        assert_eq!(value, crate::from_str::<Vec<Vec<Vec<i32>>>>(&crate::to_string(&value).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn null_style() {
+        use std::collections::BTreeMap;
+        use crate::ser::{NullStyle, Style};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+        struct Marker;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+        struct Config {
+            name: Option<String>,
+            marker: Option<Marker>,
+            unit: (),
+            items: Vec<Option<i32>>,
+            nested: BTreeMap<String, Option<i32>>,
+        }
+
+        let value = Config {
+            name: None,
+            marker: Some(Marker),
+            unit: (),
+            items: vec![Some(1), None],
+            nested: BTreeMap::from([(String::from("a"), None), (String::from("b"), Some(2))]),
+        };
+
+        let cases = [
+            (NullStyle::Tilde, "name: ~\nmarker: ~\nunit: ~\nitems:\n  - 1\n  - ~\nnested:\n  a: ~\n  b: 2\n", "~\n", "[1, ~, {a: ~}]\n"),
+            (NullStyle::Null, "name: null\nmarker: null\nunit: null\nitems:\n  - 1\n  - null\nnested:\n  a: null\n  b: 2\n", "null\n", "[1, null, {a: null}]\n"),
+            (NullStyle::Empty, "name:\nmarker:\nunit:\nitems:\n  - 1\n  - null\nnested:\n  a:\n  b: 2\n", "null\n", "[1, null, {a: null}]\n"),
+        ];
+
+        for (style, expected, root, flow) in cases {
+            let options = SerializerOptions::new().null_style(style);
+            let output = to_string_with_options(&value, options.clone()).unwrap();
+            assert_eq!(expected, output);
+            // a unit struct is null as well, which reads back as None
+            assert_eq!(Config { marker: None, ..value.clone() }, crate::from_str::<Config>(&output).unwrap(), "{output}");
+
+            assert_eq!(root, to_string_with_options(None::<i32>, options.clone()).unwrap());
+            assert_eq!(None, crate::from_str::<Option<i32>>(root).unwrap());
+
+            let flow_value = (1, None::<i32>, BTreeMap::from([(String::from("a"), None::<i32>)]));
+            let output = to_string_with_options(&flow_value, options.style(Style::Flow)).unwrap();
+            assert_eq!(flow, output);
+            assert_eq!(flow_value, crate::from_str(&output).unwrap(), "{output}");
+        }
+    }
+
+    #[test]
+    fn omit_none_values() {
+        use std::collections::BTreeMap;
+        use crate::ser::Style;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Service {
+            name: Option<String>,
+            image: Option<String>,
+            port: Option<u16>,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Deployment {
+            service: Service,
+            replicas: Vec<Option<i32>>,
+            labels: BTreeMap<String, Option<String>>,
+        }
+
+        let options = SerializerOptions::new().omit_none_values(true);
+
+        let value = Service { name: None, image: Some(String::from("nginx")), port: None };
+        let output = to_string_with_options(&value, options.clone()).unwrap();
+        assert_eq!("image: nginx\n", output);
+        assert_eq!(value, crate::from_str::<Service>(&output).unwrap());
+
+        // a mapping left without entries is empty, sequence elements are kept
+        let value = Deployment {
+            service: Service { name: None, image: None, port: None },
+            replicas: vec![Some(1), None],
+            labels: BTreeMap::from([(String::from("a"), None), (String::from("b"), Some(String::from("x")))]),
+        };
+
+        let output = to_string_with_options(&value, options.clone()).unwrap();
+        assert_eq!("service: {}\nreplicas:\n  - 1\n  - ~\nlabels:\n  b: x\n", output);
+
+        let output = to_string_with_options(&value, options.clone().sort_keys(true)).unwrap();
+        assert_eq!("service: {}\nreplicas:\n  - 1\n  - ~\nlabels:\n  b: x\n", output);
+
+        let output = to_string_with_options(&value, options.clone().style(Style::Flow)).unwrap();
+        assert_eq!("{service: {}, replicas: [1, ~], labels: {b: x}}\n", output);
+
+        let value = BTreeMap::from([(vec![1, 2], None), (vec![3], Some(4))]);
+        assert_eq!("? [3]\n: 4\n", to_string_with_options(&value, options.clone()).unwrap());
+
+        // the keys of a mapping inside a key are buffered as well, the key around them stays explicit
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+        enum Key {
+            S { x: i32, y: Vec<i32> },
+            M(BTreeMap<String, i32>),
+        }
+
+        let value = BTreeMap::from([
+            (Key::S { x: 1, y: vec![1] }, 3),
+            (Key::M(BTreeMap::from([(String::from("a"), 1)])), 4),
+        ]);
+        let output = to_string_with_options(&value, options).unwrap();
+        assert_eq!("? {S: {x: 1, y: [1]}}\n: 3\n? {M: {a: 1}}\n: 4\n", output);
+        assert_eq!(value, crate::from_str::<BTreeMap<Key, i32>>(&output).unwrap());
+    }
+
+    #[test]
+    fn quote_style() {
+        use crate::ser::QuoteStyle;
+
+        #[derive(Serialize, Debug)]
+        struct TestStruct {
+            name: String,
+            enabled: String,
+            ports: Vec<&'static str>,
+        }
+
+        let value = TestStruct { name: String::from("web"), enabled: String::from("true"), ports: vec!["80", "http"] };
+        assert_eq!("name: web\nenabled: 'true'\nports:\n  - '80'\n  - http\n", to_string_with_options(&value, SerializerOptions::new()).unwrap());
+
+        let options = SerializerOptions::new().quote_style(QuoteStyle::AlwaysQuote);
+        assert_eq!("'name': 'web'\n'enabled': 'true'\n'ports':\n  - '80'\n  - 'http'\n", to_string_with_options(&value, options).unwrap());
+    }
+
+    #[test]
+    fn scalar_styler() {
+        use crate::ser::{ScalarContext, ScalarStyle, ScalarStyler, Style};
+
+        #[derive(Debug)]
+        struct Fixed(ScalarStyle);
+
+        impl ScalarStyler for Fixed {
+            fn style(&self, _value: &str, context: ScalarContext) -> ScalarStyle {
+                if context.is_key() {
+                    return ScalarStyle::Plain;
+                }
+
+                self.0
+            }
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Note {
+            name: String,
+            lines: Vec<String>,
+        }
+
+        let value = Note {
+            name: String::from("web"),
+            lines: vec![String::from("one"), String::from("a\nb\n"), String::from("a\n\nb"), String::from("\na b\n\n\n")],
+        };
+
+        let output = to_string_with_options(&value, SerializerOptions::new().scalar_styler(Fixed(ScalarStyle::Literal))).unwrap();
+        assert_eq!("name: |-\n  web\nlines:\n  - |-\n    one\n  - |\n    a\n    b\n  - |-\n    a\n\n    b\n  - |+\n\n    a b\n\n\n", output);
+        assert_eq!(value, crate::from_str::<Note>(&output).unwrap(), "{output}");
+
+        let output = to_string_with_options(&value, SerializerOptions::new().scalar_styler(Fixed(ScalarStyle::Folded))).unwrap();
+        assert_eq!("name: >-\n  web\nlines:\n  - >-\n    one\n  - >\n    a\n\n    b\n  - >-\n    a\n\n\n    b\n  - >+\n\n    a b\n\n\n", output);
+        assert_eq!(value, crate::from_str::<Note>(&output).unwrap(), "{output}");
+
+        let output = to_string_with_options(&value, SerializerOptions::new().scalar_styler(Fixed(ScalarStyle::SingleQuoted))).unwrap();
+        assert_eq!("name: 'web'\nlines:\n  - 'one'\n  - \"a\\nb\\n\"\n  - \"a\\n\\nb\"\n  - \"\\na b\\n\\n\\n\"\n", output);
+        assert_eq!(value, crate::from_str::<Note>(&output).unwrap(), "{output}");
+
+        // styles which can't carry the string where it is written fall back to double quotes
+        let value = vec!["true", "", "  indented\nline", "tab\there"];
+        let output = to_string_with_options(&value, SerializerOptions::new().scalar_styler(Fixed(ScalarStyle::Plain))).unwrap();
+        assert_eq!("- \"true\"\n- \"\"\n- \"  indented\\nline\"\n- \"tab\\there\"\n", output);
+        assert_eq!(value, crate::from_str::<Vec<String>>(&output).unwrap(), "{output}");
+
+        let options = SerializerOptions::new().scalar_styler(Fixed(ScalarStyle::Literal)).style(Style::Flow);
+        let output = to_string_with_options(vec!["a\nb", "c"], options).unwrap();
+        assert_eq!("[\"a\\nb\", \"c\"]\n", output);
+
+        // whitespace a block scalar would alter is kept by the fallback
+        let value = vec!["line \n", "\nafter a blank line", " leading\nspace", "a\n  b\n   \nc", "\n\n", " \n"];
+
+        for style in [ScalarStyle::Literal, ScalarStyle::Folded] {
+            let output = to_string_with_options(&value, SerializerOptions::new().scalar_styler(Fixed(style))).unwrap();
+            assert_eq!(value, crate::from_str::<Vec<String>>(&output).unwrap(), "{output}");
+        }
+    }
+
+    #[test]
+    fn force_double_quotes() {
+        use std::collections::BTreeMap;
+        use crate::ser::{QuoteStyle, Style};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Mode {
+            Fast,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            name: String,
+            script: String,
+            mode: Mode,
+            initial: char,
+            ports: Vec<u16>,
+            labels: BTreeMap<String, String>,
+        }
+
+        let value = TestStruct {
+            name: String::from("it's \"web\""),
+            script: String::from("make\n\tmake install"),
+            mode: Mode::Fast,
+            initial: 'w',
+            ports: vec![80],
+            labels: BTreeMap::from([(String::from("app"), String::from("true"))]),
+        };
+
+        let output = crate::to_string(&value).unwrap();
+        assert_eq!("name: it's \"web\"\nscript: \"make\\n\\tmake install\"\nmode: Fast\ninitial: w\nports:\n  - 80\nlabels:\n  app: 'true'\n", output);
+
+        let options = SerializerOptions::new().quote_style(QuoteStyle::ForceDouble);
+        let output = to_string_with_options(&value, options.clone()).unwrap();
+        assert_eq!(concat!(
+            "\"name\": \"it's \\\"web\\\"\"\n\"script\": \"make\\n\\tmake install\"\n\"mode\": \"Fast\"\n\"initial\": \"w\"\n",
+            "\"ports\":\n  - 80\n\"labels\":\n  \"app\": \"true\"\n",
+        ), output);
+        assert_eq!(value, crate::from_str::<TestStruct>(&output).unwrap(), "{output}");
+
+        let multi_line = String::from("Hello\nworld");
+        assert_eq!("|-\n  Hello\n  world\n", crate::to_string(&multi_line).unwrap());
+        assert_eq!("\"Hello\\nworld\"\n", to_string_with_options(&multi_line, options.clone()).unwrap());
+
+        let output = to_string_with_options(&value, options.style(Style::Flow)).unwrap();
+        assert_eq!(concat!(
+            "{\"name\": \"it's \\\"web\\\"\", \"script\": \"make\\n\\tmake install\", \"mode\": \"Fast\", \"initial\": \"w\", ",
+            "\"ports\": [80], \"labels\": {\"app\": \"true\"}}\n",
+        ), output);
+        assert_eq!(value, crate::from_str::<TestStruct>(&output).unwrap(), "{output}");
+    }
+
+    #[test]
+    fn flow_style() {
+        use std::collections::BTreeMap;
+        use crate::ser::Style;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            x: i32,
+            y: Vec<i32>,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum TestEnum {
+            VariantA,
+            VariantB(),
+            VariantC(i32, String),
+            VariantD(TestStruct),
+            VariantE { x: f64, y: bool },
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct TestNested {
+            name: String,
+            point: TestStruct,
+            matrix: Vec<Vec<i32>>,
+            tags: BTreeMap<String, String>,
+            variants: Vec<TestEnum>,
+            unit: TestEnum,
+            empty: Vec<i32>,
+        }
+
+        let options = SerializerOptions::new().style(Style::Flow);
+
+        let value = TestStruct { x: 1, y: vec![1, 2, 3] };
+        assert_eq!("{x: 1, y: [1, 2, 3]}\n", to_string_with_options(&value, options.clone()).unwrap());
+
+        let value = TestNested {
+            name: String::from("a, b"),
+            point: TestStruct { x: 1, y: vec![] },
+            matrix: vec![vec![1, 2], vec![], vec![3]],
+            tags: BTreeMap::from([(String::from("app"), String::from("web")), (String::from("list[0]"), String::from("{x}\ny"))]),
+            variants: vec![
+                TestEnum::VariantA,
+                TestEnum::VariantB(),
+                TestEnum::VariantC(3, String::from("Hello, world")),
+                TestEnum::VariantD(TestStruct { x: 4, y: vec![5] }),
+                TestEnum::VariantE { x: 1.5, y: true },
+            ],
+            unit: TestEnum::VariantA,
+            empty: vec![],
+        };
+
+        let output = to_string_with_options(&value, options).unwrap();
+        assert_eq!(concat!(
+            "{name: 'a, b', point: {x: 1, y: []}, matrix: [[1, 2], [], [3]], tags: {app: web, 'list[0]': \"{x}\\ny\"}, ",
+            "variants: [VariantA, {VariantB: []}, {VariantC: [3, 'Hello, world']}, {VariantD: {x: 4, y: [5]}}, {VariantE: {x: 1.5, y: true}}], ",
+            "unit: VariantA, empty: []}\n",
+        ), output);
+        assert_eq!(value, crate::from_str(&output).unwrap());
+    }
+
+    #[test]
+    fn no_trailing_whitespace() {
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Step {
+            Run { command: String, args: Vec<String> },
+            Copy(String, String),
+            Wait,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Job {
+            name: String,
+            script: String,
+            steps: Vec<Step>,
+            env: BTreeMap<String, Vec<BTreeMap<String, i32>>>,
+        }
+
+        let value = vec![
+            Job {
+                name: String::from("build"),
+                script: String::from("cargo build\n\ncargo test\n\n\nexit"),
+                steps: vec![
+                    Step::Run { command: String::from("make"), args: vec![String::from("all"), String::from("install")] },
+                    Step::Copy(String::from("a"), String::from("b")),
+                    Step::Wait,
+                ],
+                env: BTreeMap::from([(String::from("matrix"), vec![BTreeMap::from([(String::from("x"), 1)]), BTreeMap::from([(String::from("y"), 2)])])]),
+            },
+            Job { name: String::from("deploy"), script: String::from("echo"), steps: vec![Step::Wait], env: BTreeMap::from([(String::from("empty"), vec![])]) },
+        ];
+
+        let output = crate::to_string(&value).unwrap();
+        assert!(!output.lines().any(|line| line.ends_with([' ', '\t'])), "{output}");
+        assert!(!output.lines().any(|line| line.trim().is_empty() && !line.is_empty()), "{output}");
+        assert_eq!(value, crate::from_str::<Vec<Job>>(&output).unwrap(), "{output}");
+    }
+
+    #[test]
+    fn empty_maps() {
+        use std::collections::{BTreeMap, HashMap};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+        struct Labels {
+            #[serde(skip_serializing_if = "Option::is_none", default)]
+            app: Option<String>,
+            #[serde(skip_serializing_if = "Vec::is_empty", default)]
+            tiers: Vec<String>,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Probe {
+            Http {},
+            Tcp { port: u16 },
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Service {
+            labels: Labels,
+            annotations: HashMap<String, String>,
+            selectors: Vec<BTreeMap<String, String>>,
+            probe: Probe,
+        }
+
+        test!(HashMap::<String, String>::new(), "{}\n");
+        test!(Labels::default(), "{}\n");
+        test!(vec![Labels::default()], "- {}\n");
+        test!(Probe::Http {}, "Http: {}\n");
+
+        let value = Service {
+            labels: Labels::default(),
+            annotations: HashMap::new(),
+            selectors: vec![BTreeMap::new(), BTreeMap::from([(String::from("app"), String::from("web"))])],
+            probe: Probe::Http {},
+        };
+
+        let output = crate::to_string(&value).unwrap();
+        assert_eq!("labels: {}\nannotations: {}\nselectors:\n  - {}\n  -\n    app: web\nprobe:\n  Http: {}\n", output);
+        assert_eq!(value, crate::from_str::<Service>(&output).unwrap(), "{output}");
+
+        let value = HashMap::<String, String>::new();
+        assert_eq!(value, crate::from_str::<HashMap<String, String>>(&crate::to_string(&value).unwrap()).unwrap());
+        assert_eq!(Labels::default(), crate::from_str::<Labels>(&crate::to_string(Labels::default()).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn unit_variants() {
+        use crate::ser::Style;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Mode {
+            Fast,
+            #[serde(rename = "true")]
+            True,
+            Custom(i32),
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Config {
+            mode: Mode,
+            modes: Vec<Mode>,
+        }
+
+        let value = Config { mode: Mode::Fast, modes: vec![Mode::Fast, Mode::True, Mode::Custom(1)] };
+
+        let output = crate::to_string(&value).unwrap();
+        assert_eq!("mode: Fast\nmodes:\n  - Fast\n  - 'true'\n  -\n    Custom: 1\n", output);
+        assert_eq!(value, crate::from_str::<Config>(&output).unwrap(), "{output}");
+
+        let output = to_string_with_options(&value, SerializerOptions::new().style(Style::Flow)).unwrap();
+        assert_eq!("{mode: Fast, modes: [Fast, 'true', {Custom: 1}]}\n", output);
+        assert_eq!(value, crate::from_str::<Config>(&output).unwrap(), "{output}");
+
+        let options = SerializerOptions::new().unit_variant_mapping(true);
+        assert_eq!("Fast: ~\n", to_string_with_options(Mode::Fast, options.clone()).unwrap());
+        let output = to_string_with_options(&value, options.clone()).unwrap();
+        assert!(output.starts_with("mode:\n  Fast: ~\n"), "{output}");
+        assert_eq!(value, crate::from_str::<Config>(&output).unwrap(), "{output}");
+
+        let output = to_string_with_options(&value, options.style(Style::Flow)).unwrap();
+        assert_eq!("{mode: {Fast: ~}, modes: [{Fast: ~}, {true: ~}, {Custom: 1}]}\n", output);
+        assert_eq!(value, crate::from_str::<Config>(&output).unwrap(), "{output}");
+    }
+
+    #[test]
+    fn enum_tags() {
+        use std::collections::BTreeMap;
+        use crate::ser::{EnumRepr, Style};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Shape {
+            Empty,
+            Circle(f64),
+            Point(i32, i32),
+            Rect { width: u32, height: u32 },
+            Group(Vec<Shape>),
+            Nested(Box<Shape>),
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Drawing {
+            background: Shape,
+            shapes: Vec<Shape>,
+            named: BTreeMap<String, Shape>,
+        }
+
+        let value = Drawing {
+            background: Shape::Empty,
+            shapes: vec![
+                Shape::Circle(1.5),
+                Shape::Point(1, 2),
+                Shape::Rect { width: 3, height: 4 },
+                Shape::Group(vec![Shape::Empty, Shape::Circle(2.0)]),
+                Shape::Point(0, 0),
+            ],
+            named: BTreeMap::from([
+                (String::from("inner"), Shape::Nested(Box::new(Shape::Circle(3.0)))),
+                (String::from("unit"), Shape::Nested(Box::new(Shape::Empty))),
+            ]),
+        };
+
+        let options = SerializerOptions::new().enum_representation(EnumRepr::Tag);
+        let output = to_string_with_options(&value, options.clone()).unwrap();
+        assert_eq!("background: !Empty
+shapes:
+  - !Circle 1.5
+  - !Point
+    - 1
+    - 2
+  - !Rect
+    width: 3
+    height: 4
+  - !Group
+    - !Empty
+    - !Circle 2.0
+  - !Point
+    - 0
+    - 0
+named:
+  inner: !Nested
+    Circle: 3.0
+  unit: !Nested Empty
+", output);
+        assert_eq!(value, crate::from_str::<Drawing>(&output).unwrap(), "{output}");
+
+        let output = to_string_with_options(&value, options.clone().style(Style::Flow)).unwrap();
+        assert_eq!("{background: !Empty, shapes: [!Circle 1.5, !Point [1, 2], !Rect {width: 3, height: 4}, !Group [!Empty, !Circle 2.0], !Point [0, 0]], named: {inner: !Nested {Circle: 3.0}, unit: !Nested Empty}}\n", output);
+        assert_eq!(value, crate::from_str::<Drawing>(&output).unwrap(), "{output}");
+
+        // at the root and as keys
+        let cases = [
+            (Shape::Empty, "!Empty\n"),
+            (Shape::Circle(1.0), "!Circle 1.0\n"),
+            (Shape::Rect { width: 1, height: 2 }, "!Rect\nwidth: 1\nheight: 2\n"),
+            (Shape::Group(vec![]), "!Group []\n"),
+        ];
+
+        for (shape, expected) in cases {
+            let output = to_string_with_options(&shape, options.clone()).unwrap();
+            assert_eq!(expected, output);
+            assert_eq!(shape, crate::from_str::<Shape>(&output).unwrap(), "{output}");
+        }
+
+        let value = BTreeMap::from([(Mode::Fast, 1), (Mode::Custom(2), 2)]);
+        let output = to_string_with_options(&value, options).unwrap();
+        assert_eq!("!Fast : 1\n!Custom 2: 2\n", output);
+        assert_eq!(value, crate::from_str::<BTreeMap<Mode, i32>>(&output).unwrap(), "{output}");
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+        enum Mode {
+            Fast,
+            Custom(i32),
+        }
+    }
+
+    #[test]
+    fn wide_integers() {
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Balance {
+            total: u128,
+            delta: i128,
+        }
+
+        for value in [Balance { total: u128::MAX, delta: i128::MIN }, Balance { total: 0, delta: i128::MAX }] {
+            let output = crate::to_string(&value).unwrap();
+            assert_eq!(value, crate::from_str::<Balance>(&output).unwrap(), "{output}");
+        }
+
+        let value = BTreeMap::from([(u128::MAX, i128::MIN), (1, -1)]);
+        let output = crate::to_string(&value).unwrap();
+        assert_eq!(value, crate::from_str::<BTreeMap<u128, i128>>(&output).unwrap(), "{output}");
+    }
+
+    #[test]
+    fn floats() {
+        use yaml_rust2::Yaml;
+        use crate::wrapper::YamlNodeWrapper;
+
+        test!(45.0, "45.0\n");
+        test!(-45.0f32, "-45.0\n");
+        test!(-0.0, "-0.0\n");
+        test!(1.5, "1.5\n");
+        test!(1e300, "1.0e+300\n");
+        test!(-1.5e-7, "-1.5e-7\n");
+        test!(f32::MAX, "3.4028235e+38\n");
+        test!(vec![1.0, 2.5], "- 1.0\n- 2.5\n");
+
+        test!(0.1 + 0.2, "0.30000000000000004\n");
+
+        // the shortest representation reads back as the same bits
+        let subnormals = [5e-324, -5e-324, f64::MIN_POSITIVE / 3.0, f64::from_bits(0x000F_FFFF_FFFF_FFFF)];
+        let values = [1.0, -0.0, 45.0, 0.1, 0.1 + 0.2, 1e16, 1e-300, 1e300, -1.5e-7, f64::MAX, f64::MIN, f64::MIN_POSITIVE, f64::EPSILON];
+
+        for value in values.into_iter().chain(subnormals) {
+            let output = crate::to_string(value).unwrap();
+            assert_eq!(value.to_bits(), crate::from_str::<f64>(&output).unwrap().to_bits(), "{output}");
+
+            let node = crate::from_str::<YamlNodeWrapper>(&output).unwrap().into_inner();
+            assert!(matches!(node, Yaml::Real(_)), "{output} {node:?}");
+        }
+
+        for value in [0.1f32, 1.0e-45, f32::MAX, f32::MIN_POSITIVE, 16777217.0] {
+            let output = crate::to_string(value).unwrap();
+            assert_eq!(value.to_bits(), crate::from_str::<f32>(&output).unwrap().to_bits(), "{output}");
+        }
+    }
+
+    #[test]
+    fn float_precision() {
+
+        let options = SerializerOptions::new().float_precision(2);
+        assert_eq!("- 0.10\n- 0.30\n- 2.00\n- -1234.57\n- 0.00\n- .inf\n", to_string_with_options(vec![0.1, 0.1 + 0.2, 2.0, -1234.5678, 1e-300, f64::INFINITY], options.clone()).unwrap());
+        assert_eq!("0.10\n", to_string_with_options(0.1f32, options).unwrap());
+
+        let output = to_string_with_options(2.0, SerializerOptions::new().float_precision(0)).unwrap();
+        assert_eq!("2.0\n", output);
+        assert_eq!(2.0, crate::from_str::<f64>(&output).unwrap());
+    }
+
+    #[test]
+    fn non_finite_floats() {
+        use crate::ser::NonFiniteFloats;
+
+        test!(f64::NAN, ".nan\n");
+        test!(f64::INFINITY, ".inf\n");
+        test!(f64::NEG_INFINITY, "-.inf\n");
+        test!(f32::NAN, ".nan\n");
+        test!(f32::NEG_INFINITY, "-.inf\n");
+        test!(vec![1.0, f64::INFINITY], "- 1.0\n- .inf\n");
+
+        assert!(crate::from_str::<f64>(&crate::to_string(f64::NAN).unwrap()).unwrap().is_nan());
+        assert_eq!(f64::INFINITY, crate::from_str::<f64>(&crate::to_string(f64::INFINITY).unwrap()).unwrap());
+        assert_eq!(f64::NEG_INFINITY, crate::from_str::<f64>(&crate::to_string(f64::NEG_INFINITY).unwrap()).unwrap());
+        assert!(crate::from_str::<f32>(&crate::to_string(f32::NAN).unwrap()).unwrap().is_nan());
+
+        let options = SerializerOptions::new().non_finite_floats(NonFiniteFloats::Null);
+        assert_eq!("- 1.0\n- ~\n- ~\n", to_string_with_options(vec![1.0, f64::NAN, f64::NEG_INFINITY], options.clone()).unwrap());
+        assert_eq!(None, crate::from_str::<Option<f64>>(&to_string_with_options(f64::NAN, options).unwrap()).unwrap());
+
+        let options = SerializerOptions::new().non_finite_floats(NonFiniteFloats::Error);
+        assert_eq!("1.5\n", to_string_with_options(1.5, options.clone()).unwrap());
+        let error = to_string_with_options(vec![f64::NAN], options.clone()).unwrap_err();
+        assert_eq!("Can not serialize NaN, only finite floats are allowed", error.to_string());
+        let error = to_string_with_options(f32::NEG_INFINITY, options).unwrap_err();
+        assert_eq!("Can not serialize -inf, only finite floats are allowed", error.to_string());
+    }
+
+    #[test]
+    fn bytes() {
+        use std::collections::BTreeMap;
+        use serde_bytes::{ByteBuf, Bytes};
+        use crate::ser::Style;
+
+        test!(Bytes::new(b"hello"), "!!binary aGVsbG8=\n");
+        test!(Bytes::new(b""), "!!binary ''\n");
+        test!(vec![Bytes::new(b"hi")], "- !!binary aGk=\n");
+
+        // pseudo-random bytes, so every base64 digit shows up
+        let mut state = 0x2545F4914F6CDD1Du64;
+        let random = (0..1024).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        }).collect::<Vec<_>>();
+
+        let value = BTreeMap::from([(String::from("data"), ByteBuf::from(random.clone()))]);
+        let output = crate::to_string(&value).unwrap();
+        assert!(output.starts_with("data: !!binary |-\n  "), "{output}");
+        assert!(output.lines().all(|line| line.trim().len() <= 76), "{output}");
+        assert_eq!(value, crate::from_str::<BTreeMap<String, ByteBuf>>(&output).unwrap());
+
+        let value = vec![ByteBuf::from(random.clone()), ByteBuf::from(random)];
+        let output = crate::to_string(&value).unwrap();
+        assert_eq!(value, crate::from_str::<Vec<ByteBuf>>(&output).unwrap());
+
+        let output = to_string_with_options(&value, SerializerOptions::new().style(Style::Flow)).unwrap();
+        assert!(output.starts_with("[!!binary "), "{output}");
+        assert_eq!(value, crate::from_str::<Vec<ByteBuf>>(&output).unwrap());
+    }
+
+    #[test]
+    fn single_quotes() {
+        use crate::ser::QuoteStyle;
+
+        test!("'quoted'", "'''quoted'''\n");
+        test!("'", "''''\n");
+
+        let options = SerializerOptions::new().quote_style(QuoteStyle::AlwaysQuote);
+        assert_eq!("'Hello ''world'\n", to_string_with_options("Hello 'world", options.clone()).unwrap());
+
+        for value in ["Hello 'world", "''", "it''s", "'start", "end'", "'both'", "'''", "a ' b '' c"] {
+            let output = crate::to_string(value).unwrap();
+            assert_eq!(value, crate::from_str::<String>(&output).unwrap(), "{output}");
+
+            let output = to_string_with_options(value, options.clone()).unwrap();
+            assert_eq!(value, crate::from_str::<String>(&output).unwrap(), "{output}");
+
+            let output = crate::to_string(vec![value]).unwrap();
+            assert_eq!(vec![value.to_string()], crate::from_str::<Vec<String>>(&output).unwrap(), "{output}");
+        }
+    }
+
+    #[test]
+    fn control_characters() {
+        use std::collections::BTreeMap;
+        use crate::ser::QuoteStyle;
+
+        test!("a\tb", "\"a\\tb\"\n");
+        test!("a\rb", "\"a\\rb\"\n");
+        test!("\0", "\"\\0\"\n");
+        test!("\u{1B}[31mred\u{1B}[0m", "\"\\e[31mred\\e[0m\"\n");
+        test!("\u{7F}", "\"\\u007F\"\n");
+        test!("a\r\nb", "\"a\\r\\nb\"\n");
+        test!(vec!["say \"hi\"\t\\"], "- \"say \\\"hi\\\"\\t\\\\\"\n");
+
+        let value = (0..0x20u32).chain([0x7F, 0x85, 0x9F, 0x2028, 0x2029, 0xFEFF]).filter_map(char::from_u32).collect::<String>();
+        let options = SerializerOptions::new().quote_style(QuoteStyle::AlwaysQuote);
+
+        for value in [value.as_str(), "tab\tand 'quote'", "multi\nline\twith tab", "\\\"", " \u{1}"] {
+            let output = crate::to_string(value).unwrap();
+            assert_eq!(value, crate::from_str::<String>(&output).unwrap(), "{output}");
+
+            let output = to_string_with_options(value, options.clone()).unwrap();
+            assert_eq!(value, crate::from_str::<String>(&output).unwrap(), "{output}");
+
+            let map = BTreeMap::from([(value.to_string(), vec![value.to_string()])]);
+            let output = crate::to_string(&map).unwrap();
+            assert_eq!(map, crate::from_str::<BTreeMap<String, Vec<String>>>(&output).unwrap(), "{output}");
+        }
+    }
+
+    #[test]
+    fn explicit_document_start() {
+        use crate::ser::Style;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            x: i32,
+            y: Vec<String>,
+        }
+
+        let options = SerializerOptions::new().explicit_document_start(true);
+        let value = TestStruct { x: 1, y: vec![String::from("a")] };
+
+        let output = to_string_with_options(&value, options.clone()).unwrap();
+        assert_eq!("---\nx: 1\ny:\n  - a\n", output);
+        assert_eq!(value, crate::from_str(&output).unwrap());
+
+        assert_eq!("---\n123\n", to_string_with_options(123, options.clone()).unwrap());
+        assert_eq!(123, crate::from_str::<i32>(&to_string_with_options(123, options.clone()).unwrap()).unwrap());
+
+        let output = to_string_with_options("multi\nline", options.clone()).unwrap();
+        assert_eq!("multi\nline", crate::from_str::<String>(&output).unwrap());
+
+        let output = to_string_with_options(&value, options.style(Style::Flow)).unwrap();
+        assert_eq!("---\n{x: 1, y: [a]}\n", output);
+        assert_eq!(value, crate::from_str(&output).unwrap());
+
+        assert_eq!("x: 1\ny:\n  - a\n", crate::to_string(&value).unwrap());
+    }
+
+    #[test]
+    fn round_trip() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            x: i32,
+            y: String,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum TestEnum {
+            VariantA,
+            VariantB(),
+            VariantC(i32, String),
+            VariantD(TestStruct),
+            VariantE { x: f64, y: bool },
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct TestSequences {
+            numbers: Vec<i32>,
+            nested: Vec<Vec<i32>>,
+            structs: Vec<TestStruct>,
+            variants: Vec<TestEnum>,
+            tuple: (i32, String, Vec<bool>),
+            empty: Vec<String>,
+        }
+
+        let value = TestSequences {
+            numbers: vec![1, 2, 3],
+            nested: vec![vec![1, 2], vec![], vec![3]],
+            structs: vec![TestStruct { x: 1, y: String::from("Hello\nworld") }, TestStruct { x: 2, y: String::from("Hello world") }],
+            variants: vec![
+                TestEnum::VariantA,
+                TestEnum::VariantB(),
+                TestEnum::VariantC(3, String::from("Hello")),
+                TestEnum::VariantD(TestStruct { x: 4, y: String::from("world") }),
+                TestEnum::VariantE { x: 1.5, y: true },
+            ],
+            tuple: (5, String::from("multi\nline"), vec![true, false]),
+            empty: vec![],
+        };
+
+        let output = crate::to_string(&value).unwrap();
+        assert_eq!(value, crate::from_str::<TestSequences>(&output).unwrap(), "{output}");
+
+        let value = vec![vec![vec![1, 2], vec![3]], vec![vec![4]]];
+        assert_eq!(value, crate::from_str::<Vec<Vec<Vec<i32>>>>(&crate::to_string(&value).unwrap()).unwrap());
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct TestMappings {
+            number: f64,
+            flag: bool,
+            text: String,
+            multiline: String,
+            missing: Option<i32>,
+            unit: TestEnum,
+            newtype: TestEnum,
+            nested: TestStruct,
+            map: std::collections::BTreeMap<String, i32>,
+        }
+
+        let value = TestMappings {
+            number: -1.5,
+            flag: true,
+            text: String::from("Hello world"),
+            multiline: String::from("Hello\nworld"),
+            missing: None,
+            unit: TestEnum::VariantA,
+            newtype: TestEnum::VariantD(TestStruct { x: 1, y: String::from("Hello") }),
+            nested: TestStruct { x: 2, y: String::from("world") },
+            map: std::collections::BTreeMap::from([(String::from("a"), 1), (String::from("b"), 2)]),
+        };
+
+        let output = crate::to_string(&value).unwrap();
+        assert_eq!(value, crate::from_str::<TestMappings>(&output).unwrap(), "{output}");
     }
 }
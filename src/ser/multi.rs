@@ -0,0 +1,66 @@
+use std::fmt::Write;
+use serde::Serialize;
+use super::{Errors, SerializerOptions, YamlSerializer};
+
+/// Serializes every item as a document of a multi-document stream, separated by `---`
+pub fn to_string_multi<I: IntoIterator<Item = T>, T: Serialize>(documents: I) -> Result<String, Errors> {
+    to_string_multi_with_options(documents, SerializerOptions::default())
+}
+
+pub fn to_string_multi_with_options<I: IntoIterator<Item = T>, T: Serialize>(documents: I, options: SerializerOptions) -> Result<String, Errors> {
+    let mut out = String::new();
+    write_multi(&mut out, documents, &options)?;
+    Ok(out)
+}
+
+/// Writes the documents one after another, each by a new serializer. A `---` marker starts every document but
+/// the first one, which has it only if the `explicit_document_start` option is set.
+pub(super) fn write_multi<I: IntoIterator<Item = T>, T: Serialize>(writer: &mut dyn Write, documents: I, options: &SerializerOptions) -> Result<(), Errors> {
+    for (index, document) in documents.into_iter().enumerate() {
+        let options = options.clone().explicit_document_start(index > 0 || options.explicit_document_start);
+        YamlSerializer::with_options(writer, options).write(document)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde::{Deserialize, Serialize};
+    use crate::ser::{to_writer_multi, SerializerOptions};
+    use super::{to_string_multi, to_string_multi_with_options};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum Document {
+        Map(BTreeMap<String, i32>),
+        Seq(Vec<String>),
+        Scalar(String),
+    }
+
+    #[test]
+    fn should_work() {
+        let documents = vec![
+            Document::Map(BTreeMap::from([(String::from("a"), 1), (String::from("b"), 2)])),
+            Document::Seq(vec![String::from("x"), String::from("multi\nline")]),
+            Document::Scalar(String::from("Hello world")),
+        ];
+
+        let output = to_string_multi(&documents).unwrap();
+        assert_eq!("a: 1\nb: 2\n---\n- x\n- |-\n  multi\n  line\n---\nHello world\n", output);
+        assert_eq!(documents, crate::from_str_multi::<Document>(&output).unwrap());
+
+        let options = SerializerOptions::new().explicit_document_start(true);
+        let output = to_string_multi_with_options(&documents, options).unwrap();
+        assert!(output.starts_with("---\na: 1\n"), "{output}");
+        assert_eq!(documents, crate::from_str_multi::<Document>(&output).unwrap());
+
+        let mut bytes: Vec<u8> = vec![];
+        to_writer_multi(&mut bytes, &documents).unwrap();
+        assert_eq!(to_string_multi(&documents).unwrap().into_bytes(), bytes);
+
+        assert_eq!("1\n---\n2\n---\n3\n", to_string_multi([1, 2, 3]).unwrap());
+        assert_eq!("", to_string_multi(Vec::<i32>::new()).unwrap());
+    }
+}
@@ -0,0 +1,534 @@
+//! Serializes a value into a [`Yaml`] tree instead of text, so it can be combined with other nodes before it is
+//! written
+
+use serde::ser::{SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple, SerializeTupleStruct, SerializeTupleVariant};
+use serde::{Serialize, Serializer};
+use yaml_rust2::yaml::Hash;
+use yaml_rust2::Yaml;
+use crate::anchor::{ALIAS_TOKEN, ANCHOR_TOKEN};
+use crate::comment::COMMENT_TOKEN;
+use crate::with::one_or_many::SINGLE_TOKEN;
+use crate::wrapper::YamlNodeWrapper;
+use super::{binary, format_float, EnumRepr, Errors, SerializerOptions};
+
+/// Serializes the value into a [`Yaml`] node, the same tree [`from_str`](crate::from_str) reads the output of
+/// [`to_string`](super::to_string) into. Enum variants are mappings of the variant name to its value, unit
+/// variants the name alone, like [`to_string`](super::to_string) writes them, and bytes the base64 string of
+/// their `!!binary` scalar.
+pub fn to_yaml<T: Serialize + ?Sized>(value: &T) -> Result<Yaml, Errors> {
+    to_yaml_with(value, SerializerOptions::default())
+}
+
+/// Like [`to_yaml`], with the variants of enums represented as
+/// [`to_string_with_options`](super::to_string_with_options) writes them with the same options. A [`Yaml`] node
+/// has no tags, so [`EnumRepr::Tag`] fails the serialization of a variant. The options about the text, e.g. the
+/// style, don't change the node.
+pub fn to_yaml_with<T: Serialize + ?Sized>(value: &T, options: SerializerOptions) -> Result<Yaml, Errors> {
+    value.serialize(YamlNodeSerializer::new(&options))
+}
+
+/// Serializes the value into a [`YamlNodeWrapper`], like `serde_json::to_value` does, e.g. to merge it with another
+/// node or to compare it in a test. [`from_value`](crate::from_value) reads it back.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<YamlNodeWrapper, Errors> {
+    to_yaml(value).map(YamlNodeWrapper::new)
+}
+
+/// Serializer whose output is a [`Yaml`] node, see [`to_yaml`]. A value written with an anchor or a comment is the
+/// node itself, an alias to it can't be represented and fails the serialization.
+#[derive(Clone, Copy)]
+pub struct YamlNodeSerializer<'o> {
+    options: &'o SerializerOptions,
+}
+
+impl<'o> YamlNodeSerializer<'o> {
+    pub fn new(options: &'o SerializerOptions) -> Self {
+        YamlNodeSerializer { options }
+    }
+
+    /// Fails the serialization of a variant which would be written as a tag, see [`EnumRepr::Tag`]
+    fn check_variant(&self, variant: &'static str) -> Result<(), Errors> {
+        if self.options.enum_representation == EnumRepr::Tag {
+            return Err(Errors::UnsupportedSerializationError(format!("Tag of variant {variant} can't be a Yaml node")));
+        }
+
+        Ok(())
+    }
+}
+
+/// Yaml::Integer is 64-bit, a larger value is kept as a number in Yaml::Real, which is written the same way
+fn integer<T: TryInto<i64> + std::fmt::Display + Copy>(v: T) -> Result<Yaml, Errors> {
+    Ok(v.try_into().map_or_else(|_| Yaml::Real(v.to_string()), Yaml::Integer))
+}
+
+fn real(repr: String, v: f64) -> Yaml {
+    let repr = match v {
+        v if v.is_finite() => format_float(repr),
+        v if v.is_nan() => String::from(".nan"),
+        v if v > 0.0 => String::from(".inf"),
+        _ => String::from("-.inf"),
+    };
+
+    Yaml::Real(repr)
+}
+
+/// Wraps the value of a variant into a mapping of the variant name to it, e.g. `{Variant: 1}`
+fn variant(name: &'static str, value: Yaml) -> Yaml {
+    let mut hash = Hash::new();
+    hash.insert(Yaml::String(String::from(name)), value);
+    Yaml::Hash(hash)
+}
+
+impl<'o> Serializer for YamlNodeSerializer<'o> {
+    type Ok = Yaml;
+    type Error = Errors;
+    type SerializeSeq = ArraySerializer<'o>;
+    type SerializeTuple = ArraySerializer<'o>;
+    type SerializeTupleStruct = ArraySerializer<'o>;
+    type SerializeTupleVariant = ArraySerializer<'o>;
+    type SerializeMap = HashSerializer<'o>;
+    type SerializeStruct = HashSerializer<'o>;
+    type SerializeStructVariant = HashSerializer<'o>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(Yaml::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        Ok(real(format!("{v:?}"), v.into()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        Ok(real(format!("{v:?}"), v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        Ok(Yaml::String(v.into()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(Yaml::String(v.into()))
+    }
+
+    /// A `Yaml` node has no tags, so bytes are the base64 string of a `!!binary` scalar without the tag
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(Yaml::String(binary::encode(v)))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Yaml::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Yaml::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Ok(Yaml::Null)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, name: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.check_variant(name)?;
+
+        if self.options.unit_variant_mapping {
+            return Ok(variant(name, Yaml::Null));
+        }
+
+        Ok(Yaml::String(String::from(name)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, name: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
+        if name == SINGLE_TOKEN && self.options.one_or_many_sequences {
+            return Ok(Yaml::Array(vec![value.serialize(self)?]));
+        }
+
+        if name != ALIAS_TOKEN {
+            return value.serialize(self);
+        }
+
+        let name = match value.serialize(self)? {
+            Yaml::String(name) => name,
+            _ => return Err(Errors::UnsupportedSerializationError(String::from("Anchor name must be a string"))),
+        };
+
+        Err(Errors::UnsupportedSerializationError(format!("Alias {name} can't be a Yaml node")))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _variant_index: u32, name: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
+        self.check_variant(name)?;
+        Ok(variant(name, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(ArraySerializer {
+            serializer: self,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+            wrapped: false,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        let mut serializer = self.serialize_seq(Some(len))?;
+        serializer.wrapped = name == ANCHOR_TOKEN || name == COMMENT_TOKEN;
+        Ok(serializer)
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        self.check_variant(variant)?;
+        let mut serializer = self.serialize_seq(Some(len))?;
+        serializer.variant = Some(variant);
+        Ok(serializer)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(HashSerializer {
+            serializer: self,
+            hash: Hash::new(),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeStructVariant, Self::Error> {
+        self.check_variant(variant)?;
+        let mut serializer = self.serialize_map(Some(len))?;
+        serializer.variant = Some(variant);
+        Ok(serializer)
+    }
+}
+
+pub struct ArraySerializer<'o> {
+    serializer: YamlNodeSerializer<'o>,
+    items: Vec<Yaml>,
+    /// Name of the tuple variant the elements belong to
+    variant: Option<&'static str>,
+    /// The elements are the name of an anchor or a comment and the node it belongs to, which is kept alone, see
+    /// [`Anchored`](crate::Anchored) and [`Commented`](crate::Commented)
+    wrapped: bool,
+}
+
+impl ArraySerializer<'_> {
+    fn process_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Errors> {
+        self.items.push(value.serialize(self.serializer)?);
+        Ok(())
+    }
+
+    fn process_end(mut self) -> Result<Yaml, Errors> {
+        if self.wrapped {
+            return Ok(self.items.pop().unwrap_or(Yaml::Null));
+        }
+
+        let array = Yaml::Array(self.items);
+
+        match self.variant {
+            Some(name) => Ok(variant(name, array)),
+            None => Ok(array),
+        }
+    }
+}
+
+impl SerializeSeq for ArraySerializer<'_> {
+    type Ok = Yaml;
+    type Error = Errors;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.process_element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.process_end()
+    }
+}
+
+impl SerializeTuple for ArraySerializer<'_> {
+    type Ok = Yaml;
+    type Error = Errors;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.process_element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.process_end()
+    }
+}
+
+impl SerializeTupleStruct for ArraySerializer<'_> {
+    type Ok = Yaml;
+    type Error = Errors;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.process_element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.process_end()
+    }
+}
+
+impl SerializeTupleVariant for ArraySerializer<'_> {
+    type Ok = Yaml;
+    type Error = Errors;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.process_element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.process_end()
+    }
+}
+
+pub struct HashSerializer<'o> {
+    serializer: YamlNodeSerializer<'o>,
+    hash: Hash,
+    /// Key of the entry whose value is serialized next
+    key: Option<Yaml>,
+    /// Name of the struct variant the fields belong to
+    variant: Option<&'static str>,
+}
+
+impl HashSerializer<'_> {
+    fn process_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Errors> {
+        if self.key.is_some() {
+            return Err(Errors::UnsupportedSerializationError(String::from("Map key serialized before the value of the key before it")));
+        }
+
+        self.key = Some(key.serialize(self.serializer)?);
+        Ok(())
+    }
+
+    fn process_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Errors> {
+        let Some(key) = self.key.take() else {
+            return Err(Errors::UnsupportedSerializationError(String::from("Map value serialized before its key")));
+        };
+
+        self.hash.insert(key, value.serialize(self.serializer)?);
+        Ok(())
+    }
+
+    fn process_end(self) -> Result<Yaml, Errors> {
+        if self.key.is_some() {
+            return Err(Errors::UnsupportedSerializationError(String::from("Map ended before the value of its last key")));
+        }
+
+        let hash = Yaml::Hash(self.hash);
+
+        match self.variant {
+            Some(name) => Ok(variant(name, hash)),
+            None => Ok(hash),
+        }
+    }
+}
+
+impl SerializeMap for HashSerializer<'_> {
+    type Ok = Yaml;
+    type Error = Errors;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.process_key(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.process_value(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.process_end()
+    }
+}
+
+impl SerializeStruct for HashSerializer<'_> {
+    type Ok = Yaml;
+    type Error = Errors;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> {
+        self.process_key(key)?;
+        self.process_value(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.process_end()
+    }
+}
+
+impl SerializeStructVariant for HashSerializer<'_> {
+    type Ok = Yaml;
+    type Error = Errors;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> {
+        self.process_key(key)?;
+        self.process_value(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.process_end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde::Serialize;
+    use yaml_rust2::yaml::Hash;
+    use yaml_rust2::{Yaml, YamlLoader};
+    use serde_bytes::ByteBuf;
+    use crate::{from_value, to_string, to_string_with_options, to_value, Anchored, EnumRepr, SerializerOptions};
+    use super::{to_yaml, to_yaml_with};
+
+    #[derive(Serialize)]
+    enum Mode {
+        Off,
+        Limit(u32),
+        Range(u32, u32),
+        Window { from: u32, to: u32 },
+    }
+
+    #[derive(Serialize)]
+    struct Limits {
+        cpu: f64,
+        memory: Option<String>,
+    }
+
+    #[derive(Serialize)]
+    struct Service {
+        name: &'static str,
+        replicas: u16,
+        enabled: bool,
+        ports: Vec<u16>,
+        limits: Limits,
+        modes: Vec<Mode>,
+        labels: BTreeMap<&'static str, &'static str>,
+    }
+
+    fn hash<const N: usize>(entries: [(&str, Yaml); N]) -> Yaml {
+        Yaml::Hash(entries.into_iter().map(|(key, value)| (Yaml::String(String::from(key)), value)).collect::<Hash>())
+    }
+
+    fn string(value: &str) -> Yaml {
+        Yaml::String(String::from(value))
+    }
+
+    #[test]
+    fn should_work() {
+        let service = Service {
+            name: "web",
+            replicas: 3,
+            enabled: true,
+            ports: vec![80, 443],
+            limits: Limits { cpu: 1.5, memory: None },
+            modes: vec![Mode::Off, Mode::Limit(2), Mode::Range(1, 2), Mode::Window { from: 1, to: 2 }],
+            labels: BTreeMap::from([("tier", "front")]),
+        };
+
+        let expected = hash([
+            ("name", string("web")),
+            ("replicas", Yaml::Integer(3)),
+            ("enabled", Yaml::Boolean(true)),
+            ("ports", Yaml::Array(vec![Yaml::Integer(80), Yaml::Integer(443)])),
+            ("limits", hash([("cpu", Yaml::Real(String::from("1.5"))), ("memory", Yaml::Null)])),
+            ("modes", Yaml::Array(vec![
+                string("Off"),
+                hash([("Limit", Yaml::Integer(2))]),
+                hash([("Range", Yaml::Array(vec![Yaml::Integer(1), Yaml::Integer(2)]))]),
+                hash([("Window", hash([("from", Yaml::Integer(1)), ("to", Yaml::Integer(2))]))]),
+            ])),
+            ("labels", hash([("tier", string("front"))])),
+        ]);
+
+        let node = to_yaml(&service).unwrap();
+        assert_eq!(expected, node);
+
+        // the string serializer writes the same tree
+        let loaded = YamlLoader::load_from_str(&to_string(&service).unwrap()).unwrap();
+        assert_eq!(vec![node], loaded);
+    }
+
+    #[test]
+    fn special_values() {
+        assert_eq!(Yaml::Real(String::from("1.0e+300")), to_yaml(&1e300).unwrap());
+        assert_eq!(Yaml::Real(String::from("-.inf")), to_yaml(&f64::NEG_INFINITY).unwrap());
+        assert_eq!(Yaml::Integer(i64::MAX), to_yaml(&(i64::MAX as u64)).unwrap());
+        assert_eq!(Yaml::Real(String::from("18446744073709551615")), to_yaml(&u64::MAX).unwrap());
+        assert_eq!(Yaml::Real(String::from("-170141183460469231731687303715884105728")), to_yaml(&i128::MIN).unwrap());
+
+        assert_eq!(Yaml::Integer(1), to_yaml(&Anchored::define("one", &1)).unwrap());
+        assert_eq!("Alias one can't be a Yaml node", to_yaml(&Anchored::<i32>::reference("one")).unwrap_err().to_string());
+    }
+
+    #[test]
+    fn options() {
+        let modes = vec![Mode::Off, Mode::Limit(2)];
+
+        // the string serializer writes the same tree
+        for options in [SerializerOptions::new(), SerializerOptions::new().unit_variant_mapping(true)] {
+            let loaded = YamlLoader::load_from_str(&to_string_with_options(&modes, options.clone()).unwrap()).unwrap();
+            assert_eq!(loaded, vec![to_yaml_with(&modes, options).unwrap()]);
+        }
+
+        assert_eq!(Yaml::Array(vec![hash([("Off", Yaml::Null)])]), to_yaml_with(&[Mode::Off], SerializerOptions::new().unit_variant_mapping(true)).unwrap());
+        assert_eq!("Tag of variant Off can't be a Yaml node", to_yaml_with(&modes, SerializerOptions::new().enum_representation(EnumRepr::Tag)).unwrap_err().to_string());
+        assert_eq!(Yaml::Integer(2), to_yaml_with(&2, SerializerOptions::new().enum_representation(EnumRepr::Tag)).unwrap());
+    }
+
+    #[test]
+    fn bytes() {
+        let bytes = ByteBuf::from(b"hello".to_vec());
+        assert_eq!(string("aGVsbG8="), to_yaml(&bytes).unwrap());
+        assert_eq!(vec![to_yaml(&bytes).unwrap()], YamlLoader::load_from_str(&to_string(&bytes).unwrap()).unwrap());
+        assert_eq!(bytes, from_value::<ByteBuf>(to_value(&bytes).unwrap()).unwrap());
+        assert!(from_value::<ByteBuf>(to_value("not base64").unwrap()).is_err());
+    }
+}
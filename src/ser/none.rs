@@ -0,0 +1,139 @@
+//! Tells whether a value serializes as `None` without writing it, so a mapping entry can be dropped before its
+//! key is written
+
+use std::fmt;
+use serde::ser::{Impossible, Serialize, Serializer};
+
+/// Returns true if the value is `None`, or a newtype struct around `None`
+pub(crate) fn is_none<T: Serialize + ?Sized>(value: &T) -> bool {
+    value.serialize(NoneProbe {}).is_ok()
+}
+
+/// Serializes nothing and succeeds only for `None`. Any other value stops it with [`NotNone`] at its first call.
+struct NoneProbe {}
+
+#[derive(Debug)]
+struct NotNone {}
+
+impl fmt::Display for NotNone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("not None")
+    }
+}
+
+impl std::error::Error for NotNone {}
+
+impl serde::ser::Error for NotNone {
+    fn custom<T: fmt::Display>(_msg: T) -> Self {
+        NotNone {}
+    }
+}
+
+macro_rules! not_none {
+    ($($method:ident($($arg:ty),*);)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<Self::Ok, Self::Error> {
+                Err(NotNone {})
+            }
+        )*
+    }
+}
+
+impl Serializer for NoneProbe {
+    type Ok = ();
+    type Error = NotNone;
+    type SerializeSeq = Impossible<(), NotNone>;
+    type SerializeTuple = Impossible<(), NotNone>;
+    type SerializeTupleStruct = Impossible<(), NotNone>;
+    type SerializeTupleVariant = Impossible<(), NotNone>;
+    type SerializeMap = Impossible<(), NotNone>;
+    type SerializeStruct = Impossible<(), NotNone>;
+    type SerializeStructVariant = Impossible<(), NotNone>;
+
+    not_none! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+        serialize_bytes(&[u8]);
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+        serialize_unit_variant(&'static str, u32, &'static str);
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<Self::Ok, Self::Error> {
+        Err(NotNone {})
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<Self::Ok, Self::Error> {
+        Err(NotNone {})
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(NotNone {})
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(NotNone {})
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(NotNone {})
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(NotNone {})
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(NotNone {})
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(NotNone {})
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(NotNone {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+    use super::is_none;
+
+    #[derive(Serialize)]
+    struct Wrapper(Option<i32>);
+
+    #[test]
+    fn should_work() {
+        assert!(is_none(&None::<i32>));
+        assert!(is_none(&Wrapper(None)));
+        assert!(!is_none(&Some(1)));
+        assert!(!is_none(&Some(None::<i32>)));
+        assert!(!is_none(&Wrapper(Some(1))));
+        assert!(!is_none(&()));
+        assert!(!is_none(&vec![None::<i32>]));
+        assert!(!is_none("None"));
+    }
+}
@@ -0,0 +1,256 @@
+use std::sync::Arc;
+use super::ScalarStyler;
+
+/// Options that tune the output of [`YamlSerializer`](super::YamlSerializer)
+#[derive(Debug, Clone, Default)]
+pub struct SerializerOptions {
+    pub(crate) quote_style: QuoteStyle,
+    pub(crate) style: Style,
+    pub(crate) explicit_document_start: bool,
+    pub(crate) explicit_document_end: bool,
+    pub(crate) non_finite_floats: NonFiniteFloats,
+    pub(crate) unit_variant_mapping: bool,
+    pub(crate) sort_keys: bool,
+    pub(crate) sort_struct_fields: bool,
+    pub(crate) null_style: NullStyle,
+    pub(crate) omit_none_values: bool,
+    pub(crate) enum_representation: EnumRepr,
+    pub(crate) scalar_styler: Option<Arc<dyn ScalarStyler>>,
+    pub(crate) compact_leaf_seqs: Option<usize>,
+    pub(crate) escape_non_ascii: bool,
+    pub(crate) header_comment: Option<String>,
+    pub(crate) float_precision: Option<usize>,
+    pub(crate) line_ending: LineEnding,
+    pub(crate) validate_output: bool,
+    pub(crate) one_or_many_sequences: bool,
+}
+
+/// How sequences and mappings are written
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Style {
+    /// One entry per line, nested collections are indented
+    #[default]
+    Block,
+    /// On a single line, e.g. `{x: 1, y: [1, 2, 3]}`
+    Flow,
+}
+
+/// How single-line strings are written. Strings with control characters are always double-quoted with escapes,
+/// e.g. `"a\tb"`, the only style that can carry them.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum QuoteStyle {
+    /// Without quotes if the string reads back as the same string, e.g. `web`, otherwise single-quoted,
+    /// e.g. `'true'` or `'key: value'`
+    #[default]
+    Auto,
+    /// Always single-quoted
+    AlwaysQuote,
+    /// Always double-quoted, multi-line strings too, e.g. `"web"` or `"Hello\nworld"`
+    ForceDouble,
+}
+
+/// The line break ending every line
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum LineEnding {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`, which some Windows tools expect. Line breaks inside strings read back as `\n` all the same.
+    CrLf,
+}
+
+/// How NaN and infinities are written, which only YAML has a notation for
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum NonFiniteFloats {
+    /// `.nan`, `.inf` and `-.inf` of the core schema
+    #[default]
+    Special,
+    /// Null in the [`NullStyle`] of the options, like JavaScript's `JSON.stringify` does
+    Null,
+    /// Fail the serialization, like serde_json does
+    Error,
+}
+
+/// How `None`, `()` and unit structs are written
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum NullStyle {
+    /// `~`
+    #[default]
+    Tilde,
+    /// `null`
+    Null,
+    /// Nothing after the key of a mapping entry, e.g. `key:`. Anywhere else, e.g. in a sequence or as the whole
+    /// document, `null` is written.
+    Empty,
+}
+
+/// How the variant of an enum is written
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum EnumRepr {
+    /// A mapping of the variant name to its value, e.g. `Variant: 1`. Unit variants are the name alone unless
+    /// [`unit_variant_mapping`](SerializerOptions::unit_variant_mapping) is set.
+    #[default]
+    Mapping,
+    /// The value with a local tag naming the variant, e.g. `!Variant 1`, and the tag alone for unit variants,
+    /// like serde_yaml 0.9 does. The value of a newtype variant which is an enum itself can't have a second tag,
+    /// so it is written as a mapping, e.g. `!Outer {Inner: 1}`.
+    Tag,
+}
+
+impl SerializerOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The options of the canonical form, which writes the same data as the same bytes however it is held, see
+    /// [`to_string_canonical`](super::to_string_canonical): block style, the entries of maps and the fields of
+    /// structs sorted by key, every string double-quoted, and null as `~`. Floats have the shortest representation
+    /// with a fraction and a signed exponent, e.g. `1.5` or `1.0e+300`, and `.nan`, `.inf` or `-.inf`.
+    pub fn canonical() -> Self {
+        Self::new()
+            .sort_keys(true)
+            .sort_struct_fields(true)
+            .quote_style(QuoteStyle::ForceDouble)
+    }
+
+    /// The options of the compact form, which writes the whole document on a single line, see
+    /// [`to_string_compact`](super::to_string_compact): flow style throughout, multi-line strings double-quoted
+    /// with `\n` escapes, and null as `null`, e.g. `{x: 1, y: [a, b], z: null}`.
+    pub fn compact() -> Self {
+        Self::new()
+            .style(Style::Flow)
+            .null_style(NullStyle::Null)
+    }
+
+    /// How single-line strings are written. Defaults to [`QuoteStyle::Auto`].
+    pub fn quote_style(mut self, value: QuoteStyle) -> Self {
+        self.quote_style = value;
+        self
+    }
+
+    /// How sequences and mappings are written. Defaults to [`Style::Block`].
+    pub fn style(mut self, value: Style) -> Self {
+        self.style = value;
+        self
+    }
+
+    /// Write the `---` document start marker before the document, which tools concatenating YAML files
+    /// expect. Defaults to false.
+    pub fn explicit_document_start(mut self, value: bool) -> Self {
+        self.explicit_document_start = value;
+        self
+    }
+
+    /// Write the `...` document end marker after the document, which tells a reader of a stream appended to that
+    /// the document is complete before the next one starts. Defaults to false.
+    pub fn explicit_document_end(mut self, value: bool) -> Self {
+        self.explicit_document_end = value;
+        self
+    }
+
+    /// How NaN and infinities are written. Defaults to [`NonFiniteFloats::Special`].
+    pub fn non_finite_floats(mut self, value: NonFiniteFloats) -> Self {
+        self.non_finite_floats = value;
+        self
+    }
+
+    /// Write unit variants as a mapping to null, e.g. `VariantA: ~`, like versions up to 0.1.2 did, instead of
+    /// the variant name alone. Defaults to false.
+    pub fn unit_variant_mapping(mut self, value: bool) -> Self {
+        self.unit_variant_mapping = value;
+        self
+    }
+
+    /// Write the entries of maps sorted by their serialized keys, so e.g. a `HashMap` is written the same way
+    /// every time. Entries are buffered until the map ends. Defaults to false.
+    pub fn sort_keys(mut self, value: bool) -> Self {
+        self.sort_keys = value;
+        self
+    }
+
+    /// Sort the fields of structs as well when [`sort_keys`](Self::sort_keys) is set, instead of keeping
+    /// their declaration order. Defaults to false.
+    pub fn sort_struct_fields(mut self, value: bool) -> Self {
+        self.sort_struct_fields = value;
+        self
+    }
+
+    /// How null values are written. Defaults to [`NullStyle::Tilde`].
+    pub fn null_style(mut self, value: NullStyle) -> Self {
+        self.null_style = value;
+        self
+    }
+
+    /// Leave out the entries of maps and structs whose value is `None`, as if every `Option` field had
+    /// `#[serde(skip_serializing_if = "Option::is_none")]`. `None` elements of sequences are still written.
+    /// Defaults to false.
+    pub fn omit_none_values(mut self, value: bool) -> Self {
+        self.omit_none_values = value;
+        self
+    }
+
+    /// How the variants of enums are written. Defaults to [`EnumRepr::Mapping`].
+    pub fn enum_representation(mut self, value: EnumRepr) -> Self {
+        self.enum_representation = value;
+        self
+    }
+
+    /// Chooses the style of every string, see [`ScalarStyler`]. Defaults to the rules of
+    /// [`DefaultStyler`](super::DefaultStyler) with the [`quote_style`](Self::quote_style) of the options.
+    pub fn scalar_styler<S: ScalarStyler + 'static>(mut self, value: S) -> Self {
+        self.scalar_styler = Some(Arc::new(value));
+        self
+    }
+
+    /// Write a block sequence of scalars in flow style after its key or dash, e.g. `ports: [80, 443]`, if it
+    /// fits within the width, counted from `[` to `]`. Elements are buffered until the sequence is known to fit.
+    /// Defaults to block style for every sequence.
+    pub fn compact_leaf_seqs(mut self, max_width: usize) -> Self {
+        self.compact_leaf_seqs = Some(max_width);
+        self
+    }
+
+    /// Write strings with characters outside of ASCII double-quoted, with escapes for those characters, e.g.
+    /// `"caf\u00E9"` or `"\U0001F600"`, so the output is ASCII alone. Defaults to false.
+    pub fn escape_non_ascii(mut self, value: bool) -> Self {
+        self.escape_non_ascii = value;
+        self
+    }
+
+    /// Write the comment before every document, each of its lines as a comment line, e.g.
+    /// `# managed by deployctl, do not edit`. Comments of single values are written with
+    /// [`Commented`](crate::Commented).
+    pub fn header_comment(mut self, value: impl Into<String>) -> Self {
+        self.header_comment = Some(value.into());
+        self
+    }
+
+    /// Write finite floats with the number of decimals, e.g. `0.10` for `0.1` with 2, instead of the shortest
+    /// representation reading back as the same float. A float without decimals still gets a fraction, e.g. `2.0`.
+    /// Defaults to the shortest representation.
+    pub fn float_precision(mut self, value: usize) -> Self {
+        self.float_precision = Some(value);
+        self
+    }
+
+    /// The line break ending every line, those of block scalars included. Defaults to [`LineEnding::Lf`].
+    pub fn line_ending(mut self, value: LineEnding) -> Self {
+        self.line_ending = value;
+        self
+    }
+
+    /// Parse every document with yaml_rust2 once it is written, and fail with
+    /// [`Errors::InvalidOutputError`](super::Errors::InvalidOutputError) if it isn't well-formed YAML, which would be
+    /// a bug of the serializer. A copy of the document is kept until it is parsed. Defaults to false.
+    pub fn validate_output(mut self, value: bool) -> Self {
+        self.validate_output = value;
+        self
+    }
+
+    /// Write a field of [`with::one_or_many`](crate::with::one_or_many) with a single element as a sequence, e.g.
+    /// `command: [echo]` instead of `command: echo`. Defaults to false.
+    pub fn one_or_many_sequences(mut self, value: bool) -> Self {
+        self.one_or_many_sequences = value;
+        self
+    }
+}
@@ -0,0 +1,176 @@
+//! Decides whether a string can be written as a plain (unquoted) scalar, see
+//! <https://yaml.org/spec/1.2.2/#733-plain-style>
+
+use crate::de::resolve::{resolve, Resolved};
+use crate::Schema;
+
+/// Returns true if the string reads back as the same string when written without quotes. Inside a flow
+/// collection the flow indicators `,[]{}` end a plain scalar, so they need quotes anywhere in the string.
+pub(crate) fn is_plain_safe(value: &str, flow: bool) -> bool {
+    let Some(first) = value.chars().next() else {
+        return false;
+    };
+
+    if value.starts_with([' ', '\t']) || value.ends_with([' ', '\t', ':']) {
+        return false;
+    }
+
+    if value.chars().any(|c| c.is_control() || c == '\u{FEFF}') {
+        return false;
+    }
+
+    // indicators, of which `-`, `?` and `:` may start a plain scalar if a non-space character follows
+    let second = value.chars().nth(1);
+    match first {
+        '-' | '?' | ':' if second.is_some_and(|c| c != ' ') => {},
+        '-' | '?' | ':' | ',' | '[' | ']' | '{' | '}' | '#' | '&' | '*' | '!' | '|' | '>' | '\'' | '"' | '%' | '@' | '`' => return false,
+        _ => {},
+    }
+
+    // document markers
+    if value.starts_with("---") || value.starts_with("...") {
+        return false;
+    }
+
+    if value.contains(": ") || value.contains(" #") {
+        return false;
+    }
+
+    if flow && value.contains([',', '[', ']', '{', '}']) {
+        return false;
+    }
+
+    matches!(resolve(value, Schema::Core), Resolved::String) && !is_yaml11_bool(value)
+}
+
+/// Booleans of YAML 1.1, which other tools still read as booleans. `y` and `n` are left out like most of
+/// them do, so e.g. coordinates keep plain keys.
+fn is_yaml11_bool(value: &str) -> bool {
+    matches!(value, "yes" | "Yes" | "YES" | "no" | "No" | "NO" | "on" | "On" | "ON" | "off" | "Off" | "OFF")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_plain_safe;
+
+    #[test]
+    fn should_work() {
+        let cases = [
+            // plain
+            ("web", true),
+            ("Hello world", true),
+            ("nginx:1.25", true),
+            ("app.kubernetes.io/name", true),
+            ("http://example.com/a#b", true),
+            ("Hello 'world", true),
+            ("say \"hi\"", true),
+            ("-foo", true),
+            ("?foo", true),
+            (":foo", true),
+            ("a,b", true),
+            ("a[0]", true),
+            ("Grüße 😀", true),
+            ("1.2.3", true),
+            ("0x", true),
+            ("nulls", true),
+            ("truely", true),
+            ("--", true),
+            ("y", true),
+            ("N", true),
+            // empty and whitespace
+            ("", false),
+            (" ", false),
+            (" leading", false),
+            ("trailing ", false),
+            ("tab\t", false),
+            // control characters
+            ("a\nb", false),
+            ("a\tb", false),
+            ("a\u{7}b", false),
+            ("\u{FEFF}a", false),
+            // indicators at the start
+            ("-", false),
+            ("- item", false),
+            ("?", false),
+            (": x", false),
+            (",a", false),
+            ("[a]", false),
+            ("]", false),
+            ("{a}", false),
+            ("}", false),
+            ("# comment", false),
+            ("&anchor", false),
+            ("*alias", false),
+            ("!tag", false),
+            ("|", false),
+            (">", false),
+            ("'quoted'", false),
+            ("\"quoted\"", false),
+            ("%YAML", false),
+            ("@at", false),
+            ("`tick", false),
+            ("---", false),
+            ("--- a", false),
+            ("...", false),
+            // indicators inside
+            ("key: value", false),
+            ("a #comment", false),
+            ("key:", false),
+            // other types
+            ("~", false),
+            ("null", false),
+            ("Null", false),
+            ("NULL", false),
+            ("true", false),
+            ("False", false),
+            ("TRUE", false),
+            ("yes", false),
+            ("No", false),
+            ("on", false),
+            ("OFF", false),
+            ("0", false),
+            ("123", false),
+            ("-123", false),
+            ("+1", false),
+            ("0o17", false),
+            ("0x1F", false),
+            ("123456789012345678901234567890", false),
+            ("1.5", false),
+            (".5", false),
+            ("1.", false),
+            ("1e3", false),
+            ("-1.5E-3", false),
+            (".inf", false),
+            ("-.Inf", false),
+            (".nan", false),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(expected, is_plain_safe(value, false), "{value:?}");
+        }
+
+        let flow_cases = [
+            ("web", true),
+            ("nginx:1.25", true),
+            ("a,b", false),
+            ("a[0]", false),
+            ("a]", false),
+            ("{a", false),
+            ("a}", false),
+            ("key: value", false),
+        ];
+
+        for (value, expected) in flow_cases {
+            assert_eq!(expected, is_plain_safe(value, true), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for value in ["web", "nginx:1.25", "-foo", ":foo", "a,b", "Hello 'world", "http://example.com/a#b"] {
+            assert!(is_plain_safe(value, false));
+            assert_eq!(value, crate::from_str::<String>(value).unwrap());
+            assert_eq!(vec![value.to_string()], crate::from_str::<Vec<String>>(&format!("- {value}")).unwrap());
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Decides the style strings are written in, see <https://yaml.org/spec/1.2.2/#73-flow-scalar-styles> and
+//! <https://yaml.org/spec/1.2.2/#81-block-scalar-styles>
+
+use std::fmt::Debug;
+use super::plain::is_plain_safe;
+use super::{block_chomping, needs_escape, QuoteStyle};
+
+/// Style of a string scalar
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ScalarStyle {
+    /// Without quotes, e.g. `web`
+    Plain,
+    /// `'web'`
+    SingleQuoted,
+    /// `"web"`, with escapes for characters no other style can carry
+    DoubleQuoted,
+    /// `|` block, the lines kept as they are
+    Literal,
+    /// `>` block, the line breaks are written as an empty line
+    Folded,
+}
+
+/// Where a string is written
+#[derive(Debug, Clone, Copy)]
+pub struct ScalarContext {
+    pub(crate) level: i32,
+    /// The scalar is a mapping key
+    pub(crate) key: bool,
+    /// The scalar is inside a flow collection
+    pub(crate) flow: bool,
+}
+
+impl ScalarContext {
+    /// Nesting depth of the scalar, 0 at the root of the document
+    pub fn depth(&self) -> usize {
+        self.level.max(0) as usize
+    }
+
+    /// The scalar is a mapping key, which is written on a single line
+    pub fn is_key(&self) -> bool {
+        self.key
+    }
+
+    /// The scalar is inside a flow collection, which block scalars can't be
+    pub fn is_flow(&self) -> bool {
+        self.flow
+    }
+}
+
+/// Chooses the style of every string the serializer writes, installed by
+/// [`SerializerOptions::scalar_styler`](super::SerializerOptions::scalar_styler).
+///
+/// A style which can't carry the string where it is written, e.g. plain style for `true` or a block scalar as
+/// a key, falls back to double quotes, so the string always reads back the same.
+pub trait ScalarStyler: Debug + Send + Sync {
+    fn style(&self, value: &str, context: ScalarContext) -> ScalarStyle;
+}
+
+/// The style the crate writes strings in: plain if possible, otherwise single-quoted, literal blocks for
+/// multi-line strings, and double quotes for control characters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultStyler {
+    quote_style: QuoteStyle,
+}
+
+impl DefaultStyler {
+    pub fn new(quote_style: QuoteStyle) -> Self {
+        DefaultStyler { quote_style }
+    }
+}
+
+impl ScalarStyler for DefaultStyler {
+    fn style(&self, value: &str, context: ScalarContext) -> ScalarStyle {
+        let multi_line = value.contains('\n');
+
+        if self.quote_style == QuoteStyle::ForceDouble || value.chars().any(needs_escape) {
+            // only escapes can represent control characters
+            ScalarStyle::DoubleQuoted
+        } else if multi_line && (context.key || context.flow || block_chomping(value).is_none()) {
+            // neither an implicit key nor a scalar in a flow collection can be a block scalar
+            ScalarStyle::DoubleQuoted
+        } else if multi_line {
+            ScalarStyle::Literal
+        } else if self.quote_style == QuoteStyle::Auto && is_plain_safe(value, context.flow) {
+            ScalarStyle::Plain
+        } else {
+            ScalarStyle::SingleQuoted
+        }
+    }
+}
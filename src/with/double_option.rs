@@ -0,0 +1,58 @@
+//! `Option<Option<T>>` fields which tell a missing key from an explicit null: a missing key is `None`,
+//! `key: ~` is `Some(None)` and `key: 5` is `Some(Some(5))`.
+//!
+//! Without this helper an explicit null is `None` for any `Option`, also for nested ones, as serde gives the
+//! deserializer no way to tell what the option contains. The field needs `default`, so that a missing key is
+//! `None`, and usually `skip_serializing_if`, so that `None` is written as a missing key.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Patch {
+//!     #[serde(with = "serde_yaml2::with::double_option", default, skip_serializing_if = "Option::is_none")]
+//!     replicas: Option<Option<u32>>,
+//! }
+//! ```
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub fn serialize<S, T>(value: &Option<Option<T>>, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer, T: Serialize {
+    match value {
+        Some(Some(value)) => serializer.serialize_some(value),
+        Some(None) | None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error> where D: Deserializer<'de>, T: Deserialize<'de> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use crate::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Patch {
+        #[serde(with = "crate::with::double_option", default, skip_serializing_if = "Option::is_none")]
+        replicas: Option<Option<u32>>,
+        #[serde(with = "crate::with::double_option", default, skip_serializing_if = "Option::is_none")]
+        ports: Option<Option<Vec<u16>>>,
+    }
+
+    #[test]
+    fn should_work() {
+        for (patch, yaml) in [
+            (Patch { replicas: None, ports: None }, "{}\n"),
+            (Patch { replicas: Some(None), ports: Some(Some(vec![80])) }, "replicas: ~\nports:\n  - 80\n"),
+            (Patch { replicas: Some(Some(3)), ports: Some(None) }, "replicas: 3\nports: ~\n"),
+        ] {
+            assert_eq!(yaml, to_string(&patch).unwrap());
+            assert_eq!(patch, from_str(yaml).unwrap());
+        }
+
+        assert_eq!(Patch { replicas: Some(None), ports: None }, from_str("replicas: null").unwrap());
+        assert!(from_str::<Patch>("replicas: [1]").is_err());
+    }
+}
@@ -0,0 +1,80 @@
+//! Collections which are always written in flow style, e.g. `ports: [80, 443]` or `labels: {app: web}`, while
+//! the rest of the document is written in block style.
+//!
+//! Nested collections are written in flow style as well. Deserializing reads the value the usual way, as flow
+//! and block style are read the same.
+//!
+//! ```
+//! use std::collections::BTreeMap;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Service {
+//!     #[serde(with = "serde_yaml2::with::flow")]
+//!     ports: Vec<u16>,
+//!     #[serde(with = "serde_yaml2::with::flow")]
+//!     labels: BTreeMap<String, String>,
+//! }
+//! ```
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Name of the newtype struct which asks [`YamlSerializer`](crate::ser::YamlSerializer) to write the value in
+/// flow style
+pub(crate) const FLOW_TOKEN: &str = "$serde_yaml2::private::Flow";
+
+pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer, T: Serialize + ?Sized {
+    serializer.serialize_newtype_struct(FLOW_TOKEN, value)
+}
+
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error> where D: Deserializer<'de>, T: Deserialize<'de> {
+    T::deserialize(deserializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde::{Deserialize, Serialize};
+    use crate::{from_str, to_string, to_string_with_options, SerializerOptions, Style};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Service {
+        name: String,
+        #[serde(with = "crate::with::flow")]
+        ports: Vec<u16>,
+        #[serde(with = "crate::with::flow")]
+        labels: BTreeMap<String, String>,
+        #[serde(with = "crate::with::flow")]
+        matrix: Vec<Vec<i32>>,
+        volumes: Vec<String>,
+        #[serde(with = "crate::with::flow")]
+        empty: Vec<u16>,
+    }
+
+    #[test]
+    fn should_work() {
+        let service = Service {
+            name: String::from("web"),
+            ports: vec![80, 443, 8080],
+            labels: BTreeMap::from([(String::from("a"), String::from("1")), (String::from("b"), String::from("x, y"))]),
+            matrix: vec![vec![1, 0], vec![0, 1]],
+            volumes: vec![String::from("data"), String::from("logs")],
+            empty: vec![],
+        };
+
+        let output = to_string(&service).unwrap();
+        assert_eq!("name: web\nports: [80, 443, 8080]\nlabels: {a: '1', b: 'x, y'}\nmatrix: [[1, 0], [0, 1]]\nvolumes:\n  - data\n  - logs\nempty: []\n", output);
+        assert_eq!(service, from_str(&output).unwrap());
+
+        // in a sequence and nested in block collections
+        let services = BTreeMap::from([(String::from("services"), vec![service])]);
+        let output = to_string(&services).unwrap();
+        assert!(output.contains("\n    ports: [80, 443, 8080]\n    labels: {a: '1', b: 'x, y'}\n"), "{output}");
+        assert_eq!(services, from_str(&output).unwrap());
+
+        let options = SerializerOptions::new().style(Style::Flow);
+        let output = to_string_with_options(&services, options).unwrap();
+        assert!(output.starts_with("{services: [{name: web, ports: [80, 443, 8080], "), "{output}");
+        assert_eq!(services, from_str(&output).unwrap());
+    }
+}
@@ -0,0 +1,141 @@
+//! Unsigned integers written in hexadecimal notation, e.g. `flags: 0x1F4`, which reads better than decimal for
+//! flag masks and registers. [`octal_int`](super::octal_int) writes octal notation, e.g. `mode: 0o644`.
+//!
+//! The scalar is plain, so it resolves as an integer of the YAML 1.2 core schema. Other serializers write the
+//! integer itself. Deserializing accepts `0x1F4`, `0o764` and `500` spellings, quoted or not, and fails for values
+//! out of the range of the field.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Register {
+//!     #[serde(with = "serde_yaml2::with::hex_int")]
+//!     flags: u32,
+//!     #[serde(with = "serde_yaml2::with::octal_int")]
+//!     mode: u16,
+//! }
+//! ```
+
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+use serde::de::{Error, Unexpected, Visitor};
+use serde::{Deserializer, Serializer};
+
+/// Name of the newtype struct which asks [`YamlSerializer`](crate::ser::YamlSerializer) to write the integer in
+/// hexadecimal notation
+pub(crate) const HEX_INT_TOKEN: &str = "$serde_yaml2::private::HexInt";
+
+/// Name of the newtype struct which asks [`YamlSerializer`](crate::ser::YamlSerializer) to write the integer in
+/// octal notation
+pub(crate) const OCTAL_INT_TOKEN: &str = "$serde_yaml2::private::OctalInt";
+
+pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer, T: Copy + Into<u64> {
+    serializer.serialize_newtype_struct(HEX_INT_TOKEN, &(*value).into())
+}
+
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error> where D: Deserializer<'de>, T: TryFrom<u64> {
+    deserializer.deserialize_any(IntVisitor { _marker: PhantomData })
+}
+
+/// Reads an unsigned integer from a number or from a string in hexadecimal, octal or decimal notation
+pub(super) struct IntVisitor<T> {
+    pub(super) _marker: PhantomData<T>,
+}
+
+impl<T: TryFrom<u64>> IntVisitor<T> {
+    fn convert<E: Error>(value: u64, spelling: &str) -> Result<T, E> {
+        T::try_from(value).map_err(|_| E::custom(format!("{spelling} is out of the range of {}", std::any::type_name::<T>())))
+    }
+}
+
+impl<'de, T: TryFrom<u64>> Visitor<'de> for IntVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("an unsigned integer, e.g. 0x1F4, 0o764 or 500")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> where E: Error {
+        match u64::try_from(v) {
+            Ok(unsigned) => self.visit_u64(unsigned),
+            Err(_) => Err(E::invalid_value(Unexpected::Signed(v), &self)),
+        }
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> where E: Error {
+        Self::convert(v, &v.to_string())
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: Error {
+        let (digits, radix) = if let Some(digits) = v.strip_prefix("0x") {
+            (digits, 16)
+        } else if let Some(digits) = v.strip_prefix("0o") {
+            (digits, 8)
+        } else {
+            (v, 10)
+        };
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+            return Err(E::invalid_value(Unexpected::Str(v), &self));
+        }
+
+        match u64::from_str_radix(digits, radix) {
+            Ok(value) => Self::convert(value, v),
+            Err(_) => Err(E::custom(format!("{v} is out of the range of {}", std::any::type_name::<T>()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use crate::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Register {
+        #[serde(with = "crate::with::hex_int")]
+        byte: u8,
+        #[serde(with = "crate::with::hex_int")]
+        flags: u32,
+        #[serde(with = "crate::with::hex_int")]
+        address: u64,
+        #[serde(with = "crate::with::octal_int")]
+        mode: u16,
+    }
+
+    #[test]
+    fn should_work() {
+        let register = Register { byte: 0xFF, flags: 0x1F4, address: u64::MAX, mode: 0o644 };
+        let output = to_string(&register).unwrap();
+        assert_eq!("byte: 0xFF\nflags: 0x1F4\naddress: 0xFFFFFFFFFFFFFFFF\nmode: 0o644\n", output);
+        assert_eq!(register, from_str(&output).unwrap());
+
+        let zero = Register { byte: 0, flags: 0, address: 0, mode: 0 };
+        let output = to_string(&zero).unwrap();
+        assert_eq!("byte: 0x0\nflags: 0x0\naddress: 0x0\nmode: 0o0\n", output);
+        assert_eq!(zero, from_str(&output).unwrap());
+
+        // any spelling reads back, quoted ones too
+        let parsed: Register = from_str("byte: 255\nflags: '0x1f4'\naddress: 0o17\nmode: 420").unwrap();
+        assert_eq!(Register { byte: 255, flags: 500, address: 15, mode: 0o644 }, parsed);
+
+        // other serializers write the integer
+        assert_eq!(r#"{"byte":255,"flags":500,"address":18446744073709551615,"mode":420}"#, serde_json::to_string(&register).unwrap());
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        let error = from_str::<Register>("byte: 0x100\nflags: 0\naddress: 0\nmode: 0").unwrap_err();
+        assert_eq!("256 is out of the range of u8 at position Line: 1, Column: 7, Index: 6", error.to_string());
+
+        let error = from_str::<Register>("byte: 0\nflags: 0\naddress: 0x10000000000000000\nmode: 0").unwrap_err();
+        assert_eq!("0x10000000000000000 is out of the range of u64 at position Line: 3, Column: 10, Index: 26", error.to_string());
+
+        let error = from_str::<Register>("byte: -1\nflags: 0\naddress: 0\nmode: 0").unwrap_err();
+        assert!(error.to_string().starts_with("invalid value: integer `-1`, expected an unsigned integer"), "{error}");
+
+        let error = from_str::<Register>("byte: 0\nflags: 0xZZ\naddress: 0\nmode: 0").unwrap_err();
+        assert!(error.to_string().starts_with("invalid value: string \"0xZZ\", expected an unsigned integer"), "{error}");
+    }
+}
@@ -0,0 +1,136 @@
+//! Durations written the way most configs do (`30s`, `1h30m`, `250ms`) as `std::time::Duration`.
+//!
+//! Parsing is done by the `humantime` crate, a bare integer is taken as seconds. Values are serialized in
+//! the shortest form without spaces, e.g. `1h30m`.
+//!
+//! ```
+//! use std::time::Duration;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Alert {
+//!     #[serde(with = "serde_yaml2::with::humantime_duration")]
+//!     interval: Duration,
+//!     #[serde(with = "serde_yaml2::with::humantime_duration::option", default)]
+//!     timeout: Option<Duration>,
+//! }
+//! ```
+
+use std::fmt::Formatter;
+use std::time::Duration;
+use serde::{Deserializer, Serializer};
+use serde::de::{Error, Visitor};
+
+pub fn serialize<S>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+    serializer.serialize_str(&format(value))
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error> where D: Deserializer<'de> {
+    deserializer.deserialize_any(DurationVisitor {})
+}
+
+pub mod option {
+    use std::fmt::Formatter;
+    use std::time::Duration;
+    use serde::de::Visitor;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        match value {
+            Some(value) => super::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error> where D: Deserializer<'de> {
+        deserializer.deserialize_option(OptionVisitor {})
+    }
+
+    struct OptionVisitor {}
+
+    impl<'de> Visitor<'de> for OptionVisitor {
+        type Value = Option<Duration>;
+
+        fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+            formatter.write_str("null or a duration")
+        }
+
+        fn visit_none<E>(self) -> Result<Self::Value, E> where E: serde::de::Error {
+            Ok(None)
+        }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E> where E: serde::de::Error {
+            Ok(None)
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error> where D: Deserializer<'de> {
+            super::deserialize(deserializer).map(Some)
+        }
+    }
+}
+
+fn format(value: &Duration) -> String {
+    humantime::format_duration(*value).to_string().replace(' ', "")
+}
+
+struct DurationVisitor {}
+
+impl Visitor<'_> for DurationVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a duration like 30s or 1h30m, or a number of seconds")
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> where E: Error {
+        Ok(Duration::from_secs(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> where E: Error {
+        u64::try_from(v).map(Duration::from_secs).map_err(|_| E::custom(format!("Invalid duration: {v}")))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: Error {
+        humantime::parse_duration(v.trim()).map_err(|e| E::custom(format!("Invalid duration {v}: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use serde::{Deserialize, Serialize};
+    use crate::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct TestStruct {
+        #[serde(with = "crate::with::humantime_duration")]
+        interval: Duration,
+        #[serde(with = "crate::with::humantime_duration::option", default)]
+        timeout: Option<Duration>,
+    }
+
+    #[test]
+    fn should_work() {
+        let result: TestStruct = from_str("interval: 1h30m\ntimeout: 250ms").unwrap();
+        assert_eq!(TestStruct { interval: Duration::from_secs(5400), timeout: Some(Duration::from_millis(250)) }, result);
+
+        let result: TestStruct = from_str("interval: 30\ntimeout: ~").unwrap();
+        assert_eq!(TestStruct { interval: Duration::from_secs(30), timeout: None }, result);
+
+        let result: TestStruct = from_str("interval: '5m 10s'").unwrap();
+        assert_eq!(Duration::from_secs(310), result.interval);
+
+        // round trip
+        let expected = TestStruct { interval: Duration::from_secs(5400), timeout: Some(Duration::from_millis(1500)) };
+        let output = to_string(&expected).unwrap();
+        assert!(output.contains("1h30m"), "{output}");
+        assert!(output.contains("1s500ms"), "{output}");
+        assert_eq!(expected, from_str(&output).unwrap());
+
+        let error = from_str::<TestStruct>("interval: 1h\ntimeout: 5 parsecs").unwrap_err().to_string();
+        assert!(error.starts_with("Invalid duration 5 parsecs: unknown time unit"), "{error}");
+        assert!(error.ends_with("at position Line: 2, Column: 10, Index: 22"), "{error}");
+
+        assert!(from_str::<TestStruct>("interval: -5").is_err());
+    }
+}
@@ -0,0 +1,25 @@
+//! Helpers for use with `#[serde(with = "...")]` field attributes
+
+#[cfg(feature = "chrono")]
+pub mod timestamp;
+
+#[cfg(feature = "humantime")]
+pub mod humantime_duration;
+
+pub mod double_option;
+
+pub mod flow;
+
+pub mod hex_int;
+
+pub mod octal_int;
+
+pub mod one_or_many;
+
+pub mod nested_yaml;
+
+pub mod pairs;
+
+pub mod quoted;
+
+pub mod singleton_map;
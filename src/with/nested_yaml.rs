@@ -0,0 +1,104 @@
+//! String fields whose contents are a YAML document themselves, e.g. a `config: |` block, deserialized into
+//! the type of the field.
+//!
+//! The value is serialized into a YAML document written as a block literal string. Errors of the nested
+//! document tell the position inside of it, followed by the position of the string.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Job {
+//!     #[serde(with = "serde_yaml2::with::nested_yaml")]
+//!     config: Config,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     retries: u32,
+//! }
+//! ```
+
+use serde::de::{DeserializeOwned, Error as _};
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer, T: Serialize {
+    let mut document = crate::to_string(value).map_err(S::Error::custom)?;
+
+    // a string with a line break is written as a block literal
+    if !document.ends_with('\n') {
+        document.push('\n');
+    }
+
+    serializer.serialize_str(&document)
+}
+
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error> where D: Deserializer<'de>, T: DeserializeOwned {
+    let document = String::deserialize(deserializer)?;
+
+    crate::from_str(&document).map_err(|e| {
+        // keep the position of the string, which is appended to errors without a position
+        let message = e.to_string().replace("position Line: ", "position in nested document Line: ");
+        D::Error::custom(format!("Error in nested YAML document: {message}"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use crate::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Job {
+        name: String,
+        #[serde(with = "crate::with::nested_yaml")]
+        config: Config,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Config {
+        image: String,
+        #[serde(with = "crate::with::nested_yaml")]
+        resources: Resources,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Resources {
+        cpus: u32,
+        labels: Vec<String>,
+    }
+
+    #[test]
+    fn should_work() {
+        let yaml = "
+name: build
+config: |
+  image: rust
+  resources: |
+    cpus: 4
+    labels: [fast, large]
+";
+
+        let expected = Job {
+            name: String::from("build"),
+            config: Config {
+                image: String::from("rust"),
+                resources: Resources { cpus: 4, labels: vec![String::from("fast"), String::from("large")] },
+            },
+        };
+
+        assert_eq!(expected, from_str::<Job>(yaml).unwrap());
+        assert_eq!(expected, from_str::<Job>(&to_string(&expected).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        let yaml = "name: build\nconfig: |\n  image: rust\n  resources: |\n    cpus: many\n    labels: []\n";
+        let error = from_str::<Job>(yaml).unwrap_err().to_string();
+
+        assert!(error.starts_with("Error in nested YAML document: Error in nested YAML document: "), "{error}");
+        assert!(error.contains("scalar many into u32 at position in nested document Line: 1, Column: 7"), "{error}");
+        assert!(error.ends_with("at position Line: 3, Column: 3, Index: 24"), "{error}");
+    }
+}
@@ -0,0 +1,13 @@
+//! Unsigned integers written in octal notation, e.g. `mode: 0o644`, see [`hex_int`](super::hex_int)
+
+use std::marker::PhantomData;
+use serde::{Deserializer, Serializer};
+use super::hex_int::{IntVisitor, OCTAL_INT_TOKEN};
+
+pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer, T: Copy + Into<u64> {
+    serializer.serialize_newtype_struct(OCTAL_INT_TOKEN, &(*value).into())
+}
+
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error> where D: Deserializer<'de>, T: TryFrom<u64> {
+    deserializer.deserialize_any(IntVisitor { _marker: PhantomData })
+}
@@ -0,0 +1,253 @@
+//! Fields which may be written either as a single value or as a sequence, e.g. `command: echo hi` and
+//! `command: [echo, hi]`, deserialized into `Vec<T>`.
+//!
+//! A single value becomes a one-element `Vec` and null an empty one. A `Vec` with exactly one element is
+//! serialized as a bare value, unless [`SerializerOptions::one_or_many_sequences`](crate::SerializerOptions::one_or_many_sequences)
+//! is set. Use [`sequence`] to always serialize a sequence for a single field.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Service {
+//!     #[serde(with = "serde_yaml2::with::one_or_many")]
+//!     command: Vec<String>,
+//!     #[serde(with = "serde_yaml2::with::one_or_many::option", default)]
+//!     ports: Option<Vec<u16>>,
+//! }
+//! ```
+
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{Error, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Name of the newtype struct of a single element, which [`YamlSerializer`](crate::ser::YamlSerializer) writes
+/// in a sequence if asked to
+pub(crate) const SINGLE_TOKEN: &str = "$serde_yaml2::private::OneOrMany";
+
+pub fn serialize<S, T>(value: &[T], serializer: S) -> Result<S::Ok, S::Error> where S: Serializer, T: Serialize {
+    match value {
+        [single] => serializer.serialize_newtype_struct(SINGLE_TOKEN, single),
+        _ => value.serialize(serializer),
+    }
+}
+
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error> where D: Deserializer<'de>, T: Deserialize<'de> {
+    deserializer.deserialize_any(OneOrManyVisitor { _marker: PhantomData })
+}
+
+/// Same as [`one_or_many`](self), but a `Vec` is always serialized as a sequence
+pub mod sequence {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S, T>(value: &[T], serializer: S) -> Result<S::Ok, S::Error> where S: Serializer, T: Serialize {
+        value.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error> where D: Deserializer<'de>, T: Deserialize<'de> {
+        super::deserialize(deserializer)
+    }
+}
+
+pub mod option {
+    use std::fmt::Formatter;
+    use std::marker::PhantomData;
+    use serde::de::Visitor;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S, T>(value: &Option<Vec<T>>, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer, T: Serialize {
+        match value {
+            Some(value) => super::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error> where D: Deserializer<'de>, T: Deserialize<'de> {
+        deserializer.deserialize_option(OptionVisitor { _marker: PhantomData })
+    }
+
+    struct OptionVisitor<T> {
+        _marker: PhantomData<T>,
+    }
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for OptionVisitor<T> {
+        type Value = Option<Vec<T>>;
+
+        fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+            formatter.write_str("null, a single value or a sequence")
+        }
+
+        fn visit_none<E>(self) -> Result<Self::Value, E> where E: serde::de::Error {
+            Ok(None)
+        }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E> where E: serde::de::Error {
+            Ok(None)
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error> where D: Deserializer<'de> {
+            super::deserialize(deserializer).map(Some)
+        }
+    }
+}
+
+struct OneOrManyVisitor<T> {
+    _marker: PhantomData<T>,
+}
+
+impl<T> OneOrManyVisitor<T> {
+    /// Deserializes the single value from a resolved scalar. If the scalar was resolved into a number or a
+    /// boolean but `T` expects a string, its text is used instead, e.g. `command: 42` for `Vec<String>`.
+    fn single<'de, E, V>(value: V) -> Result<Vec<T>, E>
+    where
+        E: Error,
+        T: Deserialize<'de>,
+        V: IntoDeserializer<'de, E> + ToString,
+    {
+        let text = value.to_string();
+
+        match T::deserialize(value.into_deserializer()) {
+            Ok(single) => Ok(vec![single]),
+            Err(error) => T::deserialize(text.into_deserializer()).map(|single| vec![single]).map_err(|_: E| error),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for OneOrManyVisitor<T> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a single value or a sequence")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> where E: Error {
+        Self::single(v)
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> where E: Error {
+        Self::single(v)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> where E: Error {
+        Self::single(v)
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> where E: Error {
+        Self::single(v)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: Error {
+        T::deserialize(v.into_deserializer()).map(|single| vec![single])
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E> where E: Error {
+        T::deserialize(v.into_deserializer()).map(|single| vec![single])
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E> where E: Error {
+        T::deserialize(v.into_deserializer()).map(|single| vec![single])
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E> where E: Error {
+        Ok(vec![])
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> where E: Error {
+        Ok(vec![])
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error> where A: SeqAccess<'de> {
+        Vec::deserialize(SeqAccessDeserializer::new(seq))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error> where A: MapAccess<'de> {
+        T::deserialize(MapAccessDeserializer::new(map)).map(|single| vec![single])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use yaml_rust2::YamlLoader;
+    use crate::{from_str, to_string, to_string_with_options, to_yaml_with, SerializerOptions};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Mount {
+        source: String,
+        target: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Service {
+        #[serde(with = "crate::with::one_or_many")]
+        command: Vec<String>,
+        #[serde(with = "crate::with::one_or_many::option", default)]
+        ports: Option<Vec<u16>>,
+        #[serde(with = "crate::with::one_or_many::sequence", default)]
+        mounts: Vec<Mount>,
+    }
+
+    #[test]
+    fn should_work() {
+        let service: Service = from_str("command: echo hi\nports: 8080\nmounts: {source: a, target: b}").unwrap();
+        assert_eq!(Service {
+            command: vec![String::from("echo hi")],
+            ports: Some(vec![8080]),
+            mounts: vec![Mount { source: String::from("a"), target: String::from("b") }],
+        }, service);
+
+        let service: Service = from_str("command: [echo, hi]\nports: [80, 443]").unwrap();
+        assert_eq!(vec![String::from("echo"), String::from("hi")], service.command);
+        assert_eq!(Some(vec![80, 443]), service.ports);
+        assert!(service.mounts.is_empty());
+
+        // scalars resolved as numbers or booleans are accepted as strings
+        let service: Service = from_str("command: 42\nports: ~").unwrap();
+        assert_eq!(vec![String::from("42")], service.command);
+        assert_eq!(None, service.ports);
+
+        let service: Service = from_str("command:\nmounts:\n  - source: a\n    target: b\n  - source: c\n    target: d").unwrap();
+        assert!(service.command.is_empty());
+        assert_eq!(2, service.mounts.len());
+
+        assert!(from_str::<Service>("ports: [http]\ncommand: x").is_err());
+    }
+
+    #[test]
+    fn serialize() {
+        let service = Service {
+            command: vec![String::from("echo")],
+            ports: Some(vec![80, 443]),
+            mounts: vec![Mount { source: String::from("a"), target: String::from("b") }],
+        };
+
+        #[derive(Deserialize)]
+        struct Bare {
+            command: String,
+            mounts: Vec<Mount>,
+        }
+
+        let yaml = to_string(&service).unwrap();
+        assert_eq!(service, from_str(&yaml).unwrap());
+
+        // single command is a bare scalar, while a single mount stays in a sequence
+        let bare: Bare = from_str(&yaml).unwrap();
+        assert_eq!("echo", bare.command);
+        assert_eq!(1, bare.mounts.len());
+    }
+
+    #[test]
+    fn sequences_option() {
+        let service = Service { command: vec![String::from("echo")], ports: Some(vec![80]), mounts: vec![] };
+
+        assert_eq!("command: echo\nports: 80\nmounts: []\n", to_string(&service).unwrap());
+
+        let options = SerializerOptions::new().one_or_many_sequences(true);
+        let yaml = to_string_with_options(&service, options.clone()).unwrap();
+        assert_eq!("command:\n  - echo\nports:\n  - 80\nmounts: []\n", yaml);
+        assert_eq!(service, from_str(&yaml).unwrap());
+        assert_eq!(YamlLoader::load_from_str(&yaml).unwrap(), vec![to_yaml_with(&service, options).unwrap()]);
+    }
+}
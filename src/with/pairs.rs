@@ -0,0 +1,107 @@
+//! Mappings as an ordered list of entries, keeping keys which occur more than once, e.g.
+//! `server: a`, `server: b` become `vec![("server", "a"), ("server", "b")]`.
+//!
+//! The entries are serialized back as a mapping in the same order, repeated keys included. Null is an empty
+//! list.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Backend {
+//!     #[serde(with = "serde_yaml2::with::pairs")]
+//!     options: Vec<(String, String)>,
+//! }
+//! ```
+
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+use serde::de::{Error, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub fn serialize<S, K, V>(value: &[(K, V)], serializer: S) -> Result<S::Ok, S::Error> where S: Serializer, K: Serialize, V: Serialize {
+    let mut map = serializer.serialize_map(Some(value.len()))?;
+
+    for (key, value) in value {
+        map.serialize_entry(key, value)?;
+    }
+
+    map.end()
+}
+
+pub fn deserialize<'de, D, K, V>(deserializer: D) -> Result<Vec<(K, V)>, D::Error> where D: Deserializer<'de>, K: Deserialize<'de>, V: Deserialize<'de> {
+    deserializer.deserialize_any(PairsVisitor { _marker: PhantomData })
+}
+
+struct PairsVisitor<K, V> {
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Visitor<'de> for PairsVisitor<K, V> {
+    type Value = Vec<(K, V)>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a mapping")
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E> where E: Error {
+        Ok(vec![])
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> where E: Error {
+        Ok(vec![])
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error> where A: MapAccess<'de> {
+        let mut pairs = Vec::with_capacity(map.size_hint().unwrap_or(0));
+
+        while let Some(entry) = map.next_entry()? {
+            pairs.push(entry);
+        }
+
+        Ok(pairs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use crate::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Backend {
+        name: String,
+        #[serde(with = "crate::with::pairs")]
+        options: Vec<(String, u32)>,
+    }
+
+    #[test]
+    fn should_work() {
+        let yaml = "
+name: web
+options:
+  server: 1
+  timeout: 30
+  server: 2
+  server: 3
+";
+        let expected = Backend {
+            name: String::from("web"),
+            options: vec![
+                (String::from("server"), 1),
+                (String::from("timeout"), 30),
+                (String::from("server"), 2),
+                (String::from("server"), 3),
+            ],
+        };
+
+        assert_eq!(expected, from_str(yaml).unwrap());
+        assert_eq!(expected, from_str(&to_string(&expected).unwrap()).unwrap());
+
+        let result: Backend = from_str("name: web\noptions:").unwrap();
+        assert!(result.options.is_empty());
+
+        assert!(from_str::<Backend>("name: web\noptions: [1, 2]").is_err());
+    }
+}
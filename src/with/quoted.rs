@@ -0,0 +1,114 @@
+//! Strings which are always written quoted, e.g. `version: '1.10'` or `country: 'NO'`, so that other consumers
+//! don't read them as a number or a boolean whatever schema they resolve scalars with.
+//!
+//! Strings are single-quoted, or double-quoted if they need escapes or the options force double quotes.
+//! Deserializing takes the text of any scalar as it is written, e.g. `1.10` stays `1.10`, into an owned string
+//! type like `String` or `Cow<str>`.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Release {
+//!     #[serde(with = "serde_yaml2::with::quoted")]
+//!     version: String,
+//! }
+//! ```
+
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+use serde::de::{Error, Visitor};
+use serde::{Deserializer, Serializer};
+
+/// Name of the newtype struct which asks [`YamlSerializer`](crate::ser::YamlSerializer) to quote the string
+pub(crate) const QUOTED_TOKEN: &str = "$serde_yaml2::private::Quoted";
+
+pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer, T: AsRef<str> + ?Sized {
+    serializer.serialize_newtype_struct(QUOTED_TOKEN, value.as_ref())
+}
+
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error> where D: Deserializer<'de>, T: From<String> {
+    deserializer.deserialize_str(TextVisitor { _marker: PhantomData })
+}
+
+struct TextVisitor<T> {
+    _marker: PhantomData<T>,
+}
+
+impl<'de, T: From<String>> Visitor<'de> for TextVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a scalar")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> where E: Error {
+        Ok(T::from(v.to_string()))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> where E: Error {
+        Ok(T::from(v.to_string()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> where E: Error {
+        Ok(T::from(v.to_string()))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> where E: Error {
+        Ok(T::from(v.to_string()))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: Error {
+        Ok(T::from(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E> where E: Error {
+        Ok(T::from(v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use serde::{Deserialize, Serialize};
+    use crate::{from_str, to_string, to_string_with_options, QuoteStyle, SerializerOptions, Style};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Release<'a> {
+        name: String,
+        #[serde(with = "crate::with::quoted")]
+        version: String,
+        #[serde(with = "crate::with::quoted")]
+        country: Cow<'a, str>,
+        #[serde(with = "crate::with::quoted")]
+        notes: String,
+        code: String,
+    }
+
+    #[test]
+    fn should_work() {
+        let release = Release {
+            name: String::from("web"),
+            version: String::from("1.10"),
+            country: Cow::Borrowed("no"),
+            notes: String::from("tab\there"),
+            code: String::from("NO"),
+        };
+
+        let output = to_string(&release).unwrap();
+        assert_eq!("name: web\nversion: '1.10'\ncountry: 'no'\nnotes: \"tab\\there\"\ncode: 'NO'\n", output);
+        assert_eq!(release, from_str(&output).unwrap());
+
+        let options = SerializerOptions::new().style(Style::Flow);
+        assert_eq!("{name: web, version: '1.10', country: 'no', notes: \"tab\\there\", code: 'NO'}\n", to_string_with_options(&release, options).unwrap());
+
+        let options = SerializerOptions::new().quote_style(QuoteStyle::ForceDouble);
+        let output = to_string_with_options(&release, options).unwrap();
+        assert!(output.starts_with("\"name\": \"web\"\n\"version\": \"1.10\"\n"), "{output}");
+
+        // the text of a scalar is taken as it is written
+        let parsed: Release = from_str("name: web\nversion: 1.10\ncountry: no\nnotes: multi\ncode: x").unwrap();
+        assert_eq!("1.10", parsed.version);
+        assert_eq!("no", parsed.country);
+    }
+}
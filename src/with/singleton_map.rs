@@ -0,0 +1,357 @@
+//! Enums written as a mapping of the variant name to its value, e.g. `Resize: {width: 10}`, whatever
+//! [`EnumRepr`](crate::EnumRepr) the options say. Unit variants are written as the name alone, e.g. `Stop`.
+//!
+//! Only the enum of the field itself is affected, not enums nested in its variants. `Option` and newtype structs
+//! around the enum are looked through. Deserializing accepts a single-key mapping, the variant name alone for
+//! unit variants, and any other form the deserializer supports.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! enum Action {
+//!     Stop,
+//!     Resize { width: u32, height: u32 },
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Step {
+//!     #[serde(with = "serde_yaml2::with::singleton_map")]
+//!     action: Action,
+//! }
+//! ```
+
+use serde::ser::{Error, Impossible, SerializeMap, SerializeStruct, SerializeStructVariant, SerializeTuple, SerializeTupleVariant};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer, T: Serialize + ?Sized {
+    value.serialize(SingletonMap { delegate: serializer, value })
+}
+
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error> where D: Deserializer<'de>, T: Deserialize<'de> {
+    T::deserialize(deserializer)
+}
+
+/// Serializes the value through [`serialize`]
+struct Adapted<'a, T: ?Sized>(&'a T);
+
+impl<T: Serialize + ?Sized> Serialize for Adapted<'_, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serialize(self.0, serializer)
+    }
+}
+
+/// Passes everything on to the delegate but variants, which become mappings. The fields of a tuple or struct
+/// variant can't be written as the value of a mapping one by one, so the value is serialized once more as
+/// the mapping value by [`VariantFields`], and the fields given to this serializer are ignored.
+struct SingletonMap<'a, S, T: ?Sized> {
+    delegate: S,
+    value: &'a T,
+}
+
+macro_rules! forward {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method(self, $($arg: $ty),*) -> Result<Self::Ok, Self::Error> {
+                self.delegate.$method($($arg),*)
+            }
+        )*
+    }
+}
+
+impl<'a, S: Serializer, T: Serialize + ?Sized> Serializer for SingletonMap<'a, S, T> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = S::SerializeSeq;
+    type SerializeTuple = S::SerializeTuple;
+    type SerializeTupleStruct = S::SerializeTupleStruct;
+    type SerializeTupleVariant = Written<S::Ok, S::Error>;
+    type SerializeMap = S::SerializeMap;
+    type SerializeStruct = S::SerializeStruct;
+    type SerializeStructVariant = Written<S::Ok, S::Error>;
+
+    forward! {
+        serialize_bool(v: bool);
+        serialize_i8(v: i8);
+        serialize_i16(v: i16);
+        serialize_i32(v: i32);
+        serialize_i64(v: i64);
+        serialize_i128(v: i128);
+        serialize_u8(v: u8);
+        serialize_u16(v: u16);
+        serialize_u32(v: u32);
+        serialize_u64(v: u64);
+        serialize_u128(v: u128);
+        serialize_f32(v: f32);
+        serialize_f64(v: f64);
+        serialize_char(v: char);
+        serialize_str(v: &str);
+        serialize_bytes(v: &[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(name: &'static str);
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.delegate.serialize_str(variant)
+    }
+
+    fn serialize_some<U: Serialize + ?Sized>(self, value: &U) -> Result<Self::Ok, Self::Error> {
+        self.delegate.serialize_some(&Adapted(value))
+    }
+
+    fn serialize_newtype_struct<U: Serialize + ?Sized>(self, name: &'static str, value: &U) -> Result<Self::Ok, Self::Error> {
+        self.delegate.serialize_newtype_struct(name, &Adapted(value))
+    }
+
+    fn serialize_newtype_variant<U: Serialize + ?Sized>(self, _name: &'static str, _variant_index: u32, variant: &'static str, value: &U) -> Result<Self::Ok, Self::Error> {
+        let mut map = self.delegate.serialize_map(Some(1))?;
+        map.serialize_entry(variant, value)?;
+        map.end()
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        self.delegate.serialize_seq(len)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.delegate.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.delegate.serialize_tuple_struct(name, len)
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        let mut map = self.delegate.serialize_map(Some(1))?;
+        map.serialize_entry(variant, &VariantFields(self.value))?;
+        Ok(Written { result: map.end() })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.delegate.serialize_map(len)
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        self.delegate.serialize_struct(name, len)
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant, Self::Error> {
+        let mut map = self.delegate.serialize_map(Some(1))?;
+        map.serialize_entry(variant, &VariantFields(self.value))?;
+        Ok(Written { result: map.end() })
+    }
+}
+
+/// A variant written already, whose fields are ignored
+struct Written<Ok, E> {
+    result: Result<Ok, E>,
+}
+
+impl<Ok, E: Error> SerializeTupleVariant for Written<Ok, E> {
+    type Ok = Ok;
+    type Error = E;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.result
+    }
+}
+
+impl<Ok, E: Error> SerializeStructVariant for Written<Ok, E> {
+    type Ok = Ok;
+    type Error = E;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, _key: &'static str, _value: &T) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.result
+    }
+}
+
+/// Serializes the fields of a tuple or struct variant as a sequence or a mapping
+struct VariantFields<'a, T: ?Sized>(&'a T);
+
+impl<T: Serialize + ?Sized> Serialize for VariantFields<'_, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        self.0.serialize(FieldsSerializer { delegate: serializer })
+    }
+}
+
+/// Serializes a tuple or struct variant without its name, the variant the value was the first time
+struct FieldsSerializer<S> {
+    delegate: S,
+}
+
+macro_rules! not_variant {
+    ($($method:ident($($ty:ty),*);)*) => {
+        $(
+            fn $method(self, $(_: $ty),*) -> Result<Self::Ok, Self::Error> {
+                Err(S::Error::custom("Variant serialized differently the second time"))
+            }
+        )*
+    }
+}
+
+impl<S: Serializer> Serializer for FieldsSerializer<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Impossible<S::Ok, S::Error>;
+    type SerializeTuple = Impossible<S::Ok, S::Error>;
+    type SerializeTupleStruct = Impossible<S::Ok, S::Error>;
+    type SerializeTupleVariant = TupleFields<S::SerializeTuple>;
+    type SerializeMap = Impossible<S::Ok, S::Error>;
+    type SerializeStruct = Impossible<S::Ok, S::Error>;
+    type SerializeStructVariant = StructFields<S::SerializeStruct>;
+
+    not_variant! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+        serialize_bytes(&[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+        serialize_unit_variant(&'static str, u32, &'static str);
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<Self::Ok, Self::Error> {
+        Err(S::Error::custom("Variant serialized differently the second time"))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, _value: &T) -> Result<Self::Ok, Self::Error> {
+        Err(S::Error::custom("Variant serialized differently the second time"))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<Self::Ok, Self::Error> {
+        Err(S::Error::custom("Variant serialized differently the second time"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(S::Error::custom("Variant serialized differently the second time"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(S::Error::custom("Variant serialized differently the second time"))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(S::Error::custom("Variant serialized differently the second time"))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, len: usize) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(TupleFields(self.delegate.serialize_tuple(len)?))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(S::Error::custom("Variant serialized differently the second time"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(S::Error::custom("Variant serialized differently the second time"))
+    }
+
+    fn serialize_struct_variant(self, name: &'static str, _variant_index: u32, _variant: &'static str, len: usize) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Ok(StructFields(self.delegate.serialize_struct(name, len)?))
+    }
+}
+
+struct TupleFields<S>(S);
+
+impl<S: SerializeTuple> SerializeTupleVariant for TupleFields<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.0.serialize_element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.0.end()
+    }
+}
+
+struct StructFields<S>(S);
+
+impl<S: SerializeStruct> SerializeStructVariant for StructFields<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> {
+        self.0.serialize_field(key, value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.0.skip_field(key)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.0.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use crate::{from_str, to_string, to_string_with_options, EnumRepr, SerializerOptions, Style};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Action {
+        Stop,
+        Scale(u32),
+        Move(i32, i32),
+        Resize { width: u32, height: u32 },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Step {
+        name: String,
+        #[serde(with = "crate::with::singleton_map")]
+        action: Action,
+        #[serde(with = "crate::with::singleton_map", default)]
+        fallback: Option<Action>,
+    }
+
+    #[test]
+    fn should_work() {
+        let options = SerializerOptions::new().enum_representation(EnumRepr::Tag);
+        let cases = [
+            (Action::Stop, None, "name: a\naction: Stop\nfallback: ~\n"),
+            (Action::Scale(3), Some(Action::Stop), "name: a\naction:\n  Scale: 3\nfallback: Stop\n"),
+            (Action::Move(1, -2), None, "name: a\naction:\n  Move:\n    - 1\n    - -2\nfallback: ~\n"),
+            (Action::Resize { width: 10, height: 20 }, Some(Action::Move(0, 1)), "name: a\naction:\n  Resize:\n    width: 10\n    height: 20\nfallback:\n  Move:\n    - 0\n    - 1\n"),
+        ];
+
+        for (action, fallback, expected) in cases {
+            let value = Step { name: String::from("a"), action, fallback };
+
+            // the mapping form whatever the enum representation
+            let output = to_string_with_options(&value, options.clone()).unwrap();
+            assert_eq!(expected, output);
+            assert_eq!(value, from_str::<Step>(&output).unwrap(), "{output}");
+            assert_eq!(output, to_string(&value).unwrap());
+        }
+
+        let value = Step { name: String::from("a"), action: Action::Resize { width: 1, height: 2 }, fallback: Some(Action::Scale(2)) };
+        let output = to_string_with_options(&value, options.style(Style::Flow)).unwrap();
+        assert_eq!("{name: a, action: {Resize: {width: 1, height: 2}}, fallback: {Scale: 2}}\n", output);
+        assert_eq!(value, from_str::<Step>(&output).unwrap(), "{output}");
+    }
+}
@@ -0,0 +1,133 @@
+//! YAML timestamps (`2001-12-14t21:59:43.10-05:00`, `2002-12-14`) as `chrono::DateTime<Utc>`.
+//!
+//! Accepts every form of the YAML timestamp type: date only (midnight UTC is assumed), date and
+//! time separated by `T`, `t` or spaces, optional fraction and an optional `Z` or numeric offset.
+//! Values are serialized as RFC 3339 strings.
+//!
+//! ```
+//! use chrono::{DateTime, Utc};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Build {
+//!     #[serde(with = "serde_yaml2::with::timestamp")]
+//!     finished: DateTime<Utc>,
+//!     #[serde(with = "serde_yaml2::with::timestamp::option", default)]
+//!     published: Option<DateTime<Utc>>,
+//! }
+//! ```
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc};
+use serde::{Deserialize, Deserializer, Serializer};
+use serde::de::Error;
+
+pub fn serialize<S>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+    serializer.serialize_str(&value.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error> where D: Deserializer<'de> {
+    let value = String::deserialize(deserializer)?;
+    parse(&value).ok_or_else(|| D::Error::custom(format!("Invalid timestamp: {value}")))
+}
+
+pub mod option {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    pub fn serialize<S>(value: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        match value {
+            Some(value) => super::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> where D: Deserializer<'de> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(value) => super::parse(&value).map(Some).ok_or_else(|| D::Error::custom(format!("Invalid timestamp: {value}"))),
+            None => Ok(None),
+        }
+    }
+}
+
+fn parse(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN)));
+    }
+
+    let split = value.find(['T', 't', ' '])?;
+    let date = NaiveDate::parse_from_str(&value[..split], "%Y-%m-%d").ok()?;
+    let rest = value[split + 1..].trim_start();
+
+    // time is followed by an optional offset: Z, +hh, +hh:mm or +hhmm
+    let offset_start = rest.find(['Z', 'z', '+', '-']).unwrap_or(rest.len());
+    let time = NaiveTime::parse_from_str(rest[..offset_start].trim_end(), "%H:%M:%S%.f").ok()?;
+    let date_time = NaiveDateTime::new(date, time);
+
+    let offset = match &rest[offset_start..] {
+        "" | "Z" | "z" => return Some(Utc.from_utc_datetime(&date_time)),
+        offset => parse_offset(offset)?,
+    };
+
+    offset.from_local_datetime(&date_time).single().map(|v| v.with_timezone(&Utc))
+}
+
+fn parse_offset(value: &str) -> Option<FixedOffset> {
+    let sign = if value.starts_with('-') { -1 } else { 1 };
+    let value = &value[1..];
+
+    let (hours, minutes) = match value.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if value.len() > 2 => value.split_at(2),
+        None => (value, "0"),
+    };
+
+    let seconds = hours.parse::<i32>().ok()? * 3600 + minutes.parse::<i32>().ok()? * 60;
+    FixedOffset::east_opt(sign * seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+    use serde::{Deserialize, Serialize};
+    use crate::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct TestStruct {
+        #[serde(with = "crate::with::timestamp")]
+        created: DateTime<Utc>,
+        #[serde(with = "crate::with::timestamp::option", default)]
+        deleted: Option<DateTime<Utc>>,
+        day: NaiveDate,
+    }
+
+    #[test]
+    fn should_work() {
+        let expected = TestStruct {
+            created: Utc.with_ymd_and_hms(2001, 12, 15, 2, 59, 43).unwrap() + chrono::Duration::milliseconds(100),
+            deleted: None,
+            day: NaiveDate::from_ymd_opt(2002, 12, 14).unwrap(),
+        };
+
+        // offsets
+        assert_eq!(expected, from_str("created: 2001-12-14t21:59:43.10-05:00\ndeleted: ~\nday: 2002-12-14").unwrap());
+        assert_eq!(expected, from_str("created: 2001-12-14 21:59:43.10 -5\nday: 2002-12-14").unwrap());
+        assert_eq!(expected, from_str("created: '2001-12-15T02:59:43.1Z'\nday: '2002-12-14'").unwrap());
+        assert_eq!(expected, from_str("created: !!timestamp 2001-12-15 2:59:43.10\nday: !!timestamp 2002-12-14").unwrap());
+
+        // date only
+        let result: TestStruct = from_str("created: 2002-12-14\ndeleted: 2002-12-15\nday: 2002-12-14").unwrap();
+        assert_eq!(Utc.with_ymd_and_hms(2002, 12, 14, 0, 0, 0).unwrap(), result.created);
+        assert_eq!(Some(Utc.with_ymd_and_hms(2002, 12, 15, 0, 0, 0).unwrap()), result.deleted);
+
+        // round trip
+        let output = to_string(&expected).unwrap();
+        assert!(output.contains("created: 2001-12-15T02:59:43.100Z\n"), "{output}");
+        assert!(output.contains("day: 2002-12-14\n"), "{output}");
+        assert_eq!(expected, from_str(&output).unwrap());
+
+        assert!(from_str::<TestStruct>("created: 2002-13-14\nday: 2002-12-14").is_err());
+    }
+}
@@ -0,0 +1,348 @@
+use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+use serde::de::{DeserializeSeed, EnumAccess, Error as _, IntoDeserializer, Unexpected, VariantAccess, Visitor};
+use serde::de::DeserializeOwned;
+use serde::{forward_to_deserialize_any, Deserializer};
+use yaml_rust2::Yaml;
+use crate::ser::binary;
+use super::{YamlNodeWrapper, YamlRef};
+
+/// Deserializes a value from the node, like `serde_json::from_value` does, e.g. from one made by
+/// [`to_value`](crate::to_value)
+pub fn from_value<T: DeserializeOwned>(value: YamlNodeWrapper) -> Result<T, Error> {
+    T::deserialize(&value)
+}
+
+impl<'de> Deserializer<'de> for &'de YamlNodeWrapper {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.as_yaml_ref().deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.as_yaml_ref().deserialize_option(visitor)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.as_yaml_ref().deserialize_str(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.as_yaml_ref().deserialize_string(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.as_yaml_ref().deserialize_bytes(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.as_yaml_ref().deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.as_yaml_ref().deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_enum<V>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.as_yaml_ref().deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Walks the node in memory, as the deserializer of the document it was read from would have read it
+impl<'de> Deserializer<'de> for YamlRef<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        match self.0 {
+            Yaml::Real(v) => {
+                // an integer too large for Yaml::Integer, see `to_value`
+                if let Ok(parsed) = v.parse::<u64>() {
+                    return visitor.visit_u64(parsed);
+                }
+                if let Ok(parsed) = v.parse::<i128>() {
+                    return visitor.visit_i128(parsed);
+                }
+                if let Ok(parsed) = v.parse::<u128>() {
+                    return visitor.visit_u128(parsed);
+                }
+
+                let Some(parsed) = self.0.as_f64() else {
+                    return Err(Error::invalid_value(Unexpected::Str(v), &"a float"));
+                };
+                visitor.visit_f64(parsed)
+            },
+            Yaml::Integer(v) => visitor.visit_i64(*v),
+            Yaml::String(v) => visitor.visit_borrowed_str(v),
+            Yaml::Boolean(v) => visitor.visit_bool(*v),
+            Yaml::Array(v) => {
+                let mut seq = SeqDeserializer::new(v.iter().map(YamlRef));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            },
+            Yaml::Hash(v) => {
+                let mut map = MapDeserializer::new(v.iter().map(|(k, v)| (YamlRef(k), YamlRef(v))));
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            },
+            Yaml::Null => visitor.visit_unit(),
+            Yaml::Alias(id) => Err(Error::custom(format!("Can not deserialize unresolved alias with id {id}"))),
+            Yaml::BadValue => Err(Error::custom("Can not deserialize a bad value, which stands for a missing node")),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        if self.is_null() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        // a plain scalar reads as a string from the text as well, e.g. `version: 1`
+        match self.0 {
+            Yaml::Real(v) => visitor.visit_borrowed_str(v),
+            Yaml::Integer(v) => visitor.visit_string(v.to_string()),
+            Yaml::Boolean(v) => visitor.visit_borrowed_str(if *v { "true" } else { "false" }),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        // `to_value` keeps bytes as the base64 string of their `!!binary` scalar
+        match self.0 {
+            Yaml::String(v) => match binary::decode(v) {
+                Some(bytes) => visitor.visit_byte_buf(bytes),
+                None => Err(Error::invalid_value(Unexpected::Str(v), &"base64 of a !!binary scalar")),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        match self.0 {
+            // bare variant name is a unit variant
+            Yaml::String(v) => visitor.visit_enum(v.as_str().into_deserializer()),
+            Yaml::Hash(hash) if hash.len() == 1 => {
+                let (variant, value) = hash.front().expect("the mapping has an entry");
+                visitor.visit_enum(VariantRef { variant: YamlRef(variant), value: YamlRef(value) })
+            },
+            _ => Err(Error::invalid_type(unexpected(self.0), &"a variant name or a mapping of a variant name to its value")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for YamlRef<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+/// The single entry of a mapping of a variant name to its value, e.g. `Variant: 1`
+struct VariantRef<'de> {
+    variant: YamlRef<'de>,
+    value: YamlRef<'de>,
+}
+
+impl<'de> EnumAccess<'de> for VariantRef<'de> {
+    type Error = Error;
+    type Variant = YamlRef<'de>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error> where V: DeserializeSeed<'de> {
+        Ok((seed.deserialize(self.variant)?, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for YamlRef<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        if self.is_null() {
+            Ok(())
+        } else {
+            Err(Error::invalid_type(unexpected(self.0), &"a unit variant"))
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error> where T: DeserializeSeed<'de> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.deserialize_any(visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        self.deserialize_any(visitor)
+    }
+}
+
+fn unexpected(node: &Yaml) -> Unexpected<'_> {
+    match node {
+        Yaml::Real(v) => Unexpected::Other(v),
+        Yaml::Integer(v) => Unexpected::Signed(*v),
+        Yaml::String(v) => Unexpected::Str(v),
+        Yaml::Boolean(v) => Unexpected::Bool(*v),
+        Yaml::Array(_) => Unexpected::Seq,
+        Yaml::Hash(_) => Unexpected::Map,
+        Yaml::Null => Unexpected::Unit,
+        Yaml::Alias(_) | Yaml::BadValue => Unexpected::Other("an unresolved node"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde::{Deserialize, Serialize};
+    use crate::{from_str, to_value};
+    use super::{from_value, YamlNodeWrapper};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Rect { width: u32, height: u32 },
+        Line(i32, i32),
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Item<'a> {
+        name: &'a str,
+        count: Option<u8>,
+        shapes: Vec<Shape>,
+        labels: BTreeMap<String, String>,
+        extra: YamlNodeWrapper,
+    }
+
+    #[test]
+    fn should_work() {
+        let node: YamlNodeWrapper = from_str(concat!(
+            "name: box\n",
+            "count: ~\n",
+            "shapes: [Empty, {Circle: 1.5}, {Rect: {width: 2, height: 3}}, {Line: [1, -1]}, {Circle: 2}]\n",
+            "labels: {a: b}\n",
+            "extra: [1, {x: y}]\n",
+        )).unwrap();
+
+        let item: Item = node.deserialize_into().unwrap();
+        assert_eq!("box", item.name);
+        assert_eq!(None, item.count);
+        assert_eq!(vec![Shape::Empty, Shape::Circle(1.5), Shape::Rect { width: 2, height: 3 }, Shape::Line(1, -1), Shape::Circle(2.0)], item.shapes);
+        assert_eq!(BTreeMap::from([(String::from("a"), String::from("b"))]), item.labels);
+        assert_eq!(from_str::<YamlNodeWrapper>("[1, {x: y}]").unwrap(), item.extra);
+
+        let count = node.get_key("shapes").unwrap().get_index(3).unwrap();
+        assert_eq!(Shape::Line(1, -1), Shape::deserialize(count).unwrap());
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        replicas: u32,
+        offset: i64,
+        big: u64,
+        ratio: f64,
+        enabled: bool,
+        owner: Option<String>,
+        limits: BTreeMap<String, BTreeMap<String, f32>>,
+        stages: Vec<Vec<String>>,
+        mode: Mode,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Mode {
+        Off,
+        Rolling { max_surge: u8 },
+    }
+
+    #[test]
+    fn values() {
+        let config = Config {
+            name: String::from("web"),
+            replicas: 3,
+            offset: -7,
+            big: u64::MAX,
+            ratio: 0.25,
+            enabled: true,
+            owner: None,
+            limits: BTreeMap::from([(String::from("cpu"), BTreeMap::from([(String::from("max"), 1.5)]))]),
+            stages: vec![vec![String::from("build"), String::from("test")], vec![]],
+            mode: Mode::Rolling { max_surge: 2 },
+        };
+
+        let value = to_value(&config).unwrap();
+        assert_eq!(Some("web"), value.get_key("name").and_then(|name| name.as_str()));
+        assert_eq!(Some(1.5), value.get_key("limits").and_then(|limits| limits.get_key("cpu")).and_then(|cpu| cpu.get_key("max")).and_then(|max| max.as_f64()));
+        assert!(value.get_key("owner").unwrap().is_null());
+        assert_eq!(config, from_value(value).unwrap());
+
+        let value: YamlNodeWrapper = from_str(concat!(
+            "name: db\nreplicas: 1\noffset: 0\nbig: 9223372036854775807\nratio: -2.5\nenabled: false\nowner: ops\n",
+            "limits: {cpu: {max: 0.5, min: 0.25}, memory: {}}\n",
+            "stages: [[migrate]]\n",
+            "mode: Off\n",
+        )).unwrap();
+        let config: Config = value.deserialize_into().unwrap();
+        assert_eq!(Some(String::from("ops")), config.owner);
+        assert_eq!(Mode::Off, config.mode);
+        assert_eq!(value, to_value(&config).unwrap());
+
+        // Yaml::Integer can't hold it
+        assert!(from_str::<YamlNodeWrapper>("big: 18446744073709551615").is_err());
+    }
+
+    #[test]
+    fn scalars_into_strings() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Release {
+            version: String,
+            ratio: String,
+            stable: String,
+        }
+
+        let yaml = "version: 1\nratio: 0.5\nstable: true";
+        let expected = Release { version: String::from("1"), ratio: String::from("0.5"), stable: String::from("true") };
+        assert_eq!(expected, from_str::<Release>(yaml).unwrap());
+        assert_eq!(expected, from_str::<YamlNodeWrapper>(yaml).unwrap().deserialize_into::<Release>().unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        let node: YamlNodeWrapper = from_str("name: box\ncount: 300\nshapes: []\nlabels: {}\nextra: ~").unwrap();
+        assert!(node.deserialize_into::<Item>().is_err());
+
+        let node: YamlNodeWrapper = from_str("[Empty: 1, {Circle: 1, Line: [1, 2]}]").unwrap();
+        assert!(node.get_index(0).map(Shape::deserialize).unwrap().is_err());
+        assert!(node.get_index(1).map(Shape::deserialize).unwrap().is_err());
+
+        let node: YamlNodeWrapper = from_str("[1, 2, 3]").unwrap();
+        assert!(node.deserialize_into::<(i32, i32)>().is_err());
+        assert!(node.deserialize_into::<Shape>().is_err());
+    }
+}
@@ -6,6 +6,23 @@ use yaml_rust2::Yaml;
 use yaml_rust2::yaml::Hash;
 use serde::ser::Error as _;
 
+mod de;
+
+pub use de::from_value;
+
+/// Any YAML node, e.g. a field whose structure isn't known in advance. The accessors borrow the node, those of
+/// children return a [`YamlRef`] to them.
+///
+/// ```
+/// use serde_yaml2::wrapper::YamlNodeWrapper;
+///
+/// let node: YamlNodeWrapper = serde_yaml2::from_str("name: web\nports: [80, 443]\nratio: 0.5").unwrap();
+///
+/// assert_eq!(Some("web"), node.get_key("name").and_then(|name| name.as_str()));
+/// assert_eq!(Some(443), node.get_key("ports").and_then(|ports| ports.get_index(1)).and_then(|port| port.as_i64()));
+/// assert_eq!(Some(0.5), node.get_key("ratio").and_then(|ratio| ratio.as_f64()));
+/// assert!(node.get_key("missing").is_none());
+/// ```
 #[derive(Debug, Eq, PartialEq, Hash)]
 pub struct YamlNodeWrapper(Yaml);
 
@@ -14,9 +31,120 @@ impl YamlNodeWrapper {
         Self(value)
     }
 
+    pub fn into_inner(self) -> Yaml {
+        self.0
+    }
+
+    #[deprecated(note = "renamed to `into_inner`")]
     pub fn get(self) -> Yaml {
+        self.into_inner()
+    }
+
+    /// Borrows the node
+    pub fn as_yaml_ref(&self) -> YamlRef<'_> {
+        YamlRef(&self.0)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_yaml_ref().as_str()
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_yaml_ref().as_i64()
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_yaml_ref().as_f64()
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        self.as_yaml_ref().as_bool()
+    }
+
+    pub fn as_vec(&self) -> Option<&Vec<Yaml>> {
+        self.as_yaml_ref().as_vec()
+    }
+
+    pub fn as_hash(&self) -> Option<&Hash> {
+        self.as_yaml_ref().as_hash()
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.as_yaml_ref().is_null()
+    }
+
+    /// The value of the entry with the string key, if the node is a mapping
+    pub fn get_key(&self, key: &str) -> Option<YamlRef<'_>> {
+        self.as_yaml_ref().get_key(key)
+    }
+
+    /// The element at the index, if the node is a sequence
+    pub fn get_index(&self, index: usize) -> Option<YamlRef<'_>> {
+        self.as_yaml_ref().get_index(index)
+    }
+
+    /// Deserializes the node into a value of another type, e.g. once another field told which type it holds. A
+    /// borrowed node, e.g. a [`YamlRef`] returned by [`get_key`](Self::get_key), is a `Deserializer` itself.
+    pub fn deserialize_into<'de, T: Deserialize<'de>>(&'de self) -> Result<T, serde::de::value::Error> {
+        T::deserialize(self)
+    }
+}
+
+/// A borrowed node of a [`YamlNodeWrapper`], with the same accessors
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct YamlRef<'a>(&'a Yaml);
+
+impl<'a> YamlRef<'a> {
+    pub fn new(value: &'a Yaml) -> Self {
+        Self(value)
+    }
+
+    pub fn yaml(self) -> &'a Yaml {
         self.0
     }
+
+    pub fn as_str(self) -> Option<&'a str> {
+        self.0.as_str()
+    }
+
+    pub fn as_i64(self) -> Option<i64> {
+        self.0.as_i64()
+    }
+
+    /// The value of a float, or of an integer converted to a float
+    pub fn as_f64(self) -> Option<f64> {
+        match self.0 {
+            Yaml::Integer(v) => Some(*v as f64),
+            node => node.as_f64(),
+        }
+    }
+
+    pub fn as_bool(self) -> Option<bool> {
+        self.0.as_bool()
+    }
+
+    pub fn as_vec(self) -> Option<&'a Vec<Yaml>> {
+        self.0.as_vec()
+    }
+
+    pub fn as_hash(self) -> Option<&'a Hash> {
+        self.0.as_hash()
+    }
+
+    pub fn is_null(self) -> bool {
+        self.0.is_null()
+    }
+
+    /// The value of the entry with the string key, if the node is a mapping. The entries are searched in order,
+    /// as looking the key up would need it as an owned `Yaml::String`.
+    pub fn get_key(self, key: &str) -> Option<YamlRef<'a>> {
+        self.0.as_hash()?.iter().find(|(entry, _)| entry.as_str() == Some(key)).map(|(_, value)| YamlRef(value))
+    }
+
+    /// The element at the index, if the node is a sequence
+    pub fn get_index(self, index: usize) -> Option<YamlRef<'a>> {
+        self.0.as_vec()?.get(index).map(YamlRef)
+    }
 }
 
 struct YamlNodeWrapperVisitor {}
@@ -61,7 +189,9 @@ impl<'de> Visitor<'de> for YamlNodeWrapperVisitor {
     }
 
     fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> where E: Error {
-        Ok(Yaml::Integer(v as i64))
+        i64::try_from(v)
+            .map(Yaml::Integer)
+            .map_err(|_| E::custom(format!("Can not store {v}, Yaml::Integer is 64-bit")))
     }
 
     fn visit_f32<E>(self, v: f32) -> Result<Self::Value, E> where E: Error {
@@ -88,11 +218,15 @@ impl<'de> Visitor<'de> for YamlNodeWrapperVisitor {
         Ok(Yaml::Null)
     }
 
+    fn visit_unit<E>(self) -> Result<Self::Value, E> where E: Error {
+        Ok(Yaml::Null)
+    }
+
     fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error> where A: SeqAccess<'de> {
         let mut result: Vec<Yaml> = vec![];
 
-        while let Ok(Some(el)) = seq.next_element::<YamlNodeWrapper>() {
-            result.push(el.get())
+        while let Some(el) = seq.next_element::<YamlNodeWrapper>()? {
+            result.push(el.into_inner())
         }
 
         Ok(Yaml::Array(result))
@@ -101,8 +235,8 @@ impl<'de> Visitor<'de> for YamlNodeWrapperVisitor {
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error> where A: MapAccess<'de> {
         let mut result = Hash::new();
 
-        while let Ok(Some((k,v))) = map.next_entry::<YamlNodeWrapper, YamlNodeWrapper>() {
-            result.insert(k.get(), v.get());
+        while let Some((k,v)) = map.next_entry::<YamlNodeWrapper, YamlNodeWrapper>()? {
+            result.insert(k.into_inner(), v.into_inner());
         }
 
         Ok(Yaml::Hash(result))
@@ -155,9 +289,86 @@ impl Serialize for YamlNodeWrapper {
             Yaml::Null => {
                 serializer.serialize_unit()
             },
-            Yaml::Alias(_) | Yaml::BadValue => {
-                unimplemented!()
+            // the loader resolves aliases, the id of one left unresolved names no anchor to write
+            Yaml::Alias(id) => {
+                Err(S::Error::custom(format!("Can not serialize unresolved alias with id {id}")))
+            },
+            Yaml::BadValue => {
+                Err(S::Error::custom("Can not serialize a bad value, which stands for a missing node"))
             },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use yaml_rust2::yaml::Hash;
+    use yaml_rust2::Yaml;
+    use crate::{from_str, to_string};
+    use super::YamlNodeWrapper;
+
+    #[derive(Deserialize, Debug)]
+    struct Config {
+        data: YamlNodeWrapper,
+    }
+
+    #[test]
+    fn errors_are_propagated() {
+        let config: Config = from_str("data: {a: [1, 2], b: !!int 3}").unwrap();
+        assert!(matches!(config.data.into_inner(), Yaml::Hash(hash) if hash.len() == 2));
+
+        for input in ["data: [1, !!int abc, 3]", "data: {a: 1, b: !!float x}", "data: [1, *missing]", "data: {a: {b: [1, !!bool maybe]}}", "data: {a: 1, b: ]}"] {
+            assert!(from_str::<Config>(input).is_err(), "{input}");
+        }
+
+        let error = from_str::<Config>("data: [1, !!int abc, 3]").unwrap_err().to_string();
+        assert!(error.contains("abc"), "{error}");
+    }
+
+    #[test]
+    fn unrepresentable_nodes() {
+        let error = to_string(YamlNodeWrapper::new(Yaml::BadValue)).unwrap_err().to_string();
+        assert_eq!("Can not serialize a bad value, which stands for a missing node", error);
+
+        let nested = Yaml::Hash(Hash::from_iter([(Yaml::String(String::from("a")), Yaml::Array(vec![Yaml::Integer(1), Yaml::Alias(3)]))]));
+        let error = to_string(YamlNodeWrapper::new(nested)).unwrap_err().to_string();
+        assert_eq!("Can not serialize unresolved alias with id 3", error);
+    }
+
+    #[test]
+    fn accessors() {
+        let node: YamlNodeWrapper = from_str("name: web\nreplicas: 3\nratio: 0.25\nenabled: true\nports: [80, 443]\nlabels: {app: web}\nempty: ~").unwrap();
+
+        assert_eq!(Some("web"), node.get_key("name").unwrap().as_str());
+        assert_eq!(Some(3), node.get_key("replicas").unwrap().as_i64());
+        assert_eq!(Some(3.0), node.get_key("replicas").unwrap().as_f64());
+        assert_eq!(Some(0.25), node.get_key("ratio").unwrap().as_f64());
+        assert_eq!(Some(true), node.get_key("enabled").unwrap().as_bool());
+        assert_eq!(Some(&vec![Yaml::Integer(80), Yaml::Integer(443)]), node.get_key("ports").unwrap().as_vec());
+        assert_eq!(Some(80), node.get_key("ports").unwrap().get_index(0).unwrap().as_i64());
+        assert!(node.get_key("ports").unwrap().get_index(2).is_none());
+        assert_eq!(1, node.get_key("labels").unwrap().as_hash().unwrap().len());
+        assert_eq!(Some("web"), node.get_key("labels").unwrap().get_key("app").unwrap().as_str());
+        assert!(node.get_key("empty").unwrap().is_null());
+
+        // accessors of another kind of node have nothing to return
+        assert!(node.get_key("missing").is_none());
+        assert!(node.get_index(0).is_none());
+        assert!(node.get_key("name").unwrap().get_key("name").is_none());
+        assert_eq!(None, node.get_key("name").unwrap().as_i64());
+        assert_eq!(None, node.get_key("replicas").unwrap().as_str());
+        assert_eq!(None, node.as_vec());
+        assert!(!node.is_null());
+        assert_eq!(7, node.as_hash().unwrap().len());
+
+        let node: YamlNodeWrapper = from_str("[a, [1, 2]]").unwrap();
+        assert_eq!(Some("a"), node.get_index(0).and_then(|v| v.as_str()));
+        assert_eq!(Some(&Yaml::Integer(2)), node.get_index(1).and_then(|v| v.get_index(1)).map(|v| v.yaml()));
+        assert_eq!(None, node.as_str());
+
+        #[allow(deprecated)]
+        let yaml = node.get();
+        assert!(matches!(yaml, Yaml::Array(items) if items.len() == 2));
+    }
+}
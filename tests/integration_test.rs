@@ -3,6 +3,7 @@ use serde_yaml2::{from_str, to_string};
 
 #[test]
 fn deserialize() {
+    #[allow(dead_code)]
     #[derive(Deserialize, Debug, PartialEq)]
     enum TestEnum {
         VariantA,
@@ -27,7 +28,7 @@ fn deserialize() {
     }, result);
 
     let result = to_string(result).unwrap();
-    assert_eq!("'x':\n  -41\n'y':\n  'Hello world'\n'z':\n  - \n   1\n  - \n   2\n  - \n   3\n  \n", result);
+    assert_eq!("x: -41\ny: Hello world\nz:\n  - 1\n  - 2\n  - 3\n", result);
 
     assert_eq!(TestStruct {
         x: -41,
@@ -35,3 +36,26 @@ fn deserialize() {
         z: vec![1,2,3],
     }, from_str(&result).unwrap());
 }
+
+#[test]
+fn number_errors() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct TestStruct {
+        name: String,
+        count: u8,
+    }
+
+    assert_eq!(
+        "Error while parsing scalar 300 into u8 at position Line: 2, Column: 8, Index: 18. Number out of range",
+        from_str::<TestStruct>("name: test\ncount: 300\n").unwrap_err().to_string(),
+    );
+    assert_eq!(
+        "Error while parsing scalar -1 into u8 at position Line: 2, Column: 8, Index: 18. Number out of range",
+        from_str::<TestStruct>("name: test\ncount: -1\n").unwrap_err().to_string(),
+    );
+    assert_eq!(
+        "Error while parsing scalar many into u8 at position Line: 2, Column: 8, Index: 18. Not a number",
+        from_str::<TestStruct>("name: test\ncount: many\n").unwrap_err().to_string(),
+    );
+}